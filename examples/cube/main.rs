@@ -1,36 +1,38 @@
 use std::error::Error;
+use cgmath::Rotation3;
 use winit::{
     dpi::PhysicalSize, 
-    event::{Event, VirtualKeyCode, WindowEvent}, 
+    event::{DeviceEvent, Event, VirtualKeyCode, WindowEvent}, 
     event_loop::{ControlFlow, EventLoop}, 
     window::WindowBuilder
 };
-use wgpu::util::DeviceExt;
 
 use webgpu::{
-    inputs::Keyboard, 
+    camera::{Camera, CameraUniforms, OrbitController},
+    inputs::{Keyboard, Mouse},
+    render::{
+        buffer::{IndexBuffer, UniformBuffer, VertexBuffer},
+        BlendMode, GpuConfiguration, GpuContext, PipelineBuilder, ShaderLoader, ShaderReflection, Surface, DEFAULT_DEPTH_FORMAT, VertexLayout,
+    },
+    transforms::Transform,
+    window::FrameTime,
 };
 
 #[repr(C)]
-#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable, VertexLayout)]
 struct Vertex {
     position: [f32; 3],
 }
 
 #[repr(C)]
-#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable, VertexLayout)]
+#[step_mode(Instance)]
 struct Instance {
+    #[location(1)]
     model_matrix: [[f32; 4]; 4],
     color: [f32; 3],
 }
 
-#[repr(C)]
-#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
-struct Uniforms {
-    view: [[f32; 4]; 4],
-    projection: [[f32; 4]; 4],
-}
-
 #[async_std::main]
 async fn main() -> Result<(), Box<dyn Error>> {
     env_logger::init();
@@ -47,26 +49,19 @@ async fn main() -> Result<(), Box<dyn Error>> {
     let size = window.inner_size();
 
     let mut keyboard = Keyboard::new();
+    let mut mouse = Mouse::new();
 
-    let (surface, adapter) = create_surface_and_adapter(&window).await?;
-    let (device, queue) = create_device_and_queue(&adapter).await?;
+    let (gpu, surface) = GpuContext::with_surface(&window, &GpuConfiguration::new()).await?;
+    let GpuContext { adapter, device, queue, .. } = gpu;
 
-    let swap_chain_texture_format = adapter.get_swap_chain_preferred_format(&surface).unwrap();
-    let mut swap_chain_descriptor = wgpu::SwapChainDescriptor {
-        usage: wgpu::TextureUsage::RENDER_ATTACHMENT,
-        format: swap_chain_texture_format,
-        width: size.width,
-        height: size.height,
-        present_mode: wgpu::PresentMode::Fifo,
-    };
-    let mut swap_chain = device.create_swap_chain(&surface, &swap_chain_descriptor);
+    let mut surface = Surface::new(&adapter, &device, surface, size)?
+        .with_depth_buffer(&device, DEFAULT_DEPTH_FORMAT);
 
-    let shader_module_descriptor = wgpu::ShaderModuleDescriptor {
-        label: Some("Shader"),
-        flags: wgpu::ShaderFlags::all(),
-        source: wgpu::ShaderSource::Wgsl(include_str!("shader.wgsl").into()),
-    };
-    let shader_module = device.create_shader_module(&shader_module_descriptor);
+    let shader_loader = ShaderLoader::new()
+        .with_source("common.wgsl", include_str!("../shaders/common.wgsl"))
+        .with_source("shader.wgsl", include_str!("shader.wgsl"));
+    let shader_module = shader_loader.create_shader_module(&device, "shader.wgsl")?;
+    let shader_reflection = ShaderReflection::new(&shader_loader.preprocess("shader.wgsl")?.parse()?)?;
 
     let verticies = vec![
         Vertex { position: [ 1.0,  1.0, -1.0] },
@@ -78,23 +73,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
         Vertex { position: [-1.0,  1.0,  1.0] },
         Vertex { position: [-1.0, -1.0,  1.0] },
     ];
-    let vertex_buffer_descriptor = wgpu::util::BufferInitDescriptor {
-        label: Some("Vertex Buffer"),
-        contents: bytemuck::cast_slice(&verticies),
-        usage: wgpu::BufferUsage::VERTEX,
-    };
-    let vertex_buffer = device.create_buffer_init(&vertex_buffer_descriptor);
-    let vertex_buffer_layout = wgpu::VertexBufferLayout {
-        array_stride: std::mem::size_of::<Vertex>() as wgpu::BufferAddress, 
-        step_mode: wgpu::InputStepMode::Vertex, 
-        attributes: &[ 
-            wgpu::VertexAttribute {
-                offset: 0, 
-                shader_location: 0, 
-                format: wgpu::VertexFormat::Float32x3,
-            },
-        ],
-    };
+    let vertex_buffer = VertexBuffer::new(&device, Some("Vertex Buffer"), &verticies);
 
     let indices: Vec<u16> = vec![
         4, 2, 0,
@@ -110,53 +89,16 @@ async fn main() -> Result<(), Box<dyn Error>> {
         0, 2, 3,
         4, 0, 1,
     ];
-    let index_buffer_descriptor = wgpu::util::BufferInitDescriptor {
-        label: Some("Index Buffer"),
-        contents: bytemuck::cast_slice(&indices),
-        usage: wgpu::BufferUsage::INDEX,
-    };
-    let index_buffer = device.create_buffer_init(&index_buffer_descriptor);
-    let num_indices = indices.len() as u32;
-
-    let eye = cgmath::Point3::<f32>::new(0.0, 2.0, 5.0);
-    let target = cgmath::Point3::<f32>::new(0.0, 0.0, 0.0);
-    let up = cgmath::Vector3::unit_y();
-    let view_matrix = cgmath::Matrix4::look_at_rh(eye, target, up);
-
-    let vertical_fov = cgmath::Deg(45.0);
-    let aspect_ratio = size.width as f32 / size.height as f32;
-    let near = 0.1;
-    let far = 100.0;
-    let projection_matrix = cgmath::perspective(vertical_fov, aspect_ratio, near, far);
+    let index_buffer = IndexBuffer::new(&device, Some("Index Buffer"), &indices);
 
-    let uniforms = Uniforms {
-        view: view_matrix.into(),
-        projection: projection_matrix.into(),
-    };
-    let uniform_data = vec![uniforms];
-    let uniform_buffer_descriptor = wgpu::util::BufferInitDescriptor {
-        label: Some("Uniform Buffer"),
-        contents: bytemuck::cast_slice(&uniform_data),
-        usage: wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
-    };
-    let uniform_buffer = device.create_buffer_init(&uniform_buffer_descriptor);
+    let eye = cgmath::Point3::new(0.0, 2.0, 5.0);
+    let target = cgmath::Point3::new(0.0, 0.0, 0.0);
+    let mut camera = Camera::perspective(cgmath::Deg(45.0), size.width as f32 / size.height as f32, 0.1, Some(100.0))
+        .with_look_at(eye, target, cgmath::Vector3::unit_y());
+    let mut camera_controller = OrbitController::new(&camera, target);
+    let uniform_buffer = UniformBuffer::new(&device, Some("Uniform Buffer"), &camera.uniforms());
 
-    let uniform_bind_group_layout_descriptor = wgpu::BindGroupLayoutDescriptor {
-        entries: &[
-            wgpu::BindGroupLayoutEntry {
-                binding: 0,
-                visibility: wgpu::ShaderStage::VERTEX,
-                ty: wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Uniform,
-                    has_dynamic_offset: false,
-                    min_binding_size: None,
-                },
-                count: None,
-            }
-        ],
-        label: Some("uniform_bind_group_layout"),
-    };
-    let uniform_bind_group_layout = device.create_bind_group_layout(&uniform_bind_group_layout_descriptor);
+    let uniform_bind_group_layout = shader_reflection.create_bind_group_layout(&device, 0, Some("uniform_bind_group_layout"));
     
     let uniform_bind_group_descriptor = wgpu::BindGroupDescriptor {
         layout: &uniform_bind_group_layout,
@@ -170,76 +112,33 @@ async fn main() -> Result<(), Box<dyn Error>> {
     };
     let uniform_bind_group = device.create_bind_group(&uniform_bind_group_descriptor);
 
-    let model_position1 = cgmath::Vector3::new(0.0, 0.0, 0.0);
-    let model_position2 = cgmath::Vector3::new(0.0, 0.0, -5.0);
-    let model_position3 = cgmath::Vector3::new(5.0, 0.0, -5.0);
-    let model_position4 = cgmath::Vector3::new(-5.0, 0.0, -5.0);
-
-    let model_rotation1 = cgmath::Vector3::new(0.0, 0.0, 0.0);
-    let model_rotation2 = cgmath::Vector3::new(0.0, 180.0, 0.0);
-    let model_rotation3 = cgmath::Vector3::new(0.0, -90.0, 0.0);
-    let model_rotation4 = cgmath::Vector3::new(0.0, 90.0, 0.0);
+    let model_transforms = [
+        Transform::from_translation(cgmath::vec3(0.0, 0.0, 0.0)),
+        Transform::from_translation(cgmath::vec3(0.0, 0.0, -5.0)).with_rotation(cgmath::Quaternion::from_angle_y(cgmath::Deg(180.0))),
+        Transform::from_translation(cgmath::vec3(5.0, 0.0, -5.0)).with_rotation(cgmath::Quaternion::from_angle_y(cgmath::Deg(-90.0))),
+        Transform::from_translation(cgmath::vec3(-5.0, 0.0, -5.0)).with_rotation(cgmath::Quaternion::from_angle_y(cgmath::Deg(90.0))),
+    ];
 
     let instances = vec![
         Instance { 
-            model_matrix: model_matrix_from_position_and_rotation(model_position1, model_rotation1).into(), 
+            model_matrix: model_transforms[0].matrix().into(), 
             color: [1.0, 0.0, 0.0],
         },
         Instance { 
-            model_matrix: model_matrix_from_position_and_rotation(model_position2, model_rotation2).into(), 
+            model_matrix: model_transforms[1].matrix().into(), 
             color: [0.0, 1.0, 0.0],
         },
         Instance { 
-            model_matrix: model_matrix_from_position_and_rotation(model_position3, model_rotation3).into(), 
+            model_matrix: model_transforms[2].matrix().into(), 
             color: [0.0, 0.0, 1.0],
         },
         Instance { 
-            model_matrix: model_matrix_from_position_and_rotation(model_position4, model_rotation4).into(), 
+            model_matrix: model_transforms[3].matrix().into(), 
             color: [1.0, 0.0, 1.0],
         },
     ];
-    let num_instances = instances.len() as u32;
 
-    let instance_buffer_descriptor = wgpu::util::BufferInitDescriptor {
-        label: Some("Instance Buffer"),
-        contents: bytemuck::cast_slice(&instances),
-        usage: wgpu::BufferUsage::VERTEX,
-    };
-    let instance_buffer = device.create_buffer_init(&instance_buffer_descriptor);
-    let instance_buffer_layout = wgpu::VertexBufferLayout {
-        array_stride: std::mem::size_of::<Instance>() as wgpu::BufferAddress,
-        step_mode: wgpu::InputStepMode::Instance,
-        attributes: &[
-            // A mat4 takes up 4 vertex slots as it is technically 4 vec4s. We need to define a slot
-            // for each vec4. We'll have to reassemble the mat4 in
-            // the shader.
-            wgpu::VertexAttribute {
-                offset: 0,
-                shader_location: 1,
-                format: wgpu::VertexFormat::Float32x4,
-            },
-            wgpu::VertexAttribute {
-                offset: std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
-                shader_location: 2,
-                format: wgpu::VertexFormat::Float32x4,
-            },
-            wgpu::VertexAttribute {
-                offset: std::mem::size_of::<[f32; 8]>() as wgpu::BufferAddress,
-                shader_location: 3,
-                format: wgpu::VertexFormat::Float32x4,
-            },
-            wgpu::VertexAttribute {
-                offset: std::mem::size_of::<[f32; 12]>() as wgpu::BufferAddress,
-                shader_location: 4,
-                format: wgpu::VertexFormat::Float32x4,
-            },
-            wgpu::VertexAttribute {
-                offset: std::mem::size_of::<[f32; 16]>() as wgpu::BufferAddress,
-                shader_location: 5,
-                format: wgpu::VertexFormat::Float32x3,
-            },
-        ],
-    };
+    let instance_buffer = VertexBuffer::new(&device, Some("Instance Buffer"), &instances);
 
     let pipeline_layout_descriptor = wgpu::PipelineLayoutDescriptor {
         label: Some("Render Pipeline Layout"),
@@ -250,69 +149,15 @@ async fn main() -> Result<(), Box<dyn Error>> {
     };
     let render_pipeline_layout = device.create_pipeline_layout(&pipeline_layout_descriptor);
 
-    let vertex_state = wgpu::VertexState {
-        module: &shader_module,
-        entry_point: "main",
-        buffers: &[
-            vertex_buffer_layout,
-            instance_buffer_layout,
-        ],
-    };
-    let fragment_state = wgpu::FragmentState {
-        module: &shader_module,
-        entry_point: "main",
-        targets: &[wgpu::ColorTargetState {
-            format: swap_chain_descriptor.format,
-            blend: Some(wgpu::BlendState::REPLACE),
-            write_mask: wgpu::ColorWrite::ALL,
-        }],
-    };
-    let render_pipeline_descriptor = wgpu::RenderPipelineDescriptor {
-        label: Some("Render Pipeline"),
-        layout: Some(&render_pipeline_layout),
-        vertex: vertex_state,
-        fragment: Some(fragment_state),
-        primitive: wgpu::PrimitiveState {
-            topology: wgpu::PrimitiveTopology::TriangleList,
-            strip_index_format: None,
-            front_face: wgpu::FrontFace::Ccw,
-            cull_mode: Some(wgpu::Face::Back),
-            polygon_mode: wgpu::PolygonMode::Fill,
-            clamp_depth: false,
-            conservative: false,
-        },
-        depth_stencil: Some(wgpu::DepthStencilState {
-            format: wgpu::TextureFormat::Depth32Float,
-            depth_write_enabled: true,
-            depth_compare: wgpu::CompareFunction::Less,
-            stencil: wgpu::StencilState::default(),
-            bias: wgpu::DepthBiasState::default(),
-        }),
-        multisample: wgpu::MultisampleState {
-            count: 1,
-            mask: !0,
-            alpha_to_coverage_enabled: false,
-        },
-    };
-    let render_pipeline = device.create_render_pipeline(&render_pipeline_descriptor);
-
-    let size = wgpu::Extent3d {
-        width: size.width,
-        height: size.height,
-        depth_or_array_layers: 1,
-    };
-    let mut depth_texture_descriptor = wgpu::TextureDescriptor {
-        label: Some("depth texture"),
-        size,
-        mip_level_count: 1,
-        sample_count: 1,
-        dimension: wgpu::TextureDimension::D2,
-        format: wgpu::TextureFormat::Depth32Float,
-        usage: wgpu::TextureUsage::RENDER_ATTACHMENT | wgpu::TextureUsage::SAMPLED,
-    };
-    let depth_texture = device.create_texture(&depth_texture_descriptor);
+    let render_pipeline = PipelineBuilder::new()
+        .with_label("Render Pipeline")
+        .with_vertex_buffer(Vertex::buffer_layout())
+        .with_vertex_buffer(Instance::buffer_layout())
+        .with_uniform::<CameraUniforms>(0, 0)
+        .with_color_target(surface.format(), BlendMode::Replace)
+        .build_checked(&device, &render_pipeline_layout, &shader_module, &shader_reflection)?;
 
-    let mut depth_texture_view = depth_texture.create_view(&wgpu::TextureViewDescriptor::default());
+    let mut frame_time = FrameTime::new(None);
 
     event_loop.run(move |event, _, control_flow| {
         let mut update = || {
@@ -320,11 +165,11 @@ async fn main() -> Result<(), Box<dyn Error>> {
                 *control_flow = ControlFlow::Exit;
             }
 
-            if keyboard.is_key_pressed(VirtualKeyCode::F1) {
+            if keyboard.just_pressed(VirtualKeyCode::F1) {
                 window.set_fullscreen(Some(winit::window::Fullscreen::Borderless(None)));
             }
 
-            if keyboard.is_key_pressed(VirtualKeyCode::F2) {
+            if keyboard.just_pressed(VirtualKeyCode::F2) {
                 window.set_fullscreen(None);
             }
         };
@@ -340,43 +185,31 @@ async fn main() -> Result<(), Box<dyn Error>> {
                     WindowEvent::KeyboardInput { input, .. } => {
                         keyboard.handle_input(input);
                     },
+                    WindowEvent::MouseInput { state, button, .. } => {
+                        mouse.handle_input(*button, *state);
+                    },
+                    WindowEvent::CursorMoved { position, .. } => {
+                        mouse.handle_cursor_moved(*position);
+                    },
+                    WindowEvent::MouseWheel { delta, .. } => {
+                        mouse.handle_scroll(*delta);
+                    },
                     WindowEvent::Resized(physical_size) => {
-                        let size = *physical_size;
-                        swap_chain_descriptor.width = size.width;
-                        swap_chain_descriptor.height = size.height;
-                        swap_chain = device.create_swap_chain(&surface, &swap_chain_descriptor);
-
-                        depth_texture_descriptor.size.width = size.width;
-                        depth_texture_descriptor.size.height = size.height;
-                        let depth_texture = device.create_texture(&depth_texture_descriptor);
-                        depth_texture_view = depth_texture.create_view(&wgpu::TextureViewDescriptor::default());
+                        surface.resize(&device, *physical_size);
+                        camera.resize(*physical_size);
                     },
                     WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
-                        let size = **new_inner_size;
-                        swap_chain_descriptor.width = size.width;
-                        swap_chain_descriptor.height = size.height;
-                        swap_chain = device.create_swap_chain(&surface, &swap_chain_descriptor);
-
-                        depth_texture_descriptor.size.width = size.width;
-                        depth_texture_descriptor.size.height = size.height;
-                        let depth_texture = device.create_texture(&depth_texture_descriptor);
-                        depth_texture_view = depth_texture.create_view(&wgpu::TextureViewDescriptor::default());
+                        surface.resize(&device, **new_inner_size);
+                        camera.resize(**new_inner_size);
                     },
                     _ => {},
                 }
             },
             Event::RedrawRequested(_) => {
-                let frame = match swap_chain.get_current_frame() {
-                    Ok(frame) => frame,
-                    Err(wgpu::SwapChainError::Lost) => {
-                        swap_chain = device.create_swap_chain(&surface, &swap_chain_descriptor);
-                        swap_chain.get_current_frame().unwrap()
-                    },
-                    Err(wgpu::SwapChainError::Outdated) => {
-                        swap_chain = device.create_swap_chain(&surface, &swap_chain_descriptor);
-                        swap_chain.get_current_frame().unwrap()
-                    },
-                    Err(_) => panic!("failed to get frame"),
+                let frame = match surface.acquire_frame(&device) {
+                    Ok(Some(frame)) => frame,
+                    Ok(None) => return,
+                    Err(error) => panic!("failed to get frame: {}", error),
                 };
 
                 let command_encoder_descriptor = wgpu::CommandEncoderDescriptor {
@@ -402,7 +235,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
                             }
                         ],
                         depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
-                            view: &depth_texture_view,
+                            view: surface.depth_view().unwrap(),
                             depth_ops: Some(wgpu::Operations {
                                 load: wgpu::LoadOp::Clear(1.0),
                                 store: true,
@@ -414,58 +247,32 @@ async fn main() -> Result<(), Box<dyn Error>> {
 
                     render_pass.set_pipeline(&render_pipeline);
                     render_pass.set_bind_group(0, &uniform_bind_group, &[]);
-                    render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
-                    render_pass.set_vertex_buffer(1, instance_buffer.slice(..));
-                    render_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint16);
-                    render_pass.draw_indexed(0..num_indices, 0, 0..num_instances);
+                    if let (Some(vertices), Some(instances), Some(indices)) = (vertex_buffer.slice(), instance_buffer.slice(), index_buffer.slice()) {
+                        render_pass.set_vertex_buffer(0, vertices);
+                        render_pass.set_vertex_buffer(1, instances);
+                        render_pass.set_index_buffer(indices, index_buffer.format());
+                        render_pass.draw_indexed(0..index_buffer.len() as u32, 0, 0..instance_buffer.len() as u32);
+                    }
                 }
 
                 let command_buffer = encoder.finish();
                 queue.submit(std::iter::once(command_buffer));
             },
+            Event::DeviceEvent { event: DeviceEvent::MouseMotion { delta }, .. } => {
+                mouse.handle_motion(delta);
+            },
             Event::MainEventsCleared => {
                 update();
+
+                let delta = frame_time.update();
+                camera_controller.update(&mut camera, &keyboard, &mouse, delta);
+                uniform_buffer.write(&queue, &camera.uniforms());
+
+                keyboard.end_frame();
+                mouse.end_frame();
                 window.request_redraw();
             },
             _ => {},
         }
     });
-}
-
-async fn create_surface_and_adapter(window: &winit::window::Window) -> Result<(wgpu::Surface, wgpu::Adapter), Box<dyn Error>> {
-    let instance = wgpu::Instance::new(wgpu::BackendBit::PRIMARY);
-    let surface = unsafe { instance.create_surface(window) };
-
-    let adapter_options = wgpu::RequestAdapterOptions {
-        power_preference: wgpu::PowerPreference::default(),
-        compatible_surface: Some(&surface),
-    };
-    let adapter = match instance.request_adapter(&adapter_options).await {
-        Some(adapter) => adapter,
-        None => return Err(Box::from("No adapter found")),
-    };
-
-    Ok((surface, adapter))
-}
-
-async fn create_device_and_queue(adapter: &wgpu::Adapter) -> Result<(wgpu::Device, wgpu::Queue), Box<dyn Error>> {
-    let device_descriptor = wgpu::DeviceDescriptor {
-        features: wgpu::Features::empty(),
-        limits: wgpu::Limits::default(),
-        label: None,
-    };
-    let trace_path = None;
-    let (device, queue) = match adapter.request_device(&device_descriptor, trace_path).await {
-        Ok((device, queue)) => (device, queue),
-        Err(_) => return Err(Box::from("Failed to create device and queue")),
-    };
-
-    Ok((device, queue))
-}
-
-fn model_matrix_from_position_and_rotation(position: cgmath::Vector3<f32>, rotation: cgmath::Vector3<f32>) -> cgmath::Matrix4<f32> {
-    let position_matrix = cgmath::Matrix4::from_translation(position);
-    let rotation_matrix = cgmath::Matrix4::from_angle_x(cgmath::Deg(rotation.x)) * cgmath::Matrix4::from_angle_y(cgmath::Deg(rotation.y)) * cgmath::Matrix4::from_angle_z(cgmath::Deg(rotation.z));
-
-    position_matrix * rotation_matrix
 }
\ No newline at end of file
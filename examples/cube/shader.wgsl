@@ -1,10 +1,4 @@
-[[block]]
-struct Uniforms {
-    view: mat4x4<f32>;
-    projection: mat4x4<f32>;
-};
-[[group(0), binding(0)]]
-var<uniform> uniforms: Uniforms;
+#include "common.wgsl"
 
 struct VertexInput {
     [[location(0)]] position: vec3<f32>;
@@ -24,14 +18,14 @@ struct VertexOutput {
 
 [[stage(vertex)]]
 fn main(vertex_input: VertexInput, instance_input: InstanceInput) -> VertexOutput {
-    let model_matrix = mat4x4<f32>(
+    let model_matrix = instance_model_matrix(
         instance_input.model_matrix_0,
         instance_input.model_matrix_1,
         instance_input.model_matrix_2,
-        instance_input.model_matrix_3,
+        instance_input.model_matrix_3
     );
 
-    let model_view_projection = uniforms.projection * uniforms.view * model_matrix;
+    let model_view_projection = uniforms.projection_matrix * uniforms.view_matrix * model_matrix;
 
     var output: VertexOutput;
     output.color = instance_input.color;
@@ -1,33 +1,38 @@
 mod model;
 
 use std::error::Error;
+use std::time::{Duration, Instant};
+use cgmath::Rotation3;
 use winit::{
     dpi::PhysicalSize, 
-    event::{Event, VirtualKeyCode, WindowEvent}, 
+    event::{DeviceEvent, Event, VirtualKeyCode, WindowEvent}, 
     event_loop::{ControlFlow, EventLoop}, 
     window::WindowBuilder
 };
-use wgpu::util::DeviceExt;
 
 use webgpu::{
-    inputs::Keyboard, 
+    camera::{Camera, CameraUniforms, FirstPersonController},
+    inputs::{Keyboard, Mouse},
+    render::{
+        buffer::{IndexBuffer, UniformBuffer, VertexBuffer},
+        BlendMode, ClearValue, GpuConfiguration, GpuContext, HotReloadPipeline, ImportedResources, PassBuilder, PipelineBuilder, RenderGraph,
+        ShaderLoader, ShaderReflection, Surface, TransientResources, TransientTexture, DEFAULT_DEPTH_FORMAT, GpuFeature, ShaderType, VertexLayout,
+    },
+    transforms::{Frustum, Transform},
+    window::FrameTime,
 };
 
-use crate::model::wavefront::{VertexBufferLayout, VertexRaw, Instance, InstanceRaw};
+use crate::model::wavefront::{CullingStatistics, VertexRaw, Instance, InstanceRaw};
 
 #[repr(C)]
-#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
-struct Uniforms {
-    view_matrix: [[f32; 4]; 4],
-    projection_matrix: [[f32; 4]; 4],
-}
-
-#[repr(C)]
-#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable, ShaderType)]
 struct Light {
     position: [f32; 3],
-    _padding: u32, // Due to uniforms requiring 16 byte (4 float) spacing, we need to use a padding field here
+    #[padding]
+    _padding: u32,
     color: [f32; 3],
+    #[padding]
+    _color_padding: u32,
 }
 
 #[async_std::main]
@@ -45,26 +50,22 @@ async fn main() -> Result<(), Box<dyn Error>> {
     let size = window.inner_size();
 
     let mut keyboard = Keyboard::new();
+    let mut mouse = Mouse::new();
 
-    let (surface, adapter) = create_surface_and_adapter(&window).await?;
-    let (device, queue) = create_device_and_queue(&adapter).await?;
+    let mut gpu_configuration = GpuConfiguration::new();
+    gpu_configuration.required_features.push(GpuFeature::NonFillPolygonMode);
 
-    let swap_chain_texture_format = adapter.get_swap_chain_preferred_format(&surface).unwrap();
-    let mut swap_chain_descriptor = wgpu::SwapChainDescriptor {
-        usage: wgpu::TextureUsage::RENDER_ATTACHMENT,
-        format: swap_chain_texture_format,
-        width: size.width,
-        height: size.height,
-        present_mode: wgpu::PresentMode::Fifo,
-    };
-    let mut swap_chain = device.create_swap_chain(&surface, &swap_chain_descriptor);
+    let (gpu, surface) = GpuContext::with_surface(&window, &gpu_configuration).await?;
+    let GpuContext { adapter, device, queue, .. } = gpu;
 
-    let shader_module_descriptor = wgpu::ShaderModuleDescriptor {
-        label: Some("Shader"),
-        flags: wgpu::ShaderFlags::all(),
-        source: wgpu::ShaderSource::Wgsl(include_str!("shader.wgsl").into()),
-    };
-    let shader_module = device.create_shader_module(&shader_module_descriptor);
+    let mut surface = Surface::new(&adapter, &device, surface, size)?;
+
+    // Shaders are read from disk so they can be edited while the example runs.
+    let shader_loader = ShaderLoader::new()
+        .with_search_path(concat!(env!("CARGO_MANIFEST_DIR"), "/examples/shaders"))
+        .with_define("SPECULAR");
+    let shader_path = concat!(env!("CARGO_MANIFEST_DIR"), "/examples/model/shader.wgsl");
+    let shader_reflection = ShaderReflection::new(&shader_loader.preprocess(shader_path)?.parse()?)?;
 
     let mut material = model::wavefront::parse_wavefront_material(include_str!("model.mtl").to_string())?;
     let (mut model, verticies, indices) = model::wavefront::parse_wavefront_object(include_str!("model.obj").to_string())?;
@@ -72,94 +73,42 @@ async fn main() -> Result<(), Box<dyn Error>> {
     material.ambient = cgmath::vec3(1.0, 0.0, 0.0);
     material.diffuse = cgmath::vec3(1.0, 0.0, 0.0);
     model.instances.push(Instance {
-        position: cgmath::vec3(0.0, 0.0, 0.0),
-        rotation: cgmath::vec3(0.0, 90.0, 0.0),
-        scale: cgmath::vec3(1.0, 1.0, 1.0),
+        transform: Transform::from_translation(cgmath::vec3(0.0, 0.0, 0.0)).with_rotation(cgmath::Quaternion::from_angle_y(cgmath::Deg(90.0))),
         material: material.clone(),
     });
 
     material.ambient = cgmath::vec3(0.0, 1.0, 0.0);
     material.diffuse = cgmath::vec3(0.0, 1.0, 0.0);
     model.instances.push(Instance {
-        position: cgmath::vec3(-5.0, 0.0, -5.0),
-        rotation: cgmath::vec3(0.0, -45.0, 0.0),
-        scale: cgmath::vec3(1.0, 1.0, 1.0),
+        transform: Transform::from_translation(cgmath::vec3(-5.0, 0.0, -5.0)).with_rotation(cgmath::Quaternion::from_angle_y(cgmath::Deg(-45.0))),
         material: material.clone(),
     });
 
     material.ambient = cgmath::vec3(0.0, 0.0, 1.0);
     material.diffuse = cgmath::vec3(0.0, 0.0, 1.0);
     model.instances.push(Instance {
-        position: cgmath::vec3(0.0, 0.0, -5.0),
-        rotation: cgmath::vec3(0.0, 270.0, 0.0),
-        scale: cgmath::vec3(1.0, 1.0, 1.0),
+        transform: Transform::from_translation(cgmath::vec3(0.0, 0.0, -5.0)).with_rotation(cgmath::Quaternion::from_angle_y(cgmath::Deg(270.0))),
         material: material.clone(),
     });
 
     material.ambient = cgmath::vec3(1.0, 0.0, 1.0);
     material.diffuse = cgmath::vec3(1.0, 0.0, 1.0);
     model.instances.push(Instance {
-        position: cgmath::vec3(5.0, 0.0, -5.0),
-        rotation: cgmath::vec3(0.0, 45.0, 0.0),
-        scale: cgmath::vec3(1.0, 1.0, 1.0),
+        transform: Transform::from_translation(cgmath::vec3(5.0, 0.0, -5.0)).with_rotation(cgmath::Quaternion::from_angle_y(cgmath::Deg(45.0))),
         material: material.clone(),
     });
 
-    let num_instances = model.instances.len() as u32;
+    let vertex_buffer = VertexBuffer::new(&device, Some("Model Vertex Buffer"), &verticies);
+    let index_buffer = IndexBuffer::new(&device, Some("Index Buffer"), &indices);
 
-    let vertex_buffer_descriptor = wgpu::util::BufferInitDescriptor {
-        label: Some("Model Vertex Buffer"),
-        contents: bytemuck::cast_slice(&verticies),
-        usage: wgpu::BufferUsage::VERTEX,
-    };
-    let vertex_buffer = device.create_buffer_init(&vertex_buffer_descriptor);
+    let eye = cgmath::Point3::new(0.0, 10.0, 5.0);
+    let target = cgmath::Point3::new(0.0, 0.0, -3.0);
+    let mut camera = Camera::perspective(cgmath::Deg(45.0), size.width as f32 / size.height as f32, 0.1, Some(100.0))
+        .with_look_at(eye, target, cgmath::Vector3::unit_y());
+    let mut camera_controller = FirstPersonController::new(&camera);
+    let uniform_buffer = UniformBuffer::new(&device, Some("Uniform Buffer"), &camera.uniforms());
 
-    let index_buffer_descriptor = wgpu::util::BufferInitDescriptor {
-        label: Some("Index Buffer"),
-        contents: bytemuck::cast_slice(&indices),
-        usage: wgpu::BufferUsage::INDEX,
-    };
-    let index_buffer = device.create_buffer_init(&index_buffer_descriptor);
-
-    let eye = cgmath::Point3::<f32>::new(0.0, 10.0, 5.0);
-    let target = cgmath::Point3::<f32>::new(0.0, 0.0, -3.0);
-    let up = cgmath::Vector3::unit_y();
-    let view_matrix = cgmath::Matrix4::look_at_rh(eye, target, up);
-
-    let vertical_fov = cgmath::Deg(45.0);
-    let aspect_ratio = size.width as f32 / size.height as f32;
-    let near = 0.1;
-    let far = 100.0;
-    let projection_matrix = cgmath::perspective(vertical_fov, aspect_ratio, near, far);
-
-    let uniforms = Uniforms {
-        view_matrix: view_matrix.into(),
-        projection_matrix: projection_matrix.into(),
-    };
-    let uniform_data = vec![uniforms];
-    let uniform_buffer_descriptor = wgpu::util::BufferInitDescriptor {
-        label: Some("Uniform Buffer"),
-        contents: bytemuck::cast_slice(&uniform_data),
-        usage: wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
-    };
-    let uniform_buffer = device.create_buffer_init(&uniform_buffer_descriptor);
-
-    let uniform_bind_group_layout_descriptor = wgpu::BindGroupLayoutDescriptor {
-        entries: &[
-            wgpu::BindGroupLayoutEntry {
-                binding: 0,
-                visibility: wgpu::ShaderStage::VERTEX,
-                ty: wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Uniform,
-                    has_dynamic_offset: false,
-                    min_binding_size: None,
-                },
-                count: None,
-            }
-        ],
-        label: Some("uniform_bind_group_layout"),
-    };
-    let uniform_bind_group_layout = device.create_bind_group_layout(&uniform_bind_group_layout_descriptor);
+    let uniform_bind_group_layout = shader_reflection.create_bind_group_layout(&device, 0, Some("uniform_bind_group_layout"));
     
     let uniform_bind_group_descriptor = wgpu::BindGroupDescriptor {
         layout: &uniform_bind_group_layout,
@@ -177,28 +126,11 @@ async fn main() -> Result<(), Box<dyn Error>> {
         position: [0.0, 5.0, -2.5],
         _padding: 0,
         color: [1.0, 1.0, 1.0],
+        _color_padding: 0,
     };
-    let light_buffer = device.create_buffer_init(
-        &wgpu::util::BufferInitDescriptor {
-            label: Some("Light UB"),
-            contents: bytemuck::cast_slice(&[light]),
-            usage: wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
-        }
-    );
+    let light_buffer = UniformBuffer::new(&device, Some("Light UB"), &light);
 
-    let light_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-        entries: &[wgpu::BindGroupLayoutEntry {
-            binding: 0,
-            visibility: wgpu::ShaderStage::VERTEX | wgpu::ShaderStage::FRAGMENT,
-            ty: wgpu::BindingType::Buffer {
-                ty: wgpu::BufferBindingType::Uniform,
-                has_dynamic_offset: false,
-                min_binding_size: None,
-            },
-            count: None,
-        }],
-        label: None,
-    });
+    let light_bind_group_layout = shader_reflection.create_bind_group_layout(&device, 1, None);
 
     let light_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
         layout: &light_bind_group_layout,
@@ -210,13 +142,10 @@ async fn main() -> Result<(), Box<dyn Error>> {
     });
 
 
-    let instances: Vec<InstanceRaw> = model.instances.iter().map(|instance| instance.to_instance_raw()).collect();
-    let instance_buffer_descriptor = wgpu::util::BufferInitDescriptor {
-        label: Some("Model instance Buffer"),
-        contents: bytemuck::cast_slice(&instances),
-        usage: wgpu::BufferUsage::VERTEX,
-    };
-    let instance_buffer = device.create_buffer_init(&instance_buffer_descriptor);
+    // Refilled every frame with the instances that survive frustum culling.
+    let mut instances: Vec<InstanceRaw> = Vec::with_capacity(model.meshes.len() * model.instances.len());
+    let mut instance_buffer = VertexBuffer::with_capacity(&device, Some("Model instance Buffer"), instances.capacity());
+    let mut culling_statistics = CullingStatistics::default();
 
     let pipeline_layout_descriptor = wgpu::PipelineLayoutDescriptor {
         label: Some("Render Pipeline Layout"),
@@ -227,70 +156,25 @@ async fn main() -> Result<(), Box<dyn Error>> {
         push_constant_ranges: &[],
     };
     let render_pipeline_layout = device.create_pipeline_layout(&pipeline_layout_descriptor);
-
-    let vertex_state = wgpu::VertexState {
-        module: &shader_module,
-        entry_point: "main",
-        buffers: &[
-            VertexRaw::buffer_layout(),
-            InstanceRaw::buffer_layout(),
-        ],
-    };
-    let fragment_state = wgpu::FragmentState {
-        module: &shader_module,
-        entry_point: "main",
-        targets: &[wgpu::ColorTargetState {
-            format: swap_chain_descriptor.format,
-            blend: Some(wgpu::BlendState::REPLACE),
-            write_mask: wgpu::ColorWrite::ALL,
-        }],
-    };
-    let render_pipeline_descriptor = wgpu::RenderPipelineDescriptor {
-        label: Some("Render Pipeline"),
-        layout: Some(&render_pipeline_layout),
-        vertex: vertex_state,
-        fragment: Some(fragment_state),
-        primitive: wgpu::PrimitiveState {
-            topology: wgpu::PrimitiveTopology::TriangleList,
-            strip_index_format: None,
-            front_face: wgpu::FrontFace::Ccw,
-            cull_mode: Some(wgpu::Face::Back),
-            polygon_mode: wgpu::PolygonMode::Fill,
-            clamp_depth: false,
-            conservative: false,
-        },
-        depth_stencil: Some(wgpu::DepthStencilState {
-            format: wgpu::TextureFormat::Depth32Float,
-            depth_write_enabled: true,
-            depth_compare: wgpu::CompareFunction::Less,
-            stencil: wgpu::StencilState::default(),
-            bias: wgpu::DepthBiasState::default(),
-        }),
-        multisample: wgpu::MultisampleState {
-            count: 1,
-            mask: !0,
-            alpha_to_coverage_enabled: false,
-        },
-    };
-    let render_pipeline = device.create_render_pipeline(&render_pipeline_descriptor);
-
-    let size = wgpu::Extent3d {
-        width: size.width,
-        height: size.height,
-        depth_or_array_layers: 1,
-    };
-    let mut depth_texture_descriptor = wgpu::TextureDescriptor {
-        label: Some("depth texture"),
-        size,
-        mip_level_count: 1,
-        sample_count: 1,
-        dimension: wgpu::TextureDimension::D2,
-        format: wgpu::TextureFormat::Depth32Float,
-        usage: wgpu::TextureUsage::RENDER_ATTACHMENT | wgpu::TextureUsage::SAMPLED,
+    let pipeline_builder = PipelineBuilder::new()
+        .with_label("Render Pipeline")
+        .with_vertex_buffer(VertexRaw::buffer_layout())
+        .with_vertex_buffer(InstanceRaw::buffer_layout())
+        .with_uniform::<CameraUniforms>(0, 0)
+        .with_uniform::<Light>(1, 0)
+        .with_color_target(surface.format(), BlendMode::Replace);
+    pipeline_builder.check(&shader_reflection)?;
+
+    let build_render_pipeline = move |device: &wgpu::Device, shader_module: &wgpu::ShaderModule| {
+        pipeline_builder.build(device, &render_pipeline_layout, shader_module)
     };
-    let depth_texture = device.create_texture(&depth_texture_descriptor);
+    let mut render_pipeline = HotReloadPipeline::new(&device, &shader_loader, shader_path, build_render_pipeline)?;
+    let mut transient_resources = TransientResources::new();
 
-    let mut depth_texture_view = depth_texture.create_view(&wgpu::TextureViewDescriptor::default());
+    let mut frame_time = FrameTime::new(None);
+    // Frame times and culling results are logged together once a second.
+    let report_interval = Duration::from_secs(1);
+    let mut last_report = Instant::now();
 
     event_loop.run(move |event, _, control_flow| {
         let mut update = || {
@@ -298,11 +182,11 @@ async fn main() -> Result<(), Box<dyn Error>> {
                 *control_flow = ControlFlow::Exit;
             }
 
-            if keyboard.is_key_pressed(VirtualKeyCode::F1) {
+            if keyboard.just_pressed(VirtualKeyCode::F1) {
                 window.set_fullscreen(Some(winit::window::Fullscreen::Borderless(None)));
             }
 
-            if keyboard.is_key_pressed(VirtualKeyCode::F2) {
+            if keyboard.just_pressed(VirtualKeyCode::F2) {
                 window.set_fullscreen(None);
             }
         };
@@ -318,129 +202,116 @@ async fn main() -> Result<(), Box<dyn Error>> {
                     WindowEvent::KeyboardInput { input, .. } => {
                         keyboard.handle_input(input);
                     },
+                    WindowEvent::MouseInput { state, button, .. } => {
+                        mouse.handle_input(*button, *state);
+                    },
+                    WindowEvent::CursorMoved { position, .. } => {
+                        mouse.handle_cursor_moved(*position);
+                    },
+                    WindowEvent::MouseWheel { delta, .. } => {
+                        mouse.handle_scroll(*delta);
+                    },
                     WindowEvent::Resized(physical_size) => {
-                        let size = *physical_size;
-                        swap_chain_descriptor.width = size.width;
-                        swap_chain_descriptor.height = size.height;
-                        swap_chain = device.create_swap_chain(&surface, &swap_chain_descriptor);
-
-                        depth_texture_descriptor.size.width = size.width;
-                        depth_texture_descriptor.size.height = size.height;
-                        let depth_texture = device.create_texture(&depth_texture_descriptor);
-                        depth_texture_view = depth_texture.create_view(&wgpu::TextureViewDescriptor::default());
+                        surface.resize(&device, *physical_size);
+                        camera.resize(*physical_size);
                     },
                     WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
-                        let size = **new_inner_size;
-                        swap_chain_descriptor.width = size.width;
-                        swap_chain_descriptor.height = size.height;
-                        swap_chain = device.create_swap_chain(&surface, &swap_chain_descriptor);
-
-                        depth_texture_descriptor.size.width = size.width;
-                        depth_texture_descriptor.size.height = size.height;
-                        let depth_texture = device.create_texture(&depth_texture_descriptor);
-                        depth_texture_view = depth_texture.create_view(&wgpu::TextureViewDescriptor::default());
+                        surface.resize(&device, **new_inner_size);
+                        camera.resize(**new_inner_size);
                     },
                     _ => {},
                 }
             },
             Event::RedrawRequested(_) => {
-                let frame = match swap_chain.get_current_frame() {
-                    Ok(frame) => frame,
-                    Err(wgpu::SwapChainError::Lost) => {
-                        swap_chain = device.create_swap_chain(&surface, &swap_chain_descriptor);
-                        swap_chain.get_current_frame().unwrap()
-                    },
-                    Err(wgpu::SwapChainError::Outdated) => {
-                        swap_chain = device.create_swap_chain(&surface, &swap_chain_descriptor);
-                        swap_chain.get_current_frame().unwrap()
-                    },
-                    Err(_) => panic!("failed to get frame"),
-                };
+                render_pipeline.update(&device, &shader_loader);
 
-                let command_encoder_descriptor = wgpu::CommandEncoderDescriptor {
-                    label: Some("Render Encoder"),
+                let frame = match surface.acquire_frame(&device) {
+                    Ok(Some(frame)) => frame,
+                    Ok(None) => return,
+                    Err(error) => panic!("failed to get frame: {}", error),
                 };
-                let mut encoder = device.create_command_encoder(&command_encoder_descriptor);
-                {
+
+                let frustum = Frustum::from_view_projection(&camera.view_projection_matrix());
+                let (instance_ranges, statistics) = model.cull_instances(&frustum, &mut instances);
+                instance_buffer.write(&device, &queue, &instances);
+                if statistics != culling_statistics {
+                    window.set_title(&format!("Model example ({} visible, {} culled)", statistics.visible, statistics.culled));
+                }
+                culling_statistics = statistics;
+
+                let mut render_graph = RenderGraph::new();
+                let output = render_graph.import_texture("surface", Some(ClearValue::Color(wgpu::Color {
+                    r: 0.1,
+                    g: 0.2,
+                    b: 0.3,
+                    a: 1.0,
+                })));
+                let size = surface.size();
+                let depth_texture = TransientTexture::new(size.width, size.height, DEFAULT_DEPTH_FORMAT, wgpu::TextureUsage::RENDER_ATTACHMENT);
+                let depth = render_graph.create_texture("depth", depth_texture);
+
+                let model_pass = PassBuilder::new("model").writes(output).writes(depth);
+                render_graph.add_pass(model_pass, |context| {
                     let render_pass_descriptor = wgpu::RenderPassDescriptor {
                         label: Some("Render Pass"),
-                        color_attachments: &[wgpu::RenderPassColorAttachment {
-                            view: &frame.output.view,
-                            resolve_target: None,
-                            ops: wgpu::Operations {
-                                load: wgpu::LoadOp::Clear(wgpu::Color {
-                                    r: 0.1,
-                                    g: 0.2,
-                                    b: 0.3,
-                                    a: 1.0,
-                                }),
-                                store: true,
-                            },
-                        }],
-                        depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
-                            view: &depth_texture_view,
-                            depth_ops: Some(wgpu::Operations {
-                                load: wgpu::LoadOp::Clear(1.0),
-                                store: true,
-                            }),
-                            stencil_ops: None,
-                        }),
+                        color_attachments: &[context.resources.color_attachment(output)],
+                        depth_stencil_attachment: Some(context.resources.depth_stencil_attachment(depth)),
                     };
-                    
-                    let mut render_pass = encoder.begin_render_pass(&render_pass_descriptor);
 
-                    render_pass.set_pipeline(&render_pipeline);
+                    let mut render_pass = context.encoder.begin_render_pass(&render_pass_descriptor);
+
+                    render_pass.set_pipeline(render_pipeline.pipeline());
                     render_pass.set_bind_group(0, &uniform_bind_group, &[]);
                     render_pass.set_bind_group(1, &light_bind_group, &[]);
-                    render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
-                    render_pass.set_vertex_buffer(1, instance_buffer.slice(..));
-                    render_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint32);
-
-                    for mesh in &model.meshes {
-                        render_pass.draw_indexed(mesh.offset..mesh.len, 0, 0..num_instances);
+                    // The instance buffer is empty when every instance was
+                    // culled, and empty buffers can not be bound.
+                    if let (Some(vertices), Some(instances), Some(indices)) = (vertex_buffer.slice(), instance_buffer.slice(), index_buffer.slice()) {
+                        render_pass.set_vertex_buffer(0, vertices);
+                        render_pass.set_vertex_buffer(1, instances);
+                        render_pass.set_index_buffer(indices, index_buffer.format());
+
+                        for (mesh, instance_range) in model.meshes.iter().zip(instance_ranges) {
+                            if !instance_range.is_empty() {
+                                render_pass.draw_indexed(mesh.offset..mesh.len, 0, instance_range);
+                            }
+                        }
                     }
+                });
+
+                let imports = ImportedResources::new().with_texture(output, &frame.output.view);
+                let result = render_graph
+                    .compile()
+                    .and_then(|compiled| render_graph.execute(&compiled, &device, &queue, &mut transient_resources, &imports));
+                if let Err(error) = result {
+                    panic!("failed to render frame: {}", error);
                 }
-
-                let command_buffer = encoder.finish();
-                queue.submit(std::iter::once(command_buffer));
+            },
+            Event::DeviceEvent { event: DeviceEvent::MouseMotion { delta }, .. } => {
+                mouse.handle_motion(delta);
             },
             Event::MainEventsCleared => {
                 update();
+
+                let delta = frame_time.update();
+                if last_report.elapsed() >= report_interval {
+                    let frame_statistics = frame_time.statistics();
+                    log::info!(
+                        "{:.1} fps, p95 {:.2} ms, {} instances visible, {} culled",
+                        frame_statistics.fps().unwrap_or(0.0),
+                        frame_statistics.p95().map_or(0.0, |p95| p95.as_secs_f64() * 1000.0),
+                        culling_statistics.visible,
+                        culling_statistics.culled,
+                    );
+                    last_report = Instant::now();
+                }
+                camera_controller.update(&mut camera, &keyboard, &mouse, delta);
+                uniform_buffer.write(&queue, &camera.uniforms());
+
+                keyboard.end_frame();
+                mouse.end_frame();
                 window.request_redraw();
             },
             _ => {},
         }
     });
-}
-
-async fn create_surface_and_adapter(window: &winit::window::Window) -> Result<(wgpu::Surface, wgpu::Adapter), Box<dyn Error>> {
-    let instance = wgpu::Instance::new(wgpu::BackendBit::PRIMARY);
-    let surface = unsafe { instance.create_surface(window) };
-
-    let adapter_options = wgpu::RequestAdapterOptions {
-        power_preference: wgpu::PowerPreference::default(),
-        compatible_surface: Some(&surface),
-    };
-    let adapter = match instance.request_adapter(&adapter_options).await {
-        Some(adapter) => adapter,
-        None => return Err(Box::from("No adapter found")),
-    };
-
-    Ok((surface, adapter))
-}
-
-async fn create_device_and_queue(adapter: &wgpu::Adapter) -> Result<(wgpu::Device, wgpu::Queue), Box<dyn Error>> {
-    let features = wgpu::Features::NON_FILL_POLYGON_MODE;
-    let device_descriptor = wgpu::DeviceDescriptor {
-        features,
-        limits: wgpu::Limits::default(),
-        label: None,
-    };
-    let trace_path = None;
-    let (device, queue) = match adapter.request_device(&device_descriptor, trace_path).await {
-        Ok((device, queue)) => (device, queue),
-        Err(_) => return Err(Box::from("Failed to create device and queue")),
-    };
-
-    Ok((device, queue))
 }
\ No newline at end of file
@@ -1,4 +1,8 @@
 use std::error::Error;
+use std::ops::Range;
+
+use webgpu::render::VertexLayout;
+use webgpu::transforms::{Aabb, Frustum, Transform};
 
 #[derive(Clone)]
 struct FaceIndices {
@@ -51,28 +55,53 @@ pub struct Model {
 pub struct Mesh {
     pub offset: u32,
     pub len: u32,
+    // In model space, around the vertices of the mesh.
+    pub bounds: Aabb,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CullingStatistics {
+    pub visible: usize,
+    pub culled: usize,
+}
+
+impl Model {
+    // Collects the instances of every mesh that are inside the frustum,
+    // grouped per mesh. Returns the range of `instances` to draw for each
+    // mesh, in the order of `meshes`.
+    pub fn cull_instances(&self, frustum: &Frustum, instances: &mut Vec<InstanceRaw>) -> (Vec<Range<u32>>, CullingStatistics) {
+        instances.clear();
+        let mut ranges = Vec::with_capacity(self.meshes.len());
+        let mut statistics = CullingStatistics::default();
+
+        for mesh in &self.meshes {
+            let start = instances.len() as u32;
+            for instance in &self.instances {
+                if frustum.intersects_aabb(&mesh.bounds.transformed(&instance.transform.matrix())) {
+                    instances.push(instance.to_instance_raw());
+                    statistics.visible += 1;
+                } else {
+                    statistics.culled += 1;
+                }
+            }
+            ranges.push(start..instances.len() as u32);
+        }
+
+        (ranges, statistics)
+    }
 }
 
 #[derive(Debug)]
 pub struct Instance {
-    pub position: cgmath::Vector3<f32>,
-    pub rotation: cgmath::Vector3<f32>,
-    pub scale: cgmath::Vector3<f32>,
+    pub transform: Transform,
     pub material: Material,
 }
 
 impl Instance {
     pub fn to_instance_raw(&self) -> InstanceRaw {
-        let position_matrix = cgmath::Matrix4::from_translation(self.position);
-        let rotation_matrix = cgmath::Matrix4::from_angle_x(cgmath::Deg(self.rotation.x)) * cgmath::Matrix4::from_angle_y(cgmath::Deg(self.rotation.y)) * cgmath::Matrix4::from_angle_z(cgmath::Deg(self.rotation.z));
-        let scale_matrix = cgmath::Matrix4::from_nonuniform_scale(self.scale.x, self.scale.y, self.scale.z);
-
-        let model_matrix = position_matrix * rotation_matrix * scale_matrix;
-        let normal_matrix = cgmath::Matrix3::from_angle_x(cgmath::Deg(self.rotation.x)) * cgmath::Matrix3::from_angle_y(cgmath::Deg(self.rotation.y)) * cgmath::Matrix3::from_angle_z(cgmath::Deg(self.rotation.z));
-
         InstanceRaw {
-            model_matrix: model_matrix.into(),
-            normal_matrix: normal_matrix.into(),
+            model_matrix: self.transform.matrix().into(),
+            normal_matrix: self.transform.normal_matrix().into(),
             ambient_color: self.material.ambient.into(),
             diffuse_color: self.material.diffuse.into(),
             specular_color: self.material.specular.into(),
@@ -87,47 +116,19 @@ pub struct Material {
     pub specular: cgmath::Vector3<f32>,
 }
 
-pub trait VertexBufferLayout {
-    fn buffer_layout<'a>() -> wgpu::VertexBufferLayout<'a>;
-}
-
 #[repr(C)]
-#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable, VertexLayout)]
 pub struct VertexRaw {
     pub position: [f32; 3],
     pub uvs: [f32; 2],
     pub normals: [f32; 3],
 }
 
-impl VertexBufferLayout for VertexRaw {
-    fn buffer_layout<'a>() -> wgpu::VertexBufferLayout<'a> {
-        wgpu::VertexBufferLayout {
-            array_stride: std::mem::size_of::<VertexRaw>() as wgpu::BufferAddress, 
-            step_mode: wgpu::InputStepMode::Vertex, 
-            attributes: &[ 
-                wgpu::VertexAttribute {
-                    offset: 0, 
-                    shader_location: 0, 
-                    format: wgpu::VertexFormat::Float32x3,
-                },
-                wgpu::VertexAttribute {
-                    offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
-                    shader_location: 1,
-                    format: wgpu::VertexFormat::Float32x2,
-                },
-                wgpu::VertexAttribute {
-                    offset: std::mem::size_of::<[f32; 5]>() as wgpu::BufferAddress,
-                    shader_location: 2,
-                    format: wgpu::VertexFormat::Float32x3,
-                },
-            ],
-        }
-    }
-}
-
 #[repr(C)]
-#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable, VertexLayout)]
+#[step_mode(Instance)]
 pub struct InstanceRaw {
+    #[location(3)]
     pub model_matrix: [[f32; 4]; 4],
     pub normal_matrix: [[f32; 3]; 3],
     pub ambient_color: [f32; 3],
@@ -135,67 +136,6 @@ pub struct InstanceRaw {
     pub specular_color: [f32; 3],
 }
 
-impl VertexBufferLayout for InstanceRaw {
-    fn buffer_layout<'a>() -> wgpu::VertexBufferLayout<'a> {
-        wgpu::VertexBufferLayout {
-            array_stride: std::mem::size_of::<InstanceRaw>() as wgpu::BufferAddress, 
-            step_mode: wgpu::InputStepMode::Instance, 
-            attributes: &[ 
-                wgpu::VertexAttribute {
-                    offset: 0,
-                    shader_location: 3,
-                    format: wgpu::VertexFormat::Float32x4,
-                },
-                wgpu::VertexAttribute {
-                    offset: std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
-                    shader_location: 4,
-                    format: wgpu::VertexFormat::Float32x4,
-                },
-                wgpu::VertexAttribute {
-                    offset: std::mem::size_of::<[f32; 8]>() as wgpu::BufferAddress,
-                    shader_location: 5,
-                    format: wgpu::VertexFormat::Float32x4,
-                },
-                wgpu::VertexAttribute {
-                    offset: std::mem::size_of::<[f32; 12]>() as wgpu::BufferAddress,
-                    shader_location: 6,
-                    format: wgpu::VertexFormat::Float32x4,
-                },
-                wgpu::VertexAttribute {
-                    offset: std::mem::size_of::<[f32; 16]>() as wgpu::BufferAddress,
-                    shader_location: 7,
-                    format: wgpu::VertexFormat::Float32x3,
-                },
-                wgpu::VertexAttribute {
-                    offset: std::mem::size_of::<[f32; 19]>() as wgpu::BufferAddress,
-                    shader_location: 8,
-                    format: wgpu::VertexFormat::Float32x3,
-                },
-                wgpu::VertexAttribute {
-                    offset: std::mem::size_of::<[f32; 22]>() as wgpu::BufferAddress,
-                    shader_location: 9,
-                    format: wgpu::VertexFormat::Float32x3,
-                },
-                wgpu::VertexAttribute {
-                    offset: std::mem::size_of::<[f32; 25]>() as wgpu::BufferAddress,
-                    shader_location: 10,
-                    format: wgpu::VertexFormat::Float32x3,
-                },
-                wgpu::VertexAttribute {
-                    offset: std::mem::size_of::<[f32; 28]>() as wgpu::BufferAddress,
-                    shader_location: 11,
-                    format: wgpu::VertexFormat::Float32x3,
-                },
-                wgpu::VertexAttribute {
-                    offset: std::mem::size_of::<[f32; 31]>() as wgpu::BufferAddress,
-                    shader_location: 12,
-                    format: wgpu::VertexFormat::Float32x3,
-                },
-            ],
-        }
-    }
-}
-
 pub fn parse_wavefront_object(data: String) -> Result<(Model, Vec<VertexRaw>, Vec<u32>), Box<dyn Error>> {
     let mut positions = vec![];
     let mut uvs = vec![];
@@ -215,6 +155,7 @@ pub fn parse_wavefront_object(data: String) -> Result<(Model, Vec<VertexRaw>, Ve
                 meshes.push(Mesh {
                     offset,
                     len: indices_len,
+                    bounds: calc_mesh_bounds(offset..indices_len, &indices, &verticies),
                 });
             }
         }
@@ -254,6 +195,7 @@ pub fn parse_wavefront_object(data: String) -> Result<(Model, Vec<VertexRaw>, Ve
     meshes.push(Mesh {
         offset,
         len: indices.len() as u32,
+        bounds: calc_mesh_bounds(offset..indices.len() as u32, &indices, &verticies),
     });
 
     Ok((
@@ -362,6 +304,14 @@ fn find_previous_index(indices: &Vec<u32>) -> u32 {
     largest_index
 }
 
+fn calc_mesh_bounds(range: Range<u32>, indices: &Vec<u32>, verticies: &Vec<(String, VertexRaw)>) -> Aabb {
+    let points = indices[range.start as usize..range.end as usize]
+        .iter()
+        .map(|index| cgmath::Point3::from(verticies[*index as usize].1.position));
+
+    Aabb::from_points(points).unwrap_or_else(|| Aabb::new(cgmath::Point3::new(0.0, 0.0, 0.0), cgmath::Point3::new(0.0, 0.0, 0.0)))
+}
+
 fn calc_mesh_offset(meshes: &Vec<Mesh>) -> u32 {
     match meshes.last() {
         Some(mesh) => mesh.len,
@@ -1,10 +1,4 @@
-[[block]]
-struct Uniforms {
-    view_matrix: mat4x4<f32>;
-    projection_matrix: mat4x4<f32>;
-};
-[[group(0), binding(0)]]
-var<uniform> uniforms: Uniforms;
+#include "common.wgsl"
 
 [[block]]
 struct Light {
@@ -37,15 +31,20 @@ struct VertexOutput {
     [[location(0)]] ambient_color: vec3<f32>;
     [[location(1)]] diffuse_color: vec3<f32>;
     [[location(2)]] normal: vec3<f32>;
+#ifdef SPECULAR
+    [[location(3)]] specular_color: vec3<f32>;
+    [[location(4)]] view_position: vec3<f32>;
+    [[location(5)]] view_normal: vec3<f32>;
+#endif
 };
 
 [[stage(vertex)]]
 fn main(vertex_input: VertexInput, instance_input: InstanceInput) -> VertexOutput {
-    let model_matrix = mat4x4<f32>(
+    let model_matrix = instance_model_matrix(
         instance_input.model_matrix_0,
         instance_input.model_matrix_1,
         instance_input.model_matrix_2,
-        instance_input.model_matrix_3,
+        instance_input.model_matrix_3
     );
     let normal_matrix = mat3x3<f32>(
         instance_input.normal_matrix_0,
@@ -60,6 +59,11 @@ fn main(vertex_input: VertexInput, instance_input: InstanceInput) -> VertexOutpu
     output.ambient_color = instance_input.ambient_color;
     output.diffuse_color = instance_input.diffuse_color;
     output.normal = normal_matrix * vertex_input.normal;
+#ifdef SPECULAR
+    output.specular_color = instance_input.specular_color;
+    output.view_position = (uniforms.view_matrix * model_matrix * vec4<f32>(vertex_input.position, 1.0)).xyz;
+    output.view_normal = (uniforms.view_matrix * vec4<f32>(output.normal, 0.0)).xyz;
+#endif
     return output;
 }
 
@@ -73,15 +77,20 @@ fn main(input: VertexOutput) -> [[location(0)]] vec4<f32> {
     let diffuse_strength = max(dot(input.normal, light_dir), 0.0);
     let diffuse_color = input.diffuse_color * diffuse_strength;
 
-    // let view_dir = normalize(uniforms.view_matrix.xyz - input.position);
-    // let reflect_dir = reflect(-light_dir, input.normal);
+#ifdef SPECULAR
+    // Specular lighting is done in view space where the camera sits at the origin.
+    let view_light_position = (uniforms.view_matrix * vec4<f32>(light.position, 1.0)).xyz;
+    let view_light_dir = normalize(view_light_position - input.view_position);
+    let view_dir = normalize(-input.view_position);
+    let reflect_dir = reflect(-view_light_dir, normalize(input.view_normal));
 
-    // let specular_strength = pow(max(dot(view_dir, reflect_dir), 0.0), 32.0);
-    // let specular_color = specular_strength * light.color;
-
-    // let color = ambient_color + diffuse_color + specular_color;
+    let specular_strength = pow(max(dot(view_dir, reflect_dir), 0.0), 32.0);
+    let specular_color = input.specular_color * specular_strength * light.color;
 
+    let color = ambient_color + diffuse_color + specular_color;
+#else
     let color = ambient_color + diffuse_color;
+#endif
 
     return vec4<f32>(color, 1.0);
-}
\ No newline at end of file
+}
@@ -0,0 +1,12 @@
+[[block]]
+struct Uniforms {
+    view_matrix: mat4x4<f32>;
+    projection_matrix: mat4x4<f32>;
+};
+[[group(0), binding(0)]]
+var<uniform> uniforms: Uniforms;
+
+// Instance model matrices are passed in as four vec4 vertex attributes.
+fn instance_model_matrix(column_0: vec4<f32>, column_1: vec4<f32>, column_2: vec4<f32>, column_3: vec4<f32>) -> mat4x4<f32> {
+    return mat4x4<f32>(column_0, column_1, column_2, column_3);
+}
@@ -1,5 +1,6 @@
 use std::error::Error;
 use webgpu::inputs::Keyboard;
+use webgpu::render::{BlendMode, GpuConfiguration, GpuContext, PipelineBuilder, Surface};
 use winit::dpi::PhysicalSize;
 use winit::window::WindowBuilder;
 use winit::event_loop::{EventLoop, ControlFlow};
@@ -20,38 +21,11 @@ async fn main() -> Result<(), Box<dyn Error>> {
     };
     let mut keyboard = Keyboard::new();
 
-    let instance = wgpu::Instance::new(wgpu::BackendBit::PRIMARY);
-    let surface = unsafe { instance.create_surface(&window) };
-
-    let adapter_options = wgpu::RequestAdapterOptions {
-        power_preference: wgpu::PowerPreference::default(),
-        compatible_surface: Some(&surface),
-    };
-    let adapter = match instance.request_adapter(&adapter_options).await {
-        Some(adapter) => adapter,
-        None => panic!("No adapter found!"),
-    };
-
-    let device_descriptor = wgpu::DeviceDescriptor {
-        features: wgpu::Features::empty(),
-        limits: wgpu::Limits::default(),
-        label: None,
-    };
-    let trace_path = None;
-    let (device, queue) = match adapter.request_device(&device_descriptor, trace_path).await {
-        Ok((device, queue)) => (device, queue),
-        Err(_) => panic!("Failed to create device and queue"),
-    };
+    let (gpu, surface) = GpuContext::with_surface(&window, &GpuConfiguration::new()).await?;
+    let GpuContext { adapter, device, queue, .. } = gpu;
 
     let size = window.inner_size();
-    let mut swap_chain_descriptor = wgpu::SwapChainDescriptor {
-        usage: wgpu::TextureUsage::RENDER_ATTACHMENT,
-        format: adapter.get_swap_chain_preferred_format(&surface).unwrap(),
-        width: size.width,
-        height: size.height,
-        present_mode: wgpu::PresentMode::Fifo,
-    };
-    let mut swap_chain = device.create_swap_chain(&surface, &swap_chain_descriptor);
+    let mut surface = Surface::new(&adapter, &device, surface, size)?;
 
     let shader_module_descriptor = wgpu::ShaderModuleDescriptor {
         label: Some("Shader"),
@@ -67,42 +41,11 @@ async fn main() -> Result<(), Box<dyn Error>> {
     };
     let render_pipeline_layout = device.create_pipeline_layout(&pipeline_layout_descriptor);
 
-    let fragment_state = wgpu::FragmentState {
-        module: &shader_module,
-        entry_point: "main",
-        targets: &[wgpu::ColorTargetState {
-            format: swap_chain_descriptor.format,
-            blend: Some(wgpu::BlendState::REPLACE),
-            write_mask: wgpu::ColorWrite::ALL,
-        }],
-    };
-
-    let render_pipeline_descriptor = wgpu::RenderPipelineDescriptor {
-        label: Some("Render Pipeline"),
-        layout: Some(&render_pipeline_layout),
-        vertex: wgpu::VertexState {
-            module: &shader_module,
-            entry_point: "main", 
-            buffers: &[], 
-        },
-        fragment: Some(fragment_state),
-        primitive: wgpu::PrimitiveState {
-            topology: wgpu::PrimitiveTopology::TriangleList, 
-            strip_index_format: None,
-            front_face: wgpu::FrontFace::Ccw, 
-            cull_mode: Some(wgpu::Face::Back),
-            polygon_mode: wgpu::PolygonMode::Fill,
-            clamp_depth: false,
-            conservative: false,
-        },
-        depth_stencil: None, 
-        multisample: wgpu::MultisampleState {
-            count: 1, 
-            mask: !0,
-            alpha_to_coverage_enabled: false,
-        },
-    };
-    let render_pipeline = device.create_render_pipeline(&render_pipeline_descriptor);
+    let render_pipeline = PipelineBuilder::new()
+        .with_label("Render Pipeline")
+        .with_color_target(surface.format(), BlendMode::Replace)
+        .without_depth()
+        .build(&device, &render_pipeline_layout, &shader_module);
 
     event_loop.run(move |event, _, control_flow| {
         let mut update = || {
@@ -110,11 +53,11 @@ async fn main() -> Result<(), Box<dyn Error>> {
                 *control_flow = ControlFlow::Exit;
             }
 
-            if keyboard.is_key_pressed(VirtualKeyCode::F1) {
+            if keyboard.just_pressed(VirtualKeyCode::F1) {
                 window.set_fullscreen(Some(winit::window::Fullscreen::Borderless(None)));
             }
 
-            if keyboard.is_key_pressed(VirtualKeyCode::F2) {
+            if keyboard.just_pressed(VirtualKeyCode::F2) {
                 window.set_fullscreen(None);
             }
         };
@@ -131,32 +74,19 @@ async fn main() -> Result<(), Box<dyn Error>> {
                         keyboard.handle_input(input);
                     },
                     WindowEvent::Resized(physical_size) => {
-                        let size = *physical_size;
-                        swap_chain_descriptor.width = size.width;
-                        swap_chain_descriptor.height = size.height;
-                        swap_chain = device.create_swap_chain(&surface, &swap_chain_descriptor);
+                        surface.resize(&device, *physical_size);
                     },
                     WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
-                        let size = **new_inner_size;
-                        swap_chain_descriptor.width = size.width;
-                        swap_chain_descriptor.height = size.height;
-                        swap_chain = device.create_swap_chain(&surface, &swap_chain_descriptor);
+                        surface.resize(&device, **new_inner_size);
                     },
                     _ => {},
                 }
             },
             Event::RedrawRequested(_) => {
-                let frame = match swap_chain.get_current_frame() {
-                    Ok(frame) => frame,
-                    Err(wgpu::SwapChainError::Lost) => {
-                        swap_chain = device.create_swap_chain(&surface, &swap_chain_descriptor);
-                        swap_chain.get_current_frame().unwrap()
-                    },
-                    Err(wgpu::SwapChainError::Outdated) => {
-                        swap_chain = device.create_swap_chain(&surface, &swap_chain_descriptor);
-                        swap_chain.get_current_frame().unwrap()
-                    },
-                    Err(_) => panic!("failed to get frame"),
+                let frame = match surface.acquire_frame(&device) {
+                    Ok(Some(frame)) => frame,
+                    Ok(None) => return,
+                    Err(error) => panic!("failed to get frame: {}", error),
                 };
 
                 let command_encoder_descriptor = wgpu::CommandEncoderDescriptor {
@@ -195,6 +125,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
             },
             Event::MainEventsCleared => {
                 update();
+                keyboard.end_frame();
                 window.request_redraw();
             },
             _ => {},
@@ -0,0 +1,433 @@
+use std::time::Duration;
+
+use cgmath::{Angle, Deg, InnerSpace, Point3, Rad, Vector2, Vector3};
+use winit::event::{MouseButton, VirtualKeyCode};
+
+use super::{Camera, Projection};
+use crate::inputs::{Keyboard, Mouse};
+use crate::window::Clock;
+
+pub const DEFAULT_PITCH_LIMIT: Deg<f32> = Deg(89.0);
+
+// Yaw turns counter-clockwise around +Y starting from -Z, pitch turns up.
+pub fn direction_from_yaw_pitch(yaw: Rad<f32>, pitch: Rad<f32>) -> Vector3<f32> {
+    Vector3::new(-yaw.sin() * pitch.cos(), pitch.sin(), -yaw.cos() * pitch.cos())
+}
+
+pub fn yaw_pitch_from_direction(direction: Vector3<f32>) -> (Rad<f32>, Rad<f32>) {
+    let direction = direction.normalize();
+    (Rad((-direction.x).atan2(-direction.z)), Rad(direction.y.clamp(-1.0, 1.0).asin()))
+}
+
+fn clamp_pitch(pitch: Rad<f32>, limit: Rad<f32>) -> Rad<f32> {
+    Rad(pitch.0.clamp(-limit.0, limit.0))
+}
+
+fn axis<C: Clock>(keyboard: &Keyboard<C>, positive: VirtualKeyCode, negative: VirtualKeyCode) -> f32 {
+    let mut value = 0.0;
+    if keyboard.is_key_pressed(positive) {
+        value += 1.0;
+    }
+    if keyboard.is_key_pressed(negative) {
+        value -= 1.0;
+    }
+    value
+}
+
+fn mouse_delta(mouse: &Mouse, button: Option<MouseButton>) -> Vector2<f32> {
+    match button {
+        Some(button) if !mouse.is_button_pressed(button) => Vector2::new(0.0, 0.0),
+        _ => mouse.motion_delta().cast().unwrap_or_else(|| Vector2::new(0.0, 0.0)),
+    }
+}
+
+// WASD moves, Q and E move down and up, shift moves faster and the mouse
+// looks around while `look_button` is held, or always without one, for
+// example when the cursor is grabbed. Mouse movement comes from
+// `Mouse::handle_motion`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FirstPersonController {
+    pub yaw: Rad<f32>,
+    pub pitch: Rad<f32>,
+    pub move_speed: f32,
+    pub boost_multiplier: f32,
+    pub look_sensitivity: Rad<f32>,
+    pub pitch_limit: Rad<f32>,
+    pub look_button: Option<MouseButton>,
+    pub fly: bool,
+}
+
+impl FirstPersonController {
+    pub fn new(camera: &Camera) -> Self {
+        let (yaw, pitch) = yaw_pitch_from_direction(camera.direction);
+        Self {
+            yaw,
+            pitch: clamp_pitch(pitch, DEFAULT_PITCH_LIMIT.into()),
+            move_speed: 5.0,
+            boost_multiplier: 4.0,
+            look_sensitivity: Deg(0.2).into(),
+            pitch_limit: DEFAULT_PITCH_LIMIT.into(),
+            look_button: Some(MouseButton::Right),
+            fly: true,
+        }
+    }
+
+    pub fn with_move_speed(mut self, move_speed: f32) -> Self {
+        self.move_speed = move_speed;
+        self
+    }
+
+    // Angle turned per pixel of mouse movement.
+    pub fn with_look_sensitivity<A: Into<Rad<f32>>>(mut self, look_sensitivity: A) -> Self {
+        self.look_sensitivity = look_sensitivity.into();
+        self
+    }
+
+    pub fn with_pitch_limit<A: Into<Rad<f32>>>(mut self, pitch_limit: A) -> Self {
+        self.pitch_limit = pitch_limit.into();
+        self
+    }
+
+    pub fn with_look_button(mut self, look_button: Option<MouseButton>) -> Self {
+        self.look_button = look_button;
+        self
+    }
+
+    // Without flying, moving forward stays level no matter the pitch.
+    pub fn with_fly(mut self, fly: bool) -> Self {
+        self.fly = fly;
+        self
+    }
+
+    pub fn update<C: Clock>(&mut self, camera: &mut Camera, keyboard: &Keyboard<C>, mouse: &Mouse, delta: Duration) {
+        let look = mouse_delta(mouse, self.look_button);
+        self.yaw -= self.look_sensitivity * look.x;
+        self.pitch = clamp_pitch(self.pitch - self.look_sensitivity * look.y, self.pitch_limit);
+        camera.direction = direction_from_yaw_pitch(self.yaw, self.pitch);
+
+        let forward = match self.fly {
+            true => camera.direction,
+            false => direction_from_yaw_pitch(self.yaw, Rad(0.0)),
+        };
+        let right = forward.cross(camera.up).normalize();
+
+        let movement = forward * axis(keyboard, VirtualKeyCode::W, VirtualKeyCode::S)
+            + right * axis(keyboard, VirtualKeyCode::D, VirtualKeyCode::A)
+            + camera.up * axis(keyboard, VirtualKeyCode::E, VirtualKeyCode::Q);
+        if movement.magnitude2() == 0.0 {
+            return;
+        }
+
+        let mut speed = self.move_speed;
+        if keyboard.is_key_pressed(VirtualKeyCode::LShift) {
+            speed *= self.boost_multiplier;
+        }
+        camera.position += movement.normalize() * speed * delta.as_secs_f32();
+    }
+}
+
+// Orbits around `target` while the left mouse button is held or with the
+// arrow keys, which move the camera in their direction. Zooms with the
+// scroll wheel and pans the target while the middle mouse button is held.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OrbitController {
+    pub target: Point3<f32>,
+    pub distance: f32,
+    pub min_distance: f32,
+    pub max_distance: f32,
+    pub yaw: Rad<f32>,
+    pub pitch: Rad<f32>,
+    pub rotate_sensitivity: Rad<f32>,
+    pub key_rotate_speed: Rad<f32>,
+    pub zoom_speed: f32,
+    pub pan_sensitivity: f32,
+    pub pitch_limit: Rad<f32>,
+}
+
+impl OrbitController {
+    pub fn new(camera: &Camera, target: Point3<f32>) -> Self {
+        let offset = camera.position - target;
+        let distance = offset.magnitude().max(f32::EPSILON);
+        let (yaw, pitch) = match offset.magnitude2() > 0.0 {
+            true => yaw_pitch_from_direction(-offset),
+            false => yaw_pitch_from_direction(camera.direction),
+        };
+
+        Self {
+            target,
+            distance,
+            min_distance: 0.1,
+            max_distance: f32::INFINITY,
+            yaw,
+            pitch: clamp_pitch(pitch, DEFAULT_PITCH_LIMIT.into()),
+            rotate_sensitivity: Deg(0.3).into(),
+            key_rotate_speed: Deg(90.0).into(),
+            zoom_speed: 0.1,
+            pan_sensitivity: 0.002,
+            pitch_limit: DEFAULT_PITCH_LIMIT.into(),
+        }
+    }
+
+    pub fn with_distance_limits(mut self, min_distance: f32, max_distance: f32) -> Self {
+        self.min_distance = min_distance;
+        self.max_distance = max_distance;
+        self.distance = self.distance.clamp(min_distance, max_distance);
+        self
+    }
+
+    pub fn with_rotate_sensitivity<A: Into<Rad<f32>>>(mut self, rotate_sensitivity: A) -> Self {
+        self.rotate_sensitivity = rotate_sensitivity.into();
+        self
+    }
+
+    // Fraction of the distance zoomed per scrolled line.
+    pub fn with_zoom_speed(mut self, zoom_speed: f32) -> Self {
+        self.zoom_speed = zoom_speed;
+        self
+    }
+
+    // Panning is scaled by the distance, so the target follows the cursor
+    // roughly the same at every zoom level.
+    pub fn with_pan_sensitivity(mut self, pan_sensitivity: f32) -> Self {
+        self.pan_sensitivity = pan_sensitivity;
+        self
+    }
+
+    pub fn with_pitch_limit<A: Into<Rad<f32>>>(mut self, pitch_limit: A) -> Self {
+        self.pitch_limit = pitch_limit.into();
+        self
+    }
+
+    pub fn update<C: Clock>(&mut self, camera: &mut Camera, keyboard: &Keyboard<C>, mouse: &Mouse, delta: Duration) {
+        let rotate = mouse_delta(mouse, Some(MouseButton::Left));
+        let key_rotate = self.key_rotate_speed * delta.as_secs_f32();
+        self.yaw += key_rotate * axis(keyboard, VirtualKeyCode::Right, VirtualKeyCode::Left) - self.rotate_sensitivity * rotate.x;
+        self.pitch += key_rotate * axis(keyboard, VirtualKeyCode::Down, VirtualKeyCode::Up) - self.rotate_sensitivity * rotate.y;
+        self.pitch = clamp_pitch(self.pitch, self.pitch_limit);
+
+        let scroll = mouse.scroll_lines().y;
+        self.distance = (self.distance * (1.0 - self.zoom_speed).powf(scroll)).clamp(self.min_distance, self.max_distance);
+
+        let pan = mouse_delta(mouse, Some(MouseButton::Middle));
+        let direction = direction_from_yaw_pitch(self.yaw, self.pitch);
+        let right = direction.cross(camera.up).normalize();
+        let up = right.cross(direction);
+        self.target += (up * pan.y - right * pan.x) * self.pan_sensitivity * self.distance;
+
+        self.apply(camera);
+    }
+
+    pub fn apply(&self, camera: &mut Camera) {
+        camera.position = self.target - direction_from_yaw_pitch(self.yaw, self.pitch) * self.distance;
+        camera.look_at(self.target);
+    }
+}
+
+// Moves the camera in its view plane without rotating it, by dragging with
+// `pan_button` or with WASD, and zooms with the scroll wheel. Orthographic
+// cameras zoom by shrinking their view volume, perspective cameras move
+// along their direction.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PanController {
+    pub pan_sensitivity: f32,
+    pub key_pan_speed: f32,
+    pub zoom_speed: f32,
+    pub min_zoom: f32,
+    pub max_zoom: f32,
+    pub pan_button: MouseButton,
+}
+
+impl PanController {
+    pub fn new() -> Self {
+        Self {
+            pan_sensitivity: 0.01,
+            key_pan_speed: 5.0,
+            zoom_speed: 0.1,
+            min_zoom: 0.01,
+            max_zoom: 1000.0,
+            pan_button: MouseButton::Left,
+        }
+    }
+
+    // World units per pixel dragged, orthographic cameras scale it by the
+    // half height of their view volume.
+    pub fn with_pan_sensitivity(mut self, pan_sensitivity: f32) -> Self {
+        self.pan_sensitivity = pan_sensitivity;
+        self
+    }
+
+    pub fn with_key_pan_speed(mut self, key_pan_speed: f32) -> Self {
+        self.key_pan_speed = key_pan_speed;
+        self
+    }
+
+    pub fn with_zoom_speed(mut self, zoom_speed: f32) -> Self {
+        self.zoom_speed = zoom_speed;
+        self
+    }
+
+    // Limits of the orthographic half height.
+    pub fn with_zoom_limits(mut self, min_zoom: f32, max_zoom: f32) -> Self {
+        self.min_zoom = min_zoom;
+        self.max_zoom = max_zoom;
+        self
+    }
+
+    pub fn with_pan_button(mut self, pan_button: MouseButton) -> Self {
+        self.pan_button = pan_button;
+        self
+    }
+
+    pub fn update<C: Clock>(&mut self, camera: &mut Camera, keyboard: &Keyboard<C>, mouse: &Mouse, delta: Duration) {
+        let right = camera.direction.cross(camera.up).normalize();
+        let up = right.cross(camera.direction).normalize();
+
+        let scale = match camera.projection {
+            Projection::Orthographic { ymag, .. } => ymag,
+            Projection::Perspective { .. } => 1.0,
+        };
+        let drag = mouse_delta(mouse, Some(self.pan_button)) * self.pan_sensitivity * scale;
+        let keys = Vector2::new(axis(keyboard, VirtualKeyCode::D, VirtualKeyCode::A), axis(keyboard, VirtualKeyCode::W, VirtualKeyCode::S))
+            * self.key_pan_speed
+            * scale
+            * delta.as_secs_f32();
+        camera.position += right * (keys.x - drag.x) + up * (keys.y + drag.y);
+
+        let scroll = mouse.scroll_lines().y;
+        if scroll == 0.0 {
+            return;
+        }
+
+        let zoom = (1.0 - self.zoom_speed).powf(scroll);
+        match &mut camera.projection {
+            Projection::Orthographic { xmag, ymag, .. } => {
+                let new_ymag = (*ymag * zoom).clamp(self.min_zoom, self.max_zoom);
+                *xmag *= new_ymag / *ymag;
+                *ymag = new_ymag;
+            },
+            Projection::Perspective { .. } => {
+                camera.position += camera.direction * (1.0 - zoom) * self.key_pan_speed;
+            },
+        }
+    }
+}
+
+impl Default for PanController {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::{Matrix4, Vector4};
+    use winit::event::ElementState;
+
+    use super::*;
+
+    fn assert_near(actual: Vector4<f32>, expected: Vector4<f32>) {
+        assert!((actual - expected).magnitude() < 1e-4, "expected {:?}, got {:?}", expected, actual);
+    }
+
+    fn transform(matrix: Matrix4<f32>, point: Point3<f32>) -> Vector4<f32> {
+        matrix * point.to_homogeneous()
+    }
+
+    fn camera() -> Camera {
+        Camera::perspective(Deg(60.0), 1.0, 0.1, Some(100.0))
+    }
+
+    #[test]
+    fn first_person_moves_forward_by_speed_and_delta() {
+        let mut camera = camera();
+        let mut controller = FirstPersonController::new(&camera).with_move_speed(2.0);
+        let mut keyboard = Keyboard::new();
+        keyboard.handle_key(VirtualKeyCode::W, ElementState::Pressed);
+
+        controller.update(&mut camera, &keyboard, &Mouse::new(), Duration::from_millis(500));
+
+        assert_near(camera.position.to_homogeneous(), Vector4::new(0.0, 0.0, -1.0, 1.0));
+        assert_near(transform(camera.view_matrix(), Point3::new(0.0, 0.0, -3.0)), Vector4::new(0.0, 0.0, -2.0, 1.0));
+    }
+
+    #[test]
+    fn first_person_mouse_look_turns_right() {
+        let mut camera = camera();
+        let mut controller = FirstPersonController::new(&camera).with_look_sensitivity(Deg(1.0)).with_look_button(None);
+        let mut mouse = Mouse::new();
+        mouse.handle_motion((90.0, 0.0));
+
+        controller.update(&mut camera, &Keyboard::new(), &mouse, Duration::from_millis(16));
+
+        // Looking down +X, a point on +X is straight ahead.
+        assert_near(transform(camera.view_matrix(), Point3::new(5.0, 0.0, 0.0)), Vector4::new(0.0, 0.0, -5.0, 1.0));
+    }
+
+    #[test]
+    fn first_person_pitch_is_clamped() {
+        let mut camera = camera();
+        let mut controller = FirstPersonController::new(&camera).with_look_sensitivity(Deg(1.0)).with_look_button(None);
+        let mut mouse = Mouse::new();
+        mouse.handle_motion((0.0, -1000.0));
+
+        controller.update(&mut camera, &Keyboard::new(), &mouse, Duration::from_millis(16));
+
+        assert_eq!(controller.pitch, DEFAULT_PITCH_LIMIT.into());
+        assert!(camera.view_matrix().x.x.is_finite());
+    }
+
+    #[test]
+    fn first_person_walking_stays_level() {
+        let mut camera = camera().with_look_at(Point3::new(0.0, 0.0, 0.0), Point3::new(0.0, -1.0, -1.0), Vector3::unit_y());
+        let mut controller = FirstPersonController::new(&camera).with_move_speed(1.0).with_fly(false);
+        let mut keyboard = Keyboard::new();
+        keyboard.handle_key(VirtualKeyCode::W, ElementState::Pressed);
+
+        controller.update(&mut camera, &keyboard, &Mouse::new(), Duration::from_secs(1));
+
+        assert_near(camera.position.to_homogeneous(), Vector4::new(0.0, 0.0, -1.0, 1.0));
+    }
+
+    #[test]
+    fn orbit_keeps_target_centered() {
+        let mut camera = camera().with_position(Point3::new(0.0, 0.0, 5.0));
+        let target = Point3::new(0.0, 0.0, 0.0);
+        let mut controller = OrbitController::new(&camera, target);
+        let mut keyboard = Keyboard::new();
+        keyboard.handle_key(VirtualKeyCode::Left, ElementState::Pressed);
+
+        controller.update(&mut camera, &keyboard, &Mouse::new(), Duration::from_secs(1));
+
+        // Orbiting a quarter turn to the left moves the camera from +Z to -X.
+        assert_near(camera.position.to_homogeneous(), Vector4::new(-5.0, 0.0, 0.0, 1.0));
+        assert_near(transform(camera.view_matrix(), target), Vector4::new(0.0, 0.0, -5.0, 1.0));
+    }
+
+    #[test]
+    fn orbit_zoom_respects_distance_limits() {
+        let mut camera = camera().with_position(Point3::new(0.0, 0.0, 5.0));
+        let mut controller = OrbitController::new(&camera, Point3::new(0.0, 0.0, 0.0)).with_distance_limits(2.0, 10.0);
+        let mut mouse = Mouse::new();
+        mouse.handle_scroll(winit::event::MouseScrollDelta::LineDelta(0.0, 100.0));
+
+        controller.update(&mut camera, &Keyboard::new(), &mouse, Duration::from_millis(16));
+
+        assert_eq!(controller.distance, 2.0);
+        assert_near(transform(camera.view_matrix(), controller.target), Vector4::new(0.0, 0.0, -2.0, 1.0));
+    }
+
+    #[test]
+    fn orthographic_pan_and_zoom() {
+        let mut camera = Camera::orthographic(2.0, 1.0, 0.1, 100.0).with_position(Point3::new(0.0, 0.0, 10.0));
+        let mut controller = PanController::new().with_key_pan_speed(1.0).with_zoom_speed(0.5);
+        let mut keyboard = Keyboard::new();
+        keyboard.handle_key(VirtualKeyCode::D, ElementState::Pressed);
+        let mut mouse = Mouse::new();
+        mouse.handle_scroll(winit::event::MouseScrollDelta::LineDelta(0.0, 1.0));
+
+        controller.update(&mut camera, &keyboard, &mouse, Duration::from_secs(2));
+
+        assert_near(transform(camera.view_matrix(), Point3::new(2.0, 0.0, 0.0)), Vector4::new(0.0, 0.0, -10.0, 1.0));
+        assert_eq!(camera.projection, Projection::Orthographic { xmag: 1.0, ymag: 0.5, z_near: 0.1, z_far: 100.0 });
+    }
+}
@@ -0,0 +1,9 @@
+mod controller;
+mod projection;
+mod view;
+
+pub use {
+    controller::*,
+    projection::*,
+    view::*,
+};
\ No newline at end of file
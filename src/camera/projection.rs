@@ -0,0 +1,107 @@
+use cgmath::{Matrix4, Rad};
+
+// Same parameters as glTF cameras, `xmag` and `ymag` are half the width and
+// height of the orthographic view volume. A perspective camera without
+// `z_far` has its far plane at infinity.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Projection {
+    Perspective { aspect_ratio: f32, vertical_fov: Rad<f32>, z_near: f32, z_far: Option<f32> },
+    Orthographic { xmag: f32, ymag: f32, z_near: f32, z_far: f32 },
+}
+
+impl Projection {
+    pub fn set_aspect_ratio(&mut self, new_aspect_ratio: f32) {
+        match self {
+            Projection::Perspective { aspect_ratio, .. } => *aspect_ratio = new_aspect_ratio,
+            Projection::Orthographic { xmag, ymag, .. } => *xmag = *ymag * new_aspect_ratio,
+        }
+    }
+
+    // Projection into OpenGL clip space, with depth from -1 to 1.
+    pub fn opengl_matrix(&self) -> Matrix4<f32> {
+        match *self {
+            Projection::Perspective { aspect_ratio, vertical_fov, z_near, z_far: Some(z_far) } => {
+                cgmath::perspective(vertical_fov, aspect_ratio, z_near, z_far)
+            },
+            Projection::Perspective { aspect_ratio, vertical_fov, z_near, z_far: None } => {
+                let focal_length = 1.0 / (vertical_fov.0 / 2.0).tan();
+                Matrix4::new(
+                    focal_length / aspect_ratio, 0.0, 0.0, 0.0,
+                    0.0, focal_length, 0.0, 0.0,
+                    0.0, 0.0, -1.0, -1.0,
+                    0.0, 0.0, -2.0 * z_near, 0.0,
+                )
+            },
+            Projection::Orthographic { xmag, ymag, z_near, z_far } => cgmath::ortho(-xmag, xmag, -ymag, ymag, z_near, z_far),
+        }
+    }
+}
+
+// Maps depth 0 to 1 and 1 to 0 after the conversion to wgpu clip space.
+pub(crate) fn reverse_z_matrix() -> Matrix4<f32> {
+    Matrix4::new(
+        1.0, 0.0, 0.0, 0.0,
+        0.0, 1.0, 0.0, 0.0,
+        0.0, 0.0, -1.0, 0.0,
+        0.0, 0.0, 1.0, 1.0,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::{Deg, Vector4};
+
+    use super::*;
+
+    fn ndc_depth(matrix: Matrix4<f32>, distance: f32) -> f32 {
+        let clip = matrix * Vector4::new(0.0, 0.0, -distance, 1.0);
+        clip.z / clip.w
+    }
+
+    fn perspective(z_far: Option<f32>) -> Projection {
+        Projection::Perspective {
+            aspect_ratio: 1.5,
+            vertical_fov: Deg(60.0).into(),
+            z_near: 0.1,
+            z_far,
+        }
+    }
+
+    #[test]
+    fn infinite_perspective_is_the_limit_of_a_far_plane() {
+        let infinite = perspective(None).opengl_matrix();
+        let far = perspective(Some(1.0e7)).opengl_matrix();
+        for column in 0..4 {
+            for row in 0..4 {
+                assert!((infinite[column][row] - far[column][row]).abs() < 1e-5);
+            }
+        }
+
+        assert!((ndc_depth(infinite, 0.1) + 1.0).abs() < 1e-6);
+        assert!(ndc_depth(infinite, 1.0e6) < 1.0);
+        assert!((ndc_depth(infinite, 1.0e6) - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn finite_perspective_maps_near_and_far_planes() {
+        let matrix = perspective(Some(100.0)).opengl_matrix();
+        assert!((ndc_depth(matrix, 0.1) + 1.0).abs() < 1e-5);
+        assert!((ndc_depth(matrix, 100.0) - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn aspect_ratio_widens_orthographic_views() {
+        let mut projection = Projection::Orthographic { xmag: 1.0, ymag: 2.0, z_near: 0.0, z_far: 10.0 };
+        projection.set_aspect_ratio(1.5);
+        assert_eq!(projection, Projection::Orthographic { xmag: 3.0, ymag: 2.0, z_near: 0.0, z_far: 10.0 });
+    }
+
+    #[test]
+    fn reverse_z_flips_depth() {
+        let flip = reverse_z_matrix();
+        for depth in [0.0, 0.25, 1.0].iter() {
+            let flipped = flip * Vector4::new(0.0, 0.0, *depth, 1.0);
+            assert!((flipped.z / flipped.w - (1.0 - depth)).abs() < 1e-6);
+        }
+    }
+}
@@ -0,0 +1,208 @@
+use cgmath::{InnerSpace, Matrix4, Point3, Rad, SquareMatrix, Vector2, Vector3};
+use winit::dpi::PhysicalSize;
+
+use super::{reverse_z_matrix, Projection};
+use crate::render::ShaderType;
+use crate::transforms::{opengl_to_wgpu_conversion_matrix, Ray};
+
+// Matches the `Uniforms` struct in examples/shaders/common.wgsl.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, ShaderType)]
+pub struct CameraUniforms {
+    pub view_matrix: [[f32; 4]; 4],
+    pub projection_matrix: [[f32; 4]; 4],
+}
+
+// Implemented by hand, the bytemuck derive leaves an unused check function
+// behind. Sound because the struct is `repr(C)` and only holds `f32`s.
+unsafe impl bytemuck::Zeroable for CameraUniforms { }
+unsafe impl bytemuck::Pod for CameraUniforms { }
+
+// A right handed camera looking down `direction`. Its projection matrix maps
+// into wgpu clip space, so shaders multiply by it without any conversion.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Camera {
+    pub position: Point3<f32>,
+    pub direction: Vector3<f32>,
+    pub up: Vector3<f32>,
+    pub projection: Projection,
+    reverse_z: bool,
+}
+
+impl Camera {
+    pub fn new(projection: Projection) -> Self {
+        Self {
+            position: Point3::new(0.0, 0.0, 0.0),
+            direction: -Vector3::unit_z(),
+            up: Vector3::unit_y(),
+            projection,
+            reverse_z: false,
+        }
+    }
+
+    pub fn perspective<A: Into<Rad<f32>>>(vertical_fov: A, aspect_ratio: f32, z_near: f32, z_far: Option<f32>) -> Self {
+        Self::new(Projection::Perspective {
+            aspect_ratio,
+            vertical_fov: vertical_fov.into(),
+            z_near,
+            z_far,
+        })
+    }
+
+    pub fn orthographic(xmag: f32, ymag: f32, z_near: f32, z_far: f32) -> Self {
+        Self::new(Projection::Orthographic {
+            xmag,
+            ymag,
+            z_near,
+            z_far,
+        })
+    }
+
+    pub fn with_position(mut self, position: Point3<f32>) -> Self {
+        self.position = position;
+        self
+    }
+
+    pub fn with_look_at(mut self, position: Point3<f32>, target: Point3<f32>, up: Vector3<f32>) -> Self {
+        self.position = position;
+        self.up = up;
+        self.look_at(target);
+        self
+    }
+
+    // Reverse-Z keeps far more depth precision in the distance, depth tests
+    // then use `depth_compare` and depth buffers are cleared to 0.
+    pub fn with_reverse_z(mut self, reverse_z: bool) -> Self {
+        self.reverse_z = reverse_z;
+        self
+    }
+
+    pub fn is_reverse_z(&self) -> bool {
+        self.reverse_z
+    }
+
+    pub fn depth_compare(&self) -> wgpu::CompareFunction {
+        match self.reverse_z {
+            true => wgpu::CompareFunction::Greater,
+            false => wgpu::CompareFunction::Less,
+        }
+    }
+
+    pub fn depth_clear_value(&self) -> f32 {
+        match self.reverse_z {
+            true => 0.0,
+            false => 1.0,
+        }
+    }
+
+    pub fn look_at(&mut self, target: Point3<f32>) {
+        let direction = target - self.position;
+        if direction.magnitude2() > 0.0 {
+            self.direction = direction.normalize();
+        }
+    }
+
+    pub fn set_aspect_ratio(&mut self, aspect_ratio: f32) {
+        self.projection.set_aspect_ratio(aspect_ratio);
+    }
+
+    // Sizes with a zero width or height, like a minimized window, are ignored.
+    pub fn resize(&mut self, size: PhysicalSize<u32>) {
+        if size.width == 0 || size.height == 0 {
+            return;
+        }
+        self.set_aspect_ratio(size.width as f32 / size.height as f32);
+    }
+
+    pub fn view_matrix(&self) -> Matrix4<f32> {
+        Matrix4::look_to_rh(self.position, self.direction, self.up)
+    }
+
+    pub fn projection_matrix(&self) -> Matrix4<f32> {
+        let projection_matrix = opengl_to_wgpu_conversion_matrix() * self.projection.opengl_matrix();
+        match self.reverse_z {
+            true => reverse_z_matrix() * projection_matrix,
+            false => projection_matrix,
+        }
+    }
+
+    pub fn view_projection_matrix(&self) -> Matrix4<f32> {
+        self.projection_matrix() * self.view_matrix()
+    }
+
+    pub fn inverse_view_projection_matrix(&self) -> Option<Matrix4<f32>> {
+        self.view_projection_matrix().invert()
+    }
+
+    // The ray through a point on screen in normalized device coordinates.
+    pub fn screen_ray(&self, ndc: Vector2<f32>) -> Option<Ray> {
+        let inverse_view_projection = self.inverse_view_projection_matrix()?;
+        Some(Ray::from_screen(ndc, &inverse_view_projection, self.reverse_z))
+    }
+
+    pub fn uniforms(&self) -> CameraUniforms {
+        CameraUniforms {
+            view_matrix: self.view_matrix().into(),
+            projection_matrix: self.projection_matrix().into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::{Deg, Vector4};
+
+    use super::*;
+
+    fn depth(camera: &Camera, distance: f32) -> f32 {
+        let clip = camera.view_projection_matrix() * Vector4::new(0.0, 0.0, -distance, 1.0);
+        clip.z / clip.w
+    }
+
+    #[test]
+    fn reverse_z_maps_near_to_one_and_far_to_zero() {
+        let camera = Camera::perspective(Deg(60.0), 1.0, 0.1, Some(100.0));
+        assert!(depth(&camera, 0.1).abs() < 1e-5);
+        assert!((depth(&camera, 100.0) - 1.0).abs() < 1e-5);
+
+        let camera = camera.with_reverse_z(true);
+        assert!((depth(&camera, 0.1) - 1.0).abs() < 1e-5);
+        assert!(depth(&camera, 100.0).abs() < 1e-5);
+        assert!(depth(&camera, 1.0) > depth(&camera, 10.0));
+        assert_eq!(camera.depth_compare(), wgpu::CompareFunction::Greater);
+        assert_eq!(camera.depth_clear_value(), 0.0);
+    }
+
+    #[test]
+    fn infinite_reverse_z_approaches_zero_in_the_distance() {
+        let camera = Camera::perspective(Deg(60.0), 1.0, 0.1, None).with_reverse_z(true);
+        assert!((depth(&camera, 0.1) - 1.0).abs() < 1e-5);
+        assert!(depth(&camera, 1.0e6) > 0.0);
+        assert!(depth(&camera, 1.0e6) < 1e-5);
+        assert!(depth(&camera, 1000.0) > depth(&camera, 10000.0));
+    }
+
+    #[test]
+    fn resize_ignores_zero_sizes() {
+        let mut camera = Camera::perspective(Deg(60.0), 1.0, 0.1, None);
+        camera.resize(PhysicalSize::new(800, 400));
+        let resized = camera.projection;
+        assert!(matches!(resized, Projection::Perspective { aspect_ratio, .. } if aspect_ratio == 2.0));
+
+        camera.resize(PhysicalSize::new(0, 400));
+        camera.resize(PhysicalSize::new(800, 0));
+        assert_eq!(camera.projection, resized);
+    }
+
+    #[test]
+    fn uniforms_match_the_wgsl_layout() {
+        assert_eq!(CameraUniforms::SIZE, 128);
+        assert_eq!(CameraUniforms::ALIGN, 16);
+        assert_eq!(CameraUniforms::UNIFORM_ALIGN, 16);
+
+        let camera = Camera::orthographic(2.0, 1.0, 0.0, 10.0).with_position(Point3::new(1.0, 2.0, 3.0));
+        let uniforms = camera.uniforms();
+        assert_eq!(Matrix4::from(uniforms.view_matrix), camera.view_matrix());
+        assert_eq!(bytemuck::bytes_of(&uniforms).len(), 128);
+    }
+}
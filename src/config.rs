@@ -1,6 +1,6 @@
 use std::error::Error;
 use std::fs::OpenOptions;
-use std::io::Read;
+use std::io::{self, Read, Write};
 use std::path::PathBuf;
 
 pub fn load_configuration<T: serde::de::DeserializeOwned>(file_path: PathBuf) -> Result<T, Box<dyn Error>> {
@@ -10,6 +10,18 @@ pub fn load_configuration<T: serde::de::DeserializeOwned>(file_path: PathBuf) ->
     Ok(result)
 }
 
+// Missing configuration files fall back to defaults, other errors are worth
+// reporting.
+pub fn is_not_found(error: &(dyn Error + 'static)) -> bool {
+    matches!(error.downcast_ref::<io::Error>(), Some(error) if error.kind() == io::ErrorKind::NotFound)
+}
+
+pub fn save_configuration<T: serde::Serialize>(file_path: PathBuf, configuration: &T) -> Result<(), Box<dyn Error>> {
+    let file_data = serde_json::to_string_pretty(configuration)?;
+
+    write_file(file_path, &file_data)
+}
+
 fn read_file(file_path: PathBuf) -> Result<String, Box<dyn Error>> {
     let mut file = OpenOptions::new()
         .read(true)
@@ -19,4 +31,16 @@ fn read_file(file_path: PathBuf) -> Result<String, Box<dyn Error>> {
     file.read_to_string(&mut buffer)?;
 
     Ok(buffer)
+}
+
+fn write_file(file_path: PathBuf, data: &str) -> Result<(), Box<dyn Error>> {
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(file_path)?;
+
+    file.write_all(data.as_bytes())?;
+
+    Ok(())
 }
\ No newline at end of file
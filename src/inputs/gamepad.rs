@@ -0,0 +1,340 @@
+use std::collections::{HashMap, VecDeque};
+
+use serde::{Deserialize, Serialize};
+use winit::event::ElementState;
+
+const DEFAULT_DEAD_ZONE: f32 = 0.1;
+
+pub type GamepadId = usize;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum GamepadButton {
+    South,
+    East,
+    North,
+    West,
+    LeftBumper,
+    RightBumper,
+    LeftTrigger,
+    RightTrigger,
+    Select,
+    Start,
+    Mode,
+    LeftStick,
+    RightStick,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum GamepadAxis {
+    LeftStickX,
+    LeftStickY,
+    RightStickX,
+    RightStickY,
+    LeftTrigger,
+    RightTrigger,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum GamepadEvent {
+    Connected(GamepadId),
+    Disconnected(GamepadId),
+    Button(GamepadId, GamepadButton, ElementState),
+    Axis(GamepadId, GamepadAxis, f32),
+}
+
+pub trait GamepadBackend {
+    fn poll_event(&mut self) -> Option<GamepadEvent>;
+}
+
+// Backend that hands out events pushed into it, used for fake devices and
+// for replaying recorded input.
+#[derive(Default)]
+pub struct QueuedGamepadBackend {
+    events: VecDeque<GamepadEvent>,
+}
+
+impl QueuedGamepadBackend {
+    pub fn new() -> Self {
+        Self {
+            events: VecDeque::new(),
+        }
+    }
+
+    pub fn push(&mut self, event: GamepadEvent) {
+        self.events.push_back(event);
+    }
+}
+
+impl GamepadBackend for QueuedGamepadBackend {
+    fn poll_event(&mut self) -> Option<GamepadEvent> {
+        self.events.pop_front()
+    }
+}
+
+#[derive(Default)]
+struct GamepadState {
+    // Disconnected gamepads are kept until the end of the frame so their
+    // released buttons can still be queried.
+    connected: bool,
+    pressed_buttons: Vec<GamepadButton>,
+    just_pressed_buttons: Vec<GamepadButton>,
+    just_released_buttons: Vec<GamepadButton>,
+    axes: HashMap<GamepadAxis, f32>,
+}
+
+pub struct Gamepad {
+    gamepads: HashMap<GamepadId, GamepadState>,
+    just_connected: Vec<GamepadId>,
+    just_disconnected: Vec<GamepadId>,
+    dead_zone: f32,
+    axis_dead_zones: HashMap<GamepadAxis, f32>,
+}
+
+impl Gamepad {
+    pub fn new() -> Self {
+        Self {
+            gamepads: HashMap::new(),
+            just_connected: vec![],
+            just_disconnected: vec![],
+            dead_zone: DEFAULT_DEAD_ZONE,
+            axis_dead_zones: HashMap::new(),
+        }
+    }
+
+    pub fn set_dead_zone(&mut self, dead_zone: f32) {
+        self.dead_zone = dead_zone.clamp(0.0, 0.99);
+    }
+
+    pub fn set_axis_dead_zone(&mut self, axis: GamepadAxis, dead_zone: f32) {
+        self.axis_dead_zones.insert(axis, dead_zone.clamp(0.0, 0.99));
+    }
+
+    // Events polled here are not recorded, use `Input::poll_gamepads` when
+    // the gamepad is part of an `Input`.
+    pub fn poll(&mut self, backend: &mut dyn GamepadBackend) {
+        while let Some(event) = backend.poll_event() {
+            self.handle_event(event);
+        }
+    }
+
+    pub fn handle_event(&mut self, event: GamepadEvent) {
+        match event {
+            GamepadEvent::Connected(id) => {
+                self.connected_gamepad_mut(id);
+            },
+            GamepadEvent::Disconnected(id) => {
+                if let Some(gamepad) = self.gamepads.get_mut(&id).filter(|gamepad| gamepad.connected) {
+                    gamepad.connected = false;
+                    gamepad.axes.clear();
+                    let released_buttons: Vec<GamepadButton> = gamepad.pressed_buttons.drain(..).collect();
+                    gamepad.just_released_buttons.extend(released_buttons);
+                    self.just_disconnected.push(id);
+                }
+            },
+            GamepadEvent::Button(id, button, state) => {
+                let gamepad = self.connected_gamepad_mut(id);
+                if state == ElementState::Pressed {
+                    if gamepad.pressed_buttons.contains(&button) {
+                        return;
+                    }
+
+                    gamepad.pressed_buttons.push(button);
+                    gamepad.just_pressed_buttons.push(button);
+                }
+                if state == ElementState::Released {
+                    if let Some(index) = gamepad.pressed_buttons.iter().position(|pressed_button| pressed_button == &button) {
+                        gamepad.pressed_buttons.remove(index);
+                        gamepad.just_released_buttons.push(button);
+                    }
+                }
+            },
+            GamepadEvent::Axis(id, axis, value) => {
+                let gamepad = self.connected_gamepad_mut(id);
+                gamepad.axes.insert(axis, value.clamp(-1.0, 1.0));
+            },
+        }
+    }
+
+    // Backends may send input before announcing a gamepad, that counts as
+    // connecting it.
+    fn connected_gamepad_mut(&mut self, id: GamepadId) -> &mut GamepadState {
+        let gamepad = self.gamepads.entry(id).or_default();
+        if !gamepad.connected {
+            gamepad.connected = true;
+            self.just_connected.push(id);
+        }
+        gamepad
+    }
+
+    pub fn end_frame(&mut self) {
+        self.just_connected.clear();
+        self.just_disconnected.clear();
+        self.gamepads.retain(|_, gamepad| gamepad.connected);
+
+        for gamepad in self.gamepads.values_mut() {
+            gamepad.just_pressed_buttons.clear();
+            gamepad.just_released_buttons.clear();
+        }
+    }
+
+    pub fn connected_gamepads(&self) -> impl Iterator<Item = GamepadId> + '_ {
+        self.gamepads
+            .iter()
+            .filter(|(_, gamepad)| gamepad.connected)
+            .map(|(id, _)| *id)
+    }
+
+    pub fn is_connected(&self, id: GamepadId) -> bool {
+        self.gamepads.get(&id).map_or(false, |gamepad| gamepad.connected)
+    }
+
+    pub fn just_connected(&self) -> &[GamepadId] {
+        &self.just_connected
+    }
+
+    pub fn just_disconnected(&self) -> &[GamepadId] {
+        &self.just_disconnected
+    }
+
+    pub fn is_button_pressed(&self, id: GamepadId, button: GamepadButton) -> bool {
+        self.gamepads
+            .get(&id)
+            .map_or(false, |gamepad| gamepad.pressed_buttons.contains(&button))
+    }
+
+    pub fn just_pressed(&self, id: GamepadId, button: GamepadButton) -> bool {
+        self.gamepads
+            .get(&id)
+            .map_or(false, |gamepad| gamepad.just_pressed_buttons.contains(&button))
+    }
+
+    pub fn just_released(&self, id: GamepadId, button: GamepadButton) -> bool {
+        self.gamepads
+            .get(&id)
+            .map_or(false, |gamepad| gamepad.just_released_buttons.contains(&button))
+    }
+
+    pub fn raw_axis(&self, id: GamepadId, axis: GamepadAxis) -> f32 {
+        self.gamepads
+            .get(&id)
+            .and_then(|gamepad| gamepad.axes.get(&axis))
+            .copied()
+            .unwrap_or(0.0)
+    }
+
+    // Values inside the dead zone read as zero, the rest is rescaled so the
+    // output still covers the full -1..=1 range.
+    pub fn axis(&self, id: GamepadId, axis: GamepadAxis) -> f32 {
+        let value = self.raw_axis(id, axis);
+        let dead_zone = self.axis_dead_zones.get(&axis).copied().unwrap_or(self.dead_zone);
+
+        if value.abs() <= dead_zone {
+            return 0.0;
+        }
+
+        value.signum() * (value.abs() - dead_zone) / (1.0 - dead_zone)
+    }
+}
+
+impl Default for Gamepad {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn poll(gamepad: &mut Gamepad, events: &[GamepadEvent]) {
+        let mut backend = QueuedGamepadBackend::new();
+        for event in events {
+            backend.push(*event);
+        }
+        gamepad.poll(&mut backend);
+    }
+
+    #[test]
+    fn buttons_have_press_and_release_edges() {
+        let mut gamepad = Gamepad::new();
+        poll(&mut gamepad, &[
+            GamepadEvent::Connected(0),
+            GamepadEvent::Button(0, GamepadButton::South, ElementState::Pressed),
+        ]);
+        assert_eq!(gamepad.just_connected(), &[0]);
+        assert!(gamepad.just_pressed(0, GamepadButton::South));
+        assert!(gamepad.is_button_pressed(0, GamepadButton::South));
+
+        gamepad.end_frame();
+        poll(&mut gamepad, &[GamepadEvent::Button(0, GamepadButton::South, ElementState::Released)]);
+        assert!(gamepad.just_connected().is_empty());
+        assert!(!gamepad.just_pressed(0, GamepadButton::South));
+        assert!(gamepad.just_released(0, GamepadButton::South));
+        assert!(!gamepad.is_button_pressed(0, GamepadButton::South));
+    }
+
+    #[test]
+    fn input_from_an_unknown_gamepad_connects_it() {
+        let mut gamepad = Gamepad::new();
+        poll(&mut gamepad, &[GamepadEvent::Axis(3, GamepadAxis::LeftStickX, 0.5)]);
+        assert!(gamepad.is_connected(3));
+        assert_eq!(gamepad.just_connected(), &[3]);
+        assert_eq!(gamepad.raw_axis(3, GamepadAxis::LeftStickX), 0.5);
+    }
+
+    #[test]
+    fn disconnecting_releases_held_buttons() {
+        let mut gamepad = Gamepad::new();
+        poll(&mut gamepad, &[
+            GamepadEvent::Connected(0),
+            GamepadEvent::Button(0, GamepadButton::East, ElementState::Pressed),
+            GamepadEvent::Axis(0, GamepadAxis::RightTrigger, 1.0),
+        ]);
+        gamepad.end_frame();
+
+        poll(&mut gamepad, &[GamepadEvent::Disconnected(0)]);
+        assert!(!gamepad.is_connected(0));
+        assert_eq!(gamepad.just_disconnected(), &[0]);
+        assert!(gamepad.just_released(0, GamepadButton::East));
+        assert!(!gamepad.is_button_pressed(0, GamepadButton::East));
+        assert_eq!(gamepad.raw_axis(0, GamepadAxis::RightTrigger), 0.0);
+        assert_eq!(gamepad.connected_gamepads().count(), 0);
+
+        gamepad.end_frame();
+        assert!(!gamepad.just_released(0, GamepadButton::East));
+        assert!(gamepad.just_disconnected().is_empty());
+    }
+
+    #[test]
+    fn reconnecting_within_a_frame_reports_both_edges() {
+        let mut gamepad = Gamepad::new();
+        poll(&mut gamepad, &[GamepadEvent::Connected(0)]);
+        gamepad.end_frame();
+
+        poll(&mut gamepad, &[GamepadEvent::Disconnected(0), GamepadEvent::Connected(0)]);
+        assert!(gamepad.is_connected(0));
+        assert_eq!(gamepad.just_disconnected(), &[0]);
+        assert_eq!(gamepad.just_connected(), &[0]);
+
+        gamepad.end_frame();
+        assert!(gamepad.is_connected(0));
+    }
+
+    #[test]
+    fn axes_are_rescaled_outside_the_dead_zone() {
+        let mut gamepad = Gamepad::new();
+        gamepad.set_dead_zone(0.2);
+        poll(&mut gamepad, &[
+            GamepadEvent::Axis(0, GamepadAxis::LeftStickX, 0.1),
+            GamepadEvent::Axis(0, GamepadAxis::LeftStickY, -0.6),
+            GamepadEvent::Axis(0, GamepadAxis::RightStickX, 2.0),
+        ]);
+        assert_eq!(gamepad.axis(0, GamepadAxis::LeftStickX), 0.0);
+        assert!((gamepad.axis(0, GamepadAxis::LeftStickY) + 0.5).abs() < 1e-6);
+        assert_eq!(gamepad.axis(0, GamepadAxis::RightStickX), 1.0);
+    }
+}
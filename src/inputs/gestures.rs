@@ -0,0 +1,593 @@
+use std::time::{Duration, Instant};
+
+use cgmath::{Rad, Vector2};
+use winit::dpi::PhysicalPosition;
+use winit::event::TouchPhase;
+
+use super::{FingerId, TouchEvent};
+
+const TAP_MAX_DURATION: Duration = Duration::from_millis(300);
+const TAP_MAX_DISTANCE: f64 = 10.0;
+const DOUBLE_TAP_MAX_INTERVAL: Duration = Duration::from_millis(300);
+const DOUBLE_TAP_MAX_DISTANCE: f64 = 30.0;
+const DRAG_THRESHOLD: f64 = 10.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GesturePhase {
+    Started,
+    Changed,
+    Ended,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Gesture {
+    Tap { position: PhysicalPosition<f64> },
+    DoubleTap { position: PhysicalPosition<f64> },
+    Drag { phase: GesturePhase, position: PhysicalPosition<f64>, delta: Vector2<f64> },
+    // `scale` is the finger distance relative to when the pinch started.
+    Pinch { phase: GesturePhase, center: PhysicalPosition<f64>, scale: f64 },
+    // `angle` is the rotation since the gesture started, counter-clockwise in screen space.
+    Rotate { phase: GesturePhase, center: PhysicalPosition<f64>, angle: Rad<f64> },
+}
+
+// Recognizers are plain state machines over touch events, they keep track of
+// the fingers they care about themselves and never look at the window.
+pub trait GestureRecognizer {
+    fn handle_event(&mut self, event: &TouchEvent) -> Option<Gesture>;
+}
+
+struct TapCandidate {
+    id: FingerId,
+    position: PhysicalPosition<f64>,
+    started_at: Instant,
+}
+
+pub struct TapRecognizer {
+    max_duration: Duration,
+    max_distance: f64,
+    active_fingers: usize,
+    candidate: Option<TapCandidate>,
+}
+
+impl TapRecognizer {
+    pub fn new() -> Self {
+        Self {
+            max_duration: TAP_MAX_DURATION,
+            max_distance: TAP_MAX_DISTANCE,
+            active_fingers: 0,
+            candidate: None,
+        }
+    }
+
+    pub fn with_max_duration(mut self, max_duration: Duration) -> Self {
+        self.max_duration = max_duration;
+        self
+    }
+
+    pub fn with_max_distance(mut self, max_distance: f64) -> Self {
+        self.max_distance = max_distance;
+        self
+    }
+}
+
+impl Default for TapRecognizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GestureRecognizer for TapRecognizer {
+    fn handle_event(&mut self, event: &TouchEvent) -> Option<Gesture> {
+        match event.phase {
+            TouchPhase::Started => {
+                self.active_fingers += 1;
+                self.candidate = match self.active_fingers {
+                    1 => Some(TapCandidate {
+                        id: event.id,
+                        position: event.position,
+                        started_at: event.time,
+                    }),
+                    _ => None,
+                };
+                None
+            },
+            TouchPhase::Moved => {
+                let moved_too_far = self.candidate
+                    .as_ref()
+                    .map_or(false, |candidate| candidate.id == event.id && distance(candidate.position, event.position) > self.max_distance);
+                if moved_too_far {
+                    self.candidate = None;
+                }
+                None
+            },
+            TouchPhase::Ended | TouchPhase::Cancelled => {
+                self.active_fingers = self.active_fingers.saturating_sub(1);
+
+                let candidate = self.candidate.take()?;
+                if candidate.id != event.id || event.phase == TouchPhase::Cancelled {
+                    return None;
+                }
+                if event.time.saturating_duration_since(candidate.started_at) > self.max_duration {
+                    return None;
+                }
+
+                Some(Gesture::Tap { position: candidate.position })
+            },
+        }
+    }
+}
+
+pub struct DoubleTapRecognizer {
+    tap: TapRecognizer,
+    max_interval: Duration,
+    max_distance: f64,
+    last_tap: Option<(PhysicalPosition<f64>, Instant)>,
+}
+
+impl DoubleTapRecognizer {
+    pub fn new() -> Self {
+        Self {
+            tap: TapRecognizer::new(),
+            max_interval: DOUBLE_TAP_MAX_INTERVAL,
+            max_distance: DOUBLE_TAP_MAX_DISTANCE,
+            last_tap: None,
+        }
+    }
+
+    pub fn with_max_interval(mut self, max_interval: Duration) -> Self {
+        self.max_interval = max_interval;
+        self
+    }
+
+    pub fn with_max_distance(mut self, max_distance: f64) -> Self {
+        self.max_distance = max_distance;
+        self
+    }
+}
+
+impl Default for DoubleTapRecognizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GestureRecognizer for DoubleTapRecognizer {
+    fn handle_event(&mut self, event: &TouchEvent) -> Option<Gesture> {
+        let position = match self.tap.handle_event(event)? {
+            Gesture::Tap { position } => position,
+            _ => return None,
+        };
+
+        if let Some((last_position, last_time)) = self.last_tap.take() {
+            let interval = event.time.saturating_duration_since(last_time);
+            if interval <= self.max_interval && distance(last_position, position) <= self.max_distance {
+                return Some(Gesture::DoubleTap { position });
+            }
+        }
+
+        self.last_tap = Some((position, event.time));
+        None
+    }
+}
+
+struct DragCandidate {
+    id: FingerId,
+    start_position: PhysicalPosition<f64>,
+    last_position: PhysicalPosition<f64>,
+    dragging: bool,
+}
+
+pub struct DragRecognizer {
+    threshold: f64,
+    active_fingers: usize,
+    candidate: Option<DragCandidate>,
+}
+
+impl DragRecognizer {
+    pub fn new() -> Self {
+        Self {
+            threshold: DRAG_THRESHOLD,
+            active_fingers: 0,
+            candidate: None,
+        }
+    }
+
+    pub fn with_threshold(mut self, threshold: f64) -> Self {
+        self.threshold = threshold;
+        self
+    }
+}
+
+impl Default for DragRecognizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GestureRecognizer for DragRecognizer {
+    fn handle_event(&mut self, event: &TouchEvent) -> Option<Gesture> {
+        match event.phase {
+            TouchPhase::Started => {
+                self.active_fingers += 1;
+                if self.active_fingers == 1 {
+                    self.candidate = Some(DragCandidate {
+                        id: event.id,
+                        start_position: event.position,
+                        last_position: event.position,
+                        dragging: false,
+                    });
+                    return None;
+                }
+
+                // A second finger turns the drag into a pinch or rotate.
+                let candidate = self.candidate.take()?;
+                if candidate.dragging {
+                    return Some(Gesture::Drag {
+                        phase: GesturePhase::Ended,
+                        position: candidate.last_position,
+                        delta: Vector2::new(0.0, 0.0),
+                    });
+                }
+                None
+            },
+            TouchPhase::Moved => {
+                let threshold = self.threshold;
+                let candidate = self.candidate.as_mut().filter(|candidate| candidate.id == event.id)?;
+
+                let delta = Vector2::new(
+                    event.position.x - candidate.last_position.x,
+                    event.position.y - candidate.last_position.y,
+                );
+
+                if !candidate.dragging {
+                    if distance(candidate.start_position, event.position) <= threshold {
+                        return None;
+                    }
+
+                    candidate.dragging = true;
+                    candidate.last_position = event.position;
+                    return Some(Gesture::Drag {
+                        phase: GesturePhase::Started,
+                        position: event.position,
+                        delta: Vector2::new(
+                            event.position.x - candidate.start_position.x,
+                            event.position.y - candidate.start_position.y,
+                        ),
+                    });
+                }
+
+                candidate.last_position = event.position;
+                Some(Gesture::Drag {
+                    phase: GesturePhase::Changed,
+                    position: event.position,
+                    delta,
+                })
+            },
+            TouchPhase::Ended | TouchPhase::Cancelled => {
+                self.active_fingers = self.active_fingers.saturating_sub(1);
+
+                let is_candidate = self.candidate.as_ref().map_or(false, |candidate| candidate.id == event.id);
+                if !is_candidate {
+                    return None;
+                }
+
+                let candidate = self.candidate.take()?;
+                if !candidate.dragging {
+                    return None;
+                }
+
+                Some(Gesture::Drag {
+                    phase: GesturePhase::Ended,
+                    position: event.position,
+                    delta: Vector2::new(
+                        event.position.x - candidate.last_position.x,
+                        event.position.y - candidate.last_position.y,
+                    ),
+                })
+            },
+        }
+    }
+}
+
+// Tracks the first two fingers on the screen for the two finger gestures.
+#[derive(Default)]
+struct FingerPair {
+    fingers: Vec<(FingerId, PhysicalPosition<f64>)>,
+    active: bool,
+}
+
+enum FingerPairUpdate {
+    Started,
+    Changed,
+    Ended,
+}
+
+impl FingerPair {
+    fn handle_event(&mut self, event: &TouchEvent) -> Option<FingerPairUpdate> {
+        let index = self.fingers.iter().position(|(id, _)| *id == event.id);
+
+        match event.phase {
+            TouchPhase::Started => {
+                if index.is_some() || self.fingers.len() >= 2 {
+                    return None;
+                }
+
+                self.fingers.push((event.id, event.position));
+                if self.fingers.len() == 2 {
+                    self.active = true;
+                    return Some(FingerPairUpdate::Started);
+                }
+                None
+            },
+            TouchPhase::Moved => {
+                self.fingers[index?].1 = event.position;
+                if self.active {
+                    return Some(FingerPairUpdate::Changed);
+                }
+                None
+            },
+            TouchPhase::Ended | TouchPhase::Cancelled => {
+                self.fingers[index?].1 = event.position;
+                let was_active = self.active;
+                self.active = false;
+
+                if was_active {
+                    return Some(FingerPairUpdate::Ended);
+                }
+
+                self.fingers.remove(index?);
+                None
+            },
+        }
+    }
+
+    // Called once an ended update has been reported.
+    fn remove_released(&mut self, id: FingerId) {
+        self.fingers.retain(|(finger_id, _)| *finger_id != id);
+    }
+
+    fn center(&self) -> PhysicalPosition<f64> {
+        let (a, b) = (self.fingers[0].1, self.fingers[1].1);
+        PhysicalPosition::new((a.x + b.x) / 2.0, (a.y + b.y) / 2.0)
+    }
+
+    fn distance(&self) -> f64 {
+        distance(self.fingers[0].1, self.fingers[1].1)
+    }
+
+    fn angle(&self) -> f64 {
+        let (a, b) = (self.fingers[0].1, self.fingers[1].1);
+        // Screen space y grows downwards, flip it so positive angles are counter-clockwise.
+        (a.y - b.y).atan2(b.x - a.x)
+    }
+}
+
+#[derive(Default)]
+pub struct PinchRecognizer {
+    pair: FingerPair,
+    start_distance: f64,
+}
+
+impl PinchRecognizer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl GestureRecognizer for PinchRecognizer {
+    fn handle_event(&mut self, event: &TouchEvent) -> Option<Gesture> {
+        let update = self.pair.handle_event(event)?;
+
+        if let FingerPairUpdate::Started = update {
+            self.start_distance = self.pair.distance();
+        }
+
+        let scale = match self.start_distance > 0.0 {
+            true => self.pair.distance() / self.start_distance,
+            false => 1.0,
+        };
+        let phase = phase_from_update(&update);
+        let center = self.pair.center();
+
+        if let FingerPairUpdate::Ended = update {
+            self.pair.remove_released(event.id);
+        }
+
+        Some(Gesture::Pinch { phase, center, scale })
+    }
+}
+
+#[derive(Default)]
+pub struct RotateRecognizer {
+    pair: FingerPair,
+    start_angle: f64,
+}
+
+impl RotateRecognizer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl GestureRecognizer for RotateRecognizer {
+    fn handle_event(&mut self, event: &TouchEvent) -> Option<Gesture> {
+        let update = self.pair.handle_event(event)?;
+
+        if let FingerPairUpdate::Started = update {
+            self.start_angle = self.pair.angle();
+        }
+
+        let angle = wrap_angle(self.pair.angle() - self.start_angle);
+        let phase = phase_from_update(&update);
+        let center = self.pair.center();
+
+        if let FingerPairUpdate::Ended = update {
+            self.pair.remove_released(event.id);
+        }
+
+        Some(Gesture::Rotate { phase, center, angle: Rad(angle) })
+    }
+}
+
+fn phase_from_update(update: &FingerPairUpdate) -> GesturePhase {
+    match update {
+        FingerPairUpdate::Started => GesturePhase::Started,
+        FingerPairUpdate::Changed => GesturePhase::Changed,
+        FingerPairUpdate::Ended => GesturePhase::Ended,
+    }
+}
+
+fn wrap_angle(angle: f64) -> f64 {
+    let full_turn = std::f64::consts::PI * 2.0;
+    (angle + std::f64::consts::PI).rem_euclid(full_turn) - std::f64::consts::PI
+}
+
+fn distance(a: PhysicalPosition<f64>, b: PhysicalPosition<f64>) -> f64 {
+    ((b.x - a.x).powi(2) + (b.y - a.y).powi(2)).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::window::{Clock, ManualClock};
+
+    fn event(clock: &ManualClock, id: FingerId, phase: TouchPhase, x: f64, y: f64) -> TouchEvent {
+        TouchEvent {
+            id,
+            phase,
+            position: PhysicalPosition::new(x, y),
+            time: clock.now(),
+        }
+    }
+
+    fn feed<R: GestureRecognizer>(recognizer: &mut R, events: &[TouchEvent]) -> Vec<Gesture> {
+        events.iter().filter_map(|event| recognizer.handle_event(event)).collect()
+    }
+
+    #[test]
+    fn short_press_is_a_tap_and_long_press_is_not() {
+        let clock = ManualClock::new();
+        let mut recognizer = TapRecognizer::new();
+
+        let started = event(&clock, 0, TouchPhase::Started, 5.0, 5.0);
+        clock.advance(TAP_MAX_DURATION);
+        let ended = event(&clock, 0, TouchPhase::Ended, 6.0, 5.0);
+        assert_eq!(feed(&mut recognizer, &[started, ended]), vec![Gesture::Tap { position: PhysicalPosition::new(5.0, 5.0) }]);
+
+        let started = event(&clock, 1, TouchPhase::Started, 5.0, 5.0);
+        clock.advance(TAP_MAX_DURATION + Duration::from_millis(1));
+        let ended = event(&clock, 1, TouchPhase::Ended, 5.0, 5.0);
+        assert!(feed(&mut recognizer, &[started, ended]).is_empty());
+    }
+
+    #[test]
+    fn moving_or_adding_a_finger_cancels_a_tap() {
+        let clock = ManualClock::new();
+        let mut recognizer = TapRecognizer::new();
+
+        let events = [
+            event(&clock, 0, TouchPhase::Started, 0.0, 0.0),
+            event(&clock, 0, TouchPhase::Moved, 20.0, 0.0),
+            event(&clock, 0, TouchPhase::Ended, 20.0, 0.0),
+            event(&clock, 1, TouchPhase::Started, 0.0, 0.0),
+            event(&clock, 2, TouchPhase::Started, 50.0, 0.0),
+            event(&clock, 2, TouchPhase::Ended, 50.0, 0.0),
+            event(&clock, 1, TouchPhase::Ended, 0.0, 0.0),
+        ];
+        assert!(feed(&mut recognizer, &events).is_empty());
+    }
+
+    #[test]
+    fn double_tap_needs_the_second_tap_within_the_interval() {
+        let clock = ManualClock::new();
+        let mut recognizer = DoubleTapRecognizer::new();
+
+        let mut tap = |id: FingerId, pause: Duration| {
+            clock.advance(pause);
+            let started = event(&clock, id, TouchPhase::Started, 10.0, 10.0);
+            clock.advance(Duration::from_millis(50));
+            let ended = event(&clock, id, TouchPhase::Ended, 10.0, 10.0);
+            feed(&mut recognizer, &[started, ended])
+        };
+
+        assert!(tap(0, Duration::from_millis(0)).is_empty());
+        assert!(tap(1, DOUBLE_TAP_MAX_INTERVAL + Duration::from_millis(100)).is_empty());
+        assert_eq!(tap(2, Duration::from_millis(100)), vec![Gesture::DoubleTap { position: PhysicalPosition::new(10.0, 10.0) }]);
+        // The pair is used up, a third tap starts over.
+        assert!(tap(3, Duration::from_millis(100)).is_empty());
+    }
+
+    #[test]
+    fn drag_starts_past_the_threshold_and_reports_deltas() {
+        let clock = ManualClock::new();
+        let mut recognizer = DragRecognizer::new();
+
+        let gestures = feed(&mut recognizer, &[
+            event(&clock, 0, TouchPhase::Started, 0.0, 0.0),
+            event(&clock, 0, TouchPhase::Moved, 5.0, 0.0),
+            event(&clock, 0, TouchPhase::Moved, 15.0, 0.0),
+            event(&clock, 0, TouchPhase::Moved, 20.0, 5.0),
+            event(&clock, 0, TouchPhase::Ended, 20.0, 5.0),
+        ]);
+        assert_eq!(gestures, vec![
+            Gesture::Drag { phase: GesturePhase::Started, position: PhysicalPosition::new(15.0, 0.0), delta: Vector2::new(15.0, 0.0) },
+            Gesture::Drag { phase: GesturePhase::Changed, position: PhysicalPosition::new(20.0, 5.0), delta: Vector2::new(5.0, 5.0) },
+            Gesture::Drag { phase: GesturePhase::Ended, position: PhysicalPosition::new(20.0, 5.0), delta: Vector2::new(0.0, 0.0) },
+        ]);
+    }
+
+    #[test]
+    fn second_finger_ends_a_drag() {
+        let clock = ManualClock::new();
+        let mut recognizer = DragRecognizer::new();
+
+        let gestures = feed(&mut recognizer, &[
+            event(&clock, 0, TouchPhase::Started, 0.0, 0.0),
+            event(&clock, 0, TouchPhase::Moved, 30.0, 0.0),
+            event(&clock, 1, TouchPhase::Started, 100.0, 0.0),
+            event(&clock, 0, TouchPhase::Moved, 40.0, 0.0),
+            event(&clock, 0, TouchPhase::Ended, 40.0, 0.0),
+            event(&clock, 1, TouchPhase::Ended, 100.0, 0.0),
+        ]);
+        assert_eq!(gestures.len(), 2);
+        assert_eq!(gestures[1], Gesture::Drag { phase: GesturePhase::Ended, position: PhysicalPosition::new(30.0, 0.0), delta: Vector2::new(0.0, 0.0) });
+    }
+
+    #[test]
+    fn pinch_scale_is_relative_to_the_start_distance() {
+        let clock = ManualClock::new();
+        let mut recognizer = PinchRecognizer::new();
+
+        let gestures = feed(&mut recognizer, &[
+            event(&clock, 0, TouchPhase::Started, 0.0, 0.0),
+            event(&clock, 1, TouchPhase::Started, 100.0, 0.0),
+            event(&clock, 1, TouchPhase::Moved, 200.0, 0.0),
+            event(&clock, 0, TouchPhase::Ended, 50.0, 0.0),
+        ]);
+        assert_eq!(gestures, vec![
+            Gesture::Pinch { phase: GesturePhase::Started, center: PhysicalPosition::new(50.0, 0.0), scale: 1.0 },
+            Gesture::Pinch { phase: GesturePhase::Changed, center: PhysicalPosition::new(100.0, 0.0), scale: 2.0 },
+            Gesture::Pinch { phase: GesturePhase::Ended, center: PhysicalPosition::new(125.0, 0.0), scale: 1.5 },
+        ]);
+    }
+
+    #[test]
+    fn rotate_angle_is_counter_clockwise_on_screen() {
+        let clock = ManualClock::new();
+        let mut recognizer = RotateRecognizer::new();
+
+        let gestures = feed(&mut recognizer, &[
+            event(&clock, 0, TouchPhase::Started, 0.0, 0.0),
+            event(&clock, 1, TouchPhase::Started, 100.0, 0.0),
+            // Up on screen is negative y.
+            event(&clock, 1, TouchPhase::Moved, 0.0, -100.0),
+        ]);
+        match gestures[1] {
+            Gesture::Rotate { phase, angle, .. } => {
+                assert_eq!(phase, GesturePhase::Changed);
+                assert!((angle.0 - std::f64::consts::FRAC_PI_2).abs() < 1e-9);
+            },
+            gesture => panic!("expected a rotation, got {:?}", gesture),
+        }
+    }
+}
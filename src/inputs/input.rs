@@ -0,0 +1,205 @@
+use winit::event::{DeviceEvent, WindowEvent};
+
+use crate::window::{Clock, SystemClock};
+use super::{AxisBinding, Binding, Gamepad, GamepadBackend, InputEvent, InputMap, InputRecorder, InputRecording, Keyboard, Mouse, TextInput, Touch, TouchEvent};
+
+pub struct Input<C: Clock = SystemClock> {
+    clock: C,
+    pub keyboard: Keyboard<C>,
+    pub mouse: Mouse,
+    pub gamepad: Gamepad,
+    pub text: TextInput,
+    pub touch: Touch,
+    pub map: InputMap,
+    recorder: Option<InputRecorder>,
+}
+
+impl Input {
+    pub fn new(map: InputMap) -> Self {
+        Self::with_clock(SystemClock, map)
+    }
+}
+
+impl Default for Input {
+    fn default() -> Self {
+        Self::new(InputMap::new())
+    }
+}
+
+impl<C: Clock + Clone> Input<C> {
+    pub fn with_clock(clock: C, map: InputMap) -> Self {
+        Self {
+            keyboard: Keyboard::with_clock(clock.clone()),
+            clock,
+            mouse: Mouse::new(),
+            gamepad: Gamepad::new(),
+            text: TextInput::new(),
+            touch: Touch::new(),
+            map,
+            recorder: None,
+        }
+    }
+}
+
+impl<C: Clock> Input<C> {
+    pub fn handle_event(&mut self, event: InputEvent) {
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.record(event);
+        }
+
+        match event {
+            InputEvent::Key(keycode, state) => {
+                self.keyboard.handle_key(keycode, state);
+                self.text.handle_key(keycode, state, self.keyboard.modifiers());
+            },
+            InputEvent::Character(character) => self.text.handle_character(character, self.keyboard.modifiers()),
+            InputEvent::ModifiersChanged(modifiers) => self.keyboard.set_modifiers(modifiers),
+            InputEvent::MouseButton(button, state) => self.mouse.handle_input(button, state),
+            InputEvent::CursorMoved(position) => self.mouse.handle_cursor_moved(position),
+            InputEvent::CursorLeft => self.mouse.handle_cursor_left(),
+            InputEvent::MouseMotion(x, y) => self.mouse.handle_motion((x, y)),
+            InputEvent::MouseScrolled(delta) => self.mouse.handle_scroll(delta),
+            InputEvent::ScaleFactorChanged(scale_factor) => self.mouse.set_scale_factor(scale_factor),
+            InputEvent::Touch(id, phase, position) => self.touch.handle_event(TouchEvent {
+                id,
+                phase,
+                position,
+                time: self.clock.now(),
+            }),
+            InputEvent::Gamepad(event) => self.gamepad.handle_event(event),
+        }
+    }
+
+    pub fn handle_window_event(&mut self, event: &WindowEvent) {
+        if let Some(event) = InputEvent::from_window_event(event) {
+            self.handle_event(event);
+        }
+    }
+
+    pub fn handle_device_event(&mut self, event: &DeviceEvent) {
+        if let Some(event) = InputEvent::from_device_event(event) {
+            self.handle_event(event);
+        }
+    }
+
+    // Gamepads are polled through here rather than `Gamepad::poll` so their
+    // events are recorded like every other input.
+    pub fn poll_gamepads(&mut self, backend: &mut dyn GamepadBackend) {
+        while let Some(event) = backend.poll_event() {
+            self.handle_event(InputEvent::Gamepad(event));
+        }
+    }
+
+    pub fn start_recording(&mut self) {
+        self.recorder = Some(InputRecorder::new());
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recorder.is_some()
+    }
+
+    pub fn stop_recording(&mut self) -> Option<InputRecording> {
+        self.recorder.take().map(|recorder| recorder.finish())
+    }
+
+    pub fn end_frame(&mut self) {
+        self.keyboard.end_frame();
+        self.mouse.end_frame();
+        self.gamepad.end_frame();
+        self.text.end_frame();
+        self.touch.end_frame();
+
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.end_frame();
+        }
+    }
+
+    pub fn action_pressed(&self, action: &str) -> bool {
+        self.map
+            .action_bindings(action)
+            .iter()
+            .any(|binding| self.is_binding_pressed(*binding))
+    }
+
+    pub fn action_just_pressed(&self, action: &str) -> bool {
+        self.map
+            .action_bindings(action)
+            .iter()
+            .any(|binding| self.is_binding_just_pressed(*binding))
+    }
+
+    pub fn action_just_released(&self, action: &str) -> bool {
+        self.map
+            .action_bindings(action)
+            .iter()
+            .any(|binding| self.is_binding_just_released(*binding))
+    }
+
+    pub fn axis(&self, axis: &str) -> f32 {
+        let value: f32 = self.map
+            .axis_bindings(axis)
+            .iter()
+            .map(|binding| self.axis_binding_value(*binding))
+            .sum();
+
+        value.clamp(-1.0, 1.0)
+    }
+
+    fn is_binding_pressed(&self, binding: Binding) -> bool {
+        match binding {
+            Binding::Key(key) => self.keyboard.is_key_pressed(key),
+            Binding::Mouse(button) => self.mouse.is_button_pressed(button),
+            Binding::GamepadButton(button) => self.gamepad
+                .connected_gamepads()
+                .any(|id| self.gamepad.is_button_pressed(id, button)),
+        }
+    }
+
+    fn is_binding_just_pressed(&self, binding: Binding) -> bool {
+        match binding {
+            Binding::Key(key) => self.keyboard.just_pressed(key),
+            Binding::Mouse(button) => self.mouse.just_pressed(button),
+            Binding::GamepadButton(button) => self.gamepad
+                .connected_gamepads()
+                .any(|id| self.gamepad.just_pressed(id, button)),
+        }
+    }
+
+    fn is_binding_just_released(&self, binding: Binding) -> bool {
+        match binding {
+            Binding::Key(key) => self.keyboard.just_released(key),
+            Binding::Mouse(button) => self.mouse.just_released(button),
+            Binding::GamepadButton(button) => self.gamepad
+                .connected_gamepads()
+                .any(|id| self.gamepad.just_released(id, button)),
+        }
+    }
+
+    fn axis_binding_value(&self, binding: AxisBinding) -> f32 {
+        match binding {
+            AxisBinding::Keys { negative, positive } => {
+                direction(self.keyboard.is_key_pressed(negative), self.keyboard.is_key_pressed(positive))
+            },
+            AxisBinding::GamepadButtons { negative, positive } => {
+                let negative = self.is_binding_pressed(Binding::GamepadButton(negative));
+                let positive = self.is_binding_pressed(Binding::GamepadButton(positive));
+                direction(negative, positive)
+            },
+            AxisBinding::GamepadAxis(axis) => self.gamepad
+                .connected_gamepads()
+                .map(|id| self.gamepad.axis(id, axis))
+                .fold(0.0, |strongest: f32, value| if value.abs() > strongest.abs() { value } else { strongest }),
+        }
+    }
+}
+
+fn direction(negative: bool, positive: bool) -> f32 {
+    let mut value = 0.0;
+    if negative {
+        value -= 1.0;
+    }
+    if positive {
+        value += 1.0;
+    }
+    value
+}
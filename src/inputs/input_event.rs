@@ -0,0 +1,85 @@
+use serde::{Deserialize, Serialize};
+use winit::dpi::PhysicalPosition;
+use winit::event::{DeviceEvent, ElementState, ModifiersState, MouseButton, MouseScrollDelta, TouchPhase, VirtualKeyCode, WindowEvent};
+
+use super::GamepadEvent;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum InputEvent {
+    Key(VirtualKeyCode, ElementState),
+    Character(char),
+    ModifiersChanged(ModifiersState),
+    MouseButton(MouseButton, ElementState),
+    CursorMoved(PhysicalPosition<f64>),
+    CursorLeft,
+    MouseMotion(f64, f64),
+    MouseScrolled(MouseScrollDelta),
+    ScaleFactorChanged(f64),
+    Touch(u64, TouchPhase, PhysicalPosition<f64>),
+    Gamepad(GamepadEvent),
+}
+
+impl InputEvent {
+    pub fn from_window_event(event: &WindowEvent) -> Option<Self> {
+        match event {
+            WindowEvent::KeyboardInput { input, .. } => input
+                .virtual_keycode
+                .map(|keycode| InputEvent::Key(keycode, input.state)),
+            WindowEvent::ReceivedCharacter(character) => Some(InputEvent::Character(*character)),
+            WindowEvent::ModifiersChanged(modifiers) => Some(InputEvent::ModifiersChanged(*modifiers)),
+            WindowEvent::MouseInput { button, state, .. } => Some(InputEvent::MouseButton(*button, *state)),
+            WindowEvent::CursorMoved { position, .. } => Some(InputEvent::CursorMoved(*position)),
+            WindowEvent::CursorLeft { .. } => Some(InputEvent::CursorLeft),
+            WindowEvent::MouseWheel { delta, .. } => Some(InputEvent::MouseScrolled(*delta)),
+            WindowEvent::Touch(touch) => Some(InputEvent::Touch(touch.id, touch.phase, touch.location)),
+            WindowEvent::ScaleFactorChanged { scale_factor, .. } => Some(InputEvent::ScaleFactorChanged(*scale_factor)),
+            _ => None,
+        }
+    }
+
+    pub fn from_device_event(event: &DeviceEvent) -> Option<Self> {
+        match event {
+            DeviceEvent::MouseMotion { delta } => Some(InputEvent::MouseMotion(delta.0, delta.1)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::inputs::{GamepadAxis, GamepadEvent};
+
+    #[test]
+    fn window_events_without_input_are_ignored() {
+        assert_eq!(
+            InputEvent::from_window_event(&WindowEvent::ReceivedCharacter('x')),
+            Some(InputEvent::Character('x'))
+        );
+        assert_eq!(
+            InputEvent::from_window_event(&WindowEvent::ModifiersChanged(ModifiersState::SHIFT)),
+            Some(InputEvent::ModifiersChanged(ModifiersState::SHIFT))
+        );
+        assert_eq!(InputEvent::from_window_event(&WindowEvent::Focused(true)), None);
+        assert_eq!(
+            InputEvent::from_device_event(&DeviceEvent::MouseMotion { delta: (1.0, -2.0) }),
+            Some(InputEvent::MouseMotion(1.0, -2.0))
+        );
+        assert_eq!(InputEvent::from_device_event(&DeviceEvent::Added), None);
+    }
+
+    #[test]
+    fn events_survive_serialization() {
+        let events = [
+            InputEvent::Key(VirtualKeyCode::Space, ElementState::Released),
+            InputEvent::MouseScrolled(MouseScrollDelta::PixelDelta(PhysicalPosition::new(0.0, -40.0))),
+            InputEvent::Touch(3, TouchPhase::Moved, PhysicalPosition::new(5.0, 6.0)),
+            InputEvent::Gamepad(GamepadEvent::Axis(1, GamepadAxis::RightTrigger, 0.25)),
+        ];
+
+        for event in events.iter() {
+            let json = serde_json::to_string(event).unwrap();
+            assert_eq!(serde_json::from_str::<InputEvent>(&json).unwrap(), *event);
+        }
+    }
+}
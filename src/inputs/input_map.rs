@@ -0,0 +1,303 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use winit::event::{MouseButton, VirtualKeyCode};
+
+use crate::config::{load_configuration, save_configuration};
+use super::{GamepadAxis, GamepadButton};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum Binding {
+    Key(VirtualKeyCode),
+    Mouse(MouseButton),
+    GamepadButton(GamepadButton),
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum AxisBinding {
+    Keys { negative: VirtualKeyCode, positive: VirtualKeyCode },
+    GamepadButtons { negative: GamepadButton, positive: GamepadButton },
+    GamepadAxis(GamepadAxis),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BindingConflict {
+    pub binding: Binding,
+    pub actions: (String, String),
+}
+
+impl fmt::Display for BindingConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} is bound to both \"{}\" and \"{}\"", self.binding, self.actions.0, self.actions.1)
+    }
+}
+
+impl Error for BindingConflict { }
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RebindError {
+    Conflict(BindingConflict),
+    NotBound { action: String, binding: Binding },
+}
+
+impl fmt::Display for RebindError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RebindError::Conflict(conflict) => conflict.fmt(f),
+            RebindError::NotBound { action, binding } => write!(f, "{:?} is not bound to \"{}\"", binding, action),
+        }
+    }
+}
+
+impl Error for RebindError { }
+
+impl From<BindingConflict> for RebindError {
+    fn from(conflict: BindingConflict) -> Self {
+        RebindError::Conflict(conflict)
+    }
+}
+
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq)]
+pub struct InputMap {
+    #[serde(default)]
+    actions: HashMap<String, Vec<Binding>>,
+    #[serde(default)]
+    axes: HashMap<String, Vec<AxisBinding>>,
+}
+
+impl InputMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Fails when the file binds one input to several actions.
+    pub fn load(file_path: PathBuf) -> Result<Self, Box<dyn Error>> {
+        let map: Self = load_configuration(file_path)?;
+        match map.conflicts().into_iter().next() {
+            Some(conflict) => Err(Box::new(conflict)),
+            None => Ok(map),
+        }
+    }
+
+    pub fn save(&self, file_path: PathBuf) -> Result<(), Box<dyn Error>> {
+        save_configuration(file_path, self)
+    }
+
+    pub fn with_action(mut self, action: &str, binding: Binding) -> Result<Self, BindingConflict> {
+        self.bind_action(action, binding)?;
+        Ok(self)
+    }
+
+    pub fn with_axis(mut self, axis: &str, binding: AxisBinding) -> Self {
+        self.axes.entry(axis.to_owned()).or_default().push(binding);
+        self
+    }
+
+    pub fn action_bindings(&self, action: &str) -> &[Binding] {
+        self.actions.get(action).map_or(&[], |bindings| bindings.as_slice())
+    }
+
+    pub fn axis_bindings(&self, axis: &str) -> &[AxisBinding] {
+        self.axes.get(axis).map_or(&[], |bindings| bindings.as_slice())
+    }
+
+    pub fn action_for_binding(&self, binding: Binding) -> Option<&str> {
+        self.actions
+            .iter()
+            .find(|(_, bindings)| bindings.contains(&binding))
+            .map(|(action, _)| action.as_str())
+    }
+
+    pub fn bind_action(&mut self, action: &str, binding: Binding) -> Result<(), BindingConflict> {
+        if let Some(existing_action) = self.action_for_binding(binding) {
+            if existing_action == action {
+                return Ok(());
+            }
+
+            return Err(BindingConflict {
+                binding,
+                actions: (existing_action.to_owned(), action.to_owned()),
+            });
+        }
+
+        self.actions.entry(action.to_owned()).or_default().push(binding);
+        Ok(())
+    }
+
+    pub fn unbind_action(&mut self, action: &str, binding: Binding) {
+        if let Some(bindings) = self.actions.get_mut(action) {
+            bindings.retain(|bound| bound != &binding);
+        }
+    }
+
+    pub fn rebind_action(&mut self, action: &str, from: Binding, to: Binding) -> Result<(), RebindError> {
+        if !self.action_bindings(action).contains(&from) {
+            return Err(RebindError::NotBound {
+                action: action.to_owned(),
+                binding: from,
+            });
+        }
+
+        if let Some(existing_action) = self.action_for_binding(to) {
+            if existing_action != action {
+                return Err(RebindError::Conflict(BindingConflict {
+                    binding: to,
+                    actions: (existing_action.to_owned(), action.to_owned()),
+                }));
+            }
+        }
+
+        self.unbind_action(action, from);
+        self.bind_action(action, to)?;
+        Ok(())
+    }
+
+    pub fn bind_axis(&mut self, axis: &str, binding: AxisBinding) {
+        let bindings = self.axes.entry(axis.to_owned()).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    pub fn unbind_axis(&mut self, axis: &str, binding: AxisBinding) {
+        if let Some(bindings) = self.axes.get_mut(axis) {
+            bindings.retain(|bound| bound != &binding);
+        }
+    }
+
+    // A configuration file can bind the same input to several actions, this
+    // lists every such pair sorted by action name.
+    pub fn conflicts(&self) -> Vec<BindingConflict> {
+        let mut actions: Vec<(&String, &Vec<Binding>)> = self.actions.iter().collect();
+        actions.sort_by_key(|(action, _)| *action);
+
+        let mut conflicts = vec![];
+        for (index, (action, bindings)) in actions.iter().enumerate() {
+            for (other_action, other_bindings) in actions.iter().skip(index + 1) {
+                for binding in bindings.iter().filter(|binding| other_bindings.contains(binding)) {
+                    conflicts.push(BindingConflict {
+                        binding: *binding,
+                        actions: ((*action).clone(), (*other_action).clone()),
+                    });
+                }
+            }
+        }
+
+        conflicts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use winit::event::ElementState;
+
+    use super::*;
+    use crate::inputs::{GamepadEvent, Input, InputEvent};
+    use crate::window::ManualClock;
+
+    fn conflict(binding: Binding, first: &str, second: &str) -> BindingConflict {
+        BindingConflict {
+            binding,
+            actions: (first.to_owned(), second.to_owned()),
+        }
+    }
+
+    #[test]
+    fn binding_an_input_to_a_second_action_conflicts() {
+        let map = InputMap::new()
+            .with_action("jump", Binding::Key(VirtualKeyCode::Space))
+            .and_then(|map| map.with_action("jump", Binding::Key(VirtualKeyCode::Space)))
+            .unwrap();
+        assert_eq!(map.action_bindings("jump"), &[Binding::Key(VirtualKeyCode::Space)]);
+
+        let error = map.with_action("fire", Binding::Key(VirtualKeyCode::Space)).unwrap_err();
+        assert_eq!(error, conflict(Binding::Key(VirtualKeyCode::Space), "jump", "fire"));
+    }
+
+    #[test]
+    fn rebinding_moves_a_binding() {
+        let mut map = InputMap::new()
+            .with_action("jump", Binding::Key(VirtualKeyCode::Space))
+            .and_then(|map| map.with_action("fire", Binding::Mouse(MouseButton::Left)))
+            .unwrap();
+
+        map.rebind_action("jump", Binding::Key(VirtualKeyCode::Space), Binding::Key(VirtualKeyCode::J)).unwrap();
+        assert_eq!(map.action_bindings("jump"), &[Binding::Key(VirtualKeyCode::J)]);
+        assert_eq!(map.action_for_binding(Binding::Key(VirtualKeyCode::Space)), None);
+
+        assert_eq!(
+            map.rebind_action("jump", Binding::Key(VirtualKeyCode::J), Binding::Mouse(MouseButton::Left)),
+            Err(RebindError::Conflict(conflict(Binding::Mouse(MouseButton::Left), "fire", "jump")))
+        );
+        assert_eq!(
+            map.rebind_action("jump", Binding::Key(VirtualKeyCode::Space), Binding::Key(VirtualKeyCode::K)),
+            Err(RebindError::NotBound {
+                action: "jump".to_owned(),
+                binding: Binding::Key(VirtualKeyCode::Space),
+            })
+        );
+        assert_eq!(map.action_bindings("jump"), &[Binding::Key(VirtualKeyCode::J)]);
+        assert_eq!(map.action_for_binding(Binding::Key(VirtualKeyCode::K)), None);
+    }
+
+    #[test]
+    fn axis_bindings_add_up_and_clamp() {
+        let map = InputMap::new()
+            .with_axis("move_x", AxisBinding::Keys {
+                negative: VirtualKeyCode::A,
+                positive: VirtualKeyCode::D,
+            })
+            .with_axis("move_x", AxisBinding::Keys {
+                negative: VirtualKeyCode::Left,
+                positive: VirtualKeyCode::Right,
+            })
+            .with_axis("move_x", AxisBinding::GamepadAxis(GamepadAxis::LeftStickX));
+        let mut input = Input::with_clock(ManualClock::new(), map);
+        assert_eq!(input.axis("move_x"), 0.0);
+
+        input.handle_event(InputEvent::Key(VirtualKeyCode::D, ElementState::Pressed));
+        assert_eq!(input.axis("move_x"), 1.0);
+
+        input.handle_event(InputEvent::Key(VirtualKeyCode::Right, ElementState::Pressed));
+        assert_eq!(input.axis("move_x"), 1.0);
+
+        input.handle_event(InputEvent::Key(VirtualKeyCode::Right, ElementState::Released));
+        input.handle_event(InputEvent::Gamepad(GamepadEvent::Axis(0, GamepadAxis::LeftStickX, -0.55)));
+        assert!((input.axis("move_x") - 0.5).abs() < 1e-6);
+
+        input.handle_event(InputEvent::Key(VirtualKeyCode::A, ElementState::Pressed));
+        assert!((input.axis("move_x") + 0.5).abs() < 1e-6);
+        assert_eq!(input.axis("unbound"), 0.0);
+    }
+
+    #[test]
+    fn maps_survive_a_save_and_load() {
+        let directory = std::env::temp_dir().join(format!("webgpu-input-map-test-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let saved = directory.join("saved.config");
+        let conflicting = directory.join("conflicting.config");
+        fs::write(&conflicting, r#"{"actions": {"fire": [{"Key": "Space"}], "jump": [{"Key": "Space"}]}}"#).unwrap();
+
+        let map = InputMap::new()
+            .with_action("jump", Binding::Key(VirtualKeyCode::Space))
+            .and_then(|map| map.with_action("jump", Binding::GamepadButton(GamepadButton::South)))
+            .unwrap()
+            .with_axis("throttle", AxisBinding::GamepadButtons {
+                negative: GamepadButton::LeftBumper,
+                positive: GamepadButton::RightBumper,
+            });
+        map.save(saved.clone()).unwrap();
+        let loaded = InputMap::load(saved);
+        let conflicting = InputMap::load(conflicting);
+        fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(loaded.unwrap(), map);
+        let error = conflicting.err().unwrap();
+        assert_eq!(error.downcast_ref::<BindingConflict>(), Some(&conflict(Binding::Key(VirtualKeyCode::Space), "fire", "jump")));
+    }
+}
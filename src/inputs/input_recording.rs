@@ -0,0 +1,230 @@
+use std::error::Error;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::{load_configuration, save_configuration};
+use crate::window::Clock;
+use super::{Input, InputEvent};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct RecordedInputEvent {
+    pub frame: u64,
+    pub event: InputEvent,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct InputRecording {
+    pub events: Vec<RecordedInputEvent>,
+    pub frames: u64,
+}
+
+impl InputRecording {
+    pub fn load(file_path: PathBuf) -> Result<Self, Box<dyn Error>> {
+        load_configuration(file_path)
+    }
+
+    pub fn save(&self, file_path: PathBuf) -> Result<(), Box<dyn Error>> {
+        save_configuration(file_path, self)
+    }
+}
+
+#[derive(Default)]
+pub struct InputRecorder {
+    frame: u64,
+    recording: InputRecording,
+}
+
+impl InputRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, event: InputEvent) {
+        self.recording.events.push(RecordedInputEvent {
+            frame: self.frame,
+            event,
+        });
+    }
+
+    pub fn end_frame(&mut self) {
+        self.frame += 1;
+    }
+
+    pub fn finish(mut self) -> InputRecording {
+        self.recording.frames = self.frame;
+        self.recording
+    }
+}
+
+// Feeds a recording back into `Input` one frame at a time. For a replay to be
+// deterministic the input has to be driven by a `ManualClock` that is
+// advanced by a fixed timestep per frame, rather than the system clock.
+pub struct InputReplay {
+    recording: InputRecording,
+    next_event: usize,
+    frame: u64,
+}
+
+impl InputReplay {
+    pub fn new(recording: InputRecording) -> Self {
+        Self {
+            recording,
+            next_event: 0,
+            frame: 0,
+        }
+    }
+
+    pub fn load(file_path: PathBuf) -> Result<Self, Box<dyn Error>> {
+        Ok(Self::new(InputRecording::load(file_path)?))
+    }
+
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.frame >= self.recording.frames && self.next_event >= self.recording.events.len()
+    }
+
+    // Applies every event recorded for the current frame, call once per
+    // frame before the frame is updated.
+    pub fn apply_frame<C: Clock>(&mut self, input: &mut Input<C>) {
+        while let Some(recorded) = self.recording.events.get(self.next_event) {
+            if recorded.frame > self.frame {
+                break;
+            }
+
+            input.handle_event(recorded.event);
+            self.next_event += 1;
+        }
+
+        self.frame += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use winit::dpi::PhysicalPosition;
+    use winit::event::{ElementState, MouseButton, VirtualKeyCode};
+
+    use super::*;
+    use crate::inputs::{GamepadButton, GamepadEvent, InputMap, QueuedGamepadBackend};
+    use crate::window::ManualClock;
+
+    #[derive(Debug, PartialEq)]
+    struct FrameState {
+        key_pressed: bool,
+        key_just_pressed: bool,
+        key_just_released: bool,
+        key_held: Option<Duration>,
+        mouse_just_pressed: bool,
+        cursor: Option<PhysicalPosition<f64>>,
+        gamepad_pressed: bool,
+        gamepad_just_pressed: bool,
+        gamepad_just_released: bool,
+    }
+
+    fn frame_state(input: &Input<ManualClock>) -> FrameState {
+        FrameState {
+            key_pressed: input.keyboard.is_key_pressed(VirtualKeyCode::W),
+            key_just_pressed: input.keyboard.just_pressed(VirtualKeyCode::W),
+            key_just_released: input.keyboard.just_released(VirtualKeyCode::W),
+            key_held: input.keyboard.held_duration(VirtualKeyCode::W),
+            mouse_just_pressed: input.mouse.just_pressed(MouseButton::Left),
+            cursor: input.mouse.position(),
+            gamepad_pressed: input.gamepad.is_button_pressed(0, GamepadButton::South),
+            gamepad_just_pressed: input.gamepad.just_pressed(0, GamepadButton::South),
+            gamepad_just_released: input.gamepad.just_released(0, GamepadButton::South),
+        }
+    }
+
+    fn gamepad_backend(events: &[GamepadEvent]) -> QueuedGamepadBackend {
+        let mut backend = QueuedGamepadBackend::new();
+        for event in events {
+            backend.push(*event);
+        }
+        backend
+    }
+
+    #[test]
+    fn replaying_a_recording_reproduces_every_frame() {
+        let clock = ManualClock::new();
+        let mut input = Input::with_clock(clock.clone(), InputMap::new());
+        input.start_recording();
+
+        let frames: [fn(&mut Input<ManualClock>); 4] = [
+            |input| {
+                input.handle_event(InputEvent::Key(VirtualKeyCode::W, ElementState::Pressed));
+                input.poll_gamepads(&mut gamepad_backend(&[
+                    GamepadEvent::Connected(0),
+                    GamepadEvent::Button(0, GamepadButton::South, ElementState::Pressed),
+                ]));
+            },
+            |input| {
+                input.handle_event(InputEvent::Key(VirtualKeyCode::W, ElementState::Pressed));
+                input.handle_event(InputEvent::CursorMoved(PhysicalPosition::new(10.0, 20.0)));
+                input.handle_event(InputEvent::MouseButton(MouseButton::Left, ElementState::Pressed));
+            },
+            |_| { },
+            |input| {
+                input.handle_event(InputEvent::Key(VirtualKeyCode::W, ElementState::Released));
+                input.poll_gamepads(&mut gamepad_backend(&[GamepadEvent::Button(0, GamepadButton::South, ElementState::Released)]));
+            },
+        ];
+
+        let mut recorded_states = vec![];
+        for frame in frames.iter() {
+            frame(&mut input);
+            recorded_states.push(frame_state(&input));
+            input.end_frame();
+            clock.advance(Duration::from_millis(16));
+        }
+        let recording = input.stop_recording().unwrap();
+        assert_eq!(recording.frames, 4);
+
+        // Through serde, like a recording saved to and loaded from disk.
+        let recording: InputRecording = serde_json::from_str(&serde_json::to_string(&recording).unwrap()).unwrap();
+        let clock = ManualClock::new();
+        let mut input = Input::with_clock(clock.clone(), InputMap::new());
+        let mut replay = InputReplay::new(recording);
+
+        let mut replayed_states = vec![];
+        while !replay.is_finished() {
+            replay.apply_frame(&mut input);
+            replayed_states.push(frame_state(&input));
+            input.end_frame();
+            clock.advance(Duration::from_millis(16));
+        }
+
+        assert_eq!(replayed_states, recorded_states);
+        assert!(replayed_states[0].key_just_pressed && replayed_states[0].gamepad_just_pressed);
+        assert!(!replayed_states[1].key_just_pressed && replayed_states[1].mouse_just_pressed);
+        assert_eq!(replayed_states[2].key_held, Some(Duration::from_millis(32)));
+        assert!(replayed_states[3].key_just_released && replayed_states[3].gamepad_just_released);
+    }
+
+    #[test]
+    fn events_are_recorded_with_their_frame() {
+        let mut recorder = InputRecorder::new();
+        recorder.record(InputEvent::CursorLeft);
+        recorder.end_frame();
+        recorder.end_frame();
+        recorder.record(InputEvent::Character('a'));
+
+        let recording = recorder.finish();
+        assert_eq!(recording.frames, 2);
+        assert_eq!(recording.events, vec![
+            RecordedInputEvent {
+                frame: 0,
+                event: InputEvent::CursorLeft,
+            },
+            RecordedInputEvent {
+                frame: 2,
+                event: InputEvent::Character('a'),
+            },
+        ]);
+    }
+}
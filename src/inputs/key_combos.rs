@@ -0,0 +1,275 @@
+use std::time::{Duration, Instant};
+
+use winit::event::{ModifiersState, VirtualKeyCode};
+
+use crate::window::Clock;
+use super::Keyboard;
+
+pub type KeyComboId = usize;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ModifierMatch {
+    // The held modifiers must be exactly the ones in the chord.
+    Exact,
+    // The chord's modifiers must be held, additional modifiers are allowed.
+    Inclusive,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct KeyChord {
+    pub key: VirtualKeyCode,
+    pub modifiers: ModifiersState,
+}
+
+impl KeyChord {
+    pub fn new(key: VirtualKeyCode) -> Self {
+        Self {
+            key,
+            modifiers: ModifiersState::empty(),
+        }
+    }
+
+    pub fn with_modifiers(mut self, modifiers: ModifiersState) -> Self {
+        self.modifiers = modifiers;
+        self
+    }
+
+    pub fn matches(&self, key: VirtualKeyCode, modifiers: ModifiersState, modifier_match: ModifierMatch) -> bool {
+        if self.key != key {
+            return false;
+        }
+
+        match modifier_match {
+            ModifierMatch::Exact => modifiers == self.modifiers,
+            ModifierMatch::Inclusive => modifiers.contains(self.modifiers),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct KeyComboMatched {
+    pub id: KeyComboId,
+}
+
+struct KeyCombo {
+    id: KeyComboId,
+    steps: Vec<KeyChord>,
+    step_timeout: Option<Duration>,
+    modifier_match: ModifierMatch,
+    // Every partially matched attempt as (next step, time of last step), so
+    // overlapping attempts like "up up up down" still match "up up down".
+    attempts: Vec<(usize, Instant)>,
+}
+
+impl KeyCombo {
+    fn handle_key(&mut self, key: VirtualKeyCode, modifiers: ModifiersState, now: Instant) -> bool {
+        let steps = &self.steps;
+        let step_timeout = self.step_timeout;
+        let modifier_match = self.modifier_match;
+
+        let mut attempts: Vec<(usize, Instant)> = self.attempts
+            .iter()
+            .filter(|(_, last_step_at)| match step_timeout {
+                Some(timeout) => now.saturating_duration_since(*last_step_at) <= timeout,
+                None => true,
+            })
+            .filter(|(step, _)| steps[*step].matches(key, modifiers, modifier_match))
+            .map(|(step, _)| (step + 1, now))
+            .collect();
+
+        if steps[0].matches(key, modifiers, modifier_match) {
+            attempts.push((1, now));
+        }
+
+        let matched = attempts.iter().any(|(step, _)| *step == steps.len());
+        if matched {
+            attempts.clear();
+        }
+
+        self.attempts = attempts;
+        matched
+    }
+}
+
+#[derive(Default)]
+pub struct KeyComboMatcher {
+    combos: Vec<KeyCombo>,
+    next_id: KeyComboId,
+}
+
+impl KeyComboMatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register_chord(&mut self, chord: KeyChord, modifier_match: ModifierMatch) -> KeyComboId {
+        self.register_sequence(vec![chord], None, modifier_match)
+    }
+
+    pub fn register_sequence(&mut self, steps: Vec<KeyChord>, step_timeout: Option<Duration>, modifier_match: ModifierMatch) -> KeyComboId {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        if !steps.is_empty() {
+            self.combos.push(KeyCombo {
+                id,
+                steps,
+                step_timeout,
+                modifier_match,
+                attempts: vec![],
+            });
+        }
+
+        id
+    }
+
+    pub fn unregister(&mut self, id: KeyComboId) {
+        self.combos.retain(|combo| combo.id != id);
+    }
+
+    pub fn reset(&mut self) {
+        for combo in self.combos.iter_mut() {
+            combo.attempts.clear();
+        }
+    }
+
+    pub fn handle_key(&mut self, key: VirtualKeyCode, modifiers: ModifiersState, now: Instant) -> Vec<KeyComboMatched> {
+        // Modifier keys are part of the held state, not steps of their own.
+        if is_modifier_key(key) {
+            return vec![];
+        }
+
+        self.combos
+            .iter_mut()
+            .filter_map(|combo| {
+                if combo.handle_key(key, modifiers, now) {
+                    return Some(KeyComboMatched { id: combo.id });
+                }
+                None
+            })
+            .collect()
+    }
+
+    // Feeds this frame's newly pressed keys, call before `Keyboard::end_frame`.
+    pub fn update<C: Clock>(&mut self, keyboard: &Keyboard<C>) -> Vec<KeyComboMatched> {
+        let now = keyboard.clock().now();
+        let modifiers = keyboard.modifiers();
+
+        let mut matched = vec![];
+        for key in keyboard.just_pressed_keys() {
+            matched.extend(self.handle_key(key, modifiers, now));
+        }
+
+        matched
+    }
+}
+
+fn is_modifier_key(key: VirtualKeyCode) -> bool {
+    matches!(
+        key,
+        VirtualKeyCode::LShift | VirtualKeyCode::RShift |
+        VirtualKeyCode::LControl | VirtualKeyCode::RControl |
+        VirtualKeyCode::LAlt | VirtualKeyCode::RAlt |
+        VirtualKeyCode::LWin | VirtualKeyCode::RWin
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use winit::event::ElementState;
+
+    use super::*;
+    use crate::window::ManualClock;
+
+    fn press(matcher: &mut KeyComboMatcher, keyboard: &mut Keyboard<ManualClock>, key: VirtualKeyCode) -> Vec<KeyComboMatched> {
+        keyboard.handle_key(key, ElementState::Pressed);
+        let matched = matcher.update(keyboard);
+        keyboard.handle_key(key, ElementState::Released);
+        keyboard.end_frame();
+        matched
+    }
+
+    #[test]
+    fn chords_match_with_their_modifiers() {
+        let mut keyboard = Keyboard::with_clock(ManualClock::new());
+        let mut matcher = KeyComboMatcher::new();
+        let save = matcher.register_chord(KeyChord::new(VirtualKeyCode::S).with_modifiers(ModifiersState::CTRL), ModifierMatch::Exact);
+        let undo = matcher.register_chord(KeyChord::new(VirtualKeyCode::Z).with_modifiers(ModifiersState::CTRL), ModifierMatch::Inclusive);
+
+        assert!(press(&mut matcher, &mut keyboard, VirtualKeyCode::S).is_empty());
+
+        keyboard.set_modifiers(ModifiersState::CTRL);
+        assert!(press(&mut matcher, &mut keyboard, VirtualKeyCode::LControl).is_empty());
+        assert_eq!(press(&mut matcher, &mut keyboard, VirtualKeyCode::S), vec![KeyComboMatched { id: save }]);
+        assert_eq!(press(&mut matcher, &mut keyboard, VirtualKeyCode::Z), vec![KeyComboMatched { id: undo }]);
+
+        keyboard.set_modifiers(ModifiersState::CTRL | ModifiersState::SHIFT);
+        assert!(press(&mut matcher, &mut keyboard, VirtualKeyCode::S).is_empty());
+        assert_eq!(press(&mut matcher, &mut keyboard, VirtualKeyCode::Z), vec![KeyComboMatched { id: undo }]);
+    }
+
+    #[test]
+    fn sequences_match_in_order() {
+        let mut keyboard = Keyboard::with_clock(ManualClock::new());
+        let mut matcher = KeyComboMatcher::new();
+        let steps = [VirtualKeyCode::Up, VirtualKeyCode::Up, VirtualKeyCode::Down];
+        let id = matcher.register_sequence(steps.iter().map(|key| KeyChord::new(*key)).collect(), None, ModifierMatch::Exact);
+
+        assert!(press(&mut matcher, &mut keyboard, VirtualKeyCode::Up).is_empty());
+        assert!(press(&mut matcher, &mut keyboard, VirtualKeyCode::Up).is_empty());
+        assert!(press(&mut matcher, &mut keyboard, VirtualKeyCode::Up).is_empty());
+        assert_eq!(press(&mut matcher, &mut keyboard, VirtualKeyCode::Down), vec![KeyComboMatched { id }]);
+
+        assert!(press(&mut matcher, &mut keyboard, VirtualKeyCode::Down).is_empty());
+    }
+
+    #[test]
+    fn a_wrong_key_resets_a_partial_match() {
+        let mut keyboard = Keyboard::with_clock(ManualClock::new());
+        let mut matcher = KeyComboMatcher::new();
+        let steps = [VirtualKeyCode::A, VirtualKeyCode::B, VirtualKeyCode::C];
+        let id = matcher.register_sequence(steps.iter().map(|key| KeyChord::new(*key)).collect(), None, ModifierMatch::Exact);
+
+        press(&mut matcher, &mut keyboard, VirtualKeyCode::A);
+        press(&mut matcher, &mut keyboard, VirtualKeyCode::B);
+        press(&mut matcher, &mut keyboard, VirtualKeyCode::X);
+        assert!(press(&mut matcher, &mut keyboard, VirtualKeyCode::C).is_empty());
+
+        press(&mut matcher, &mut keyboard, VirtualKeyCode::A);
+        press(&mut matcher, &mut keyboard, VirtualKeyCode::B);
+        matcher.reset();
+        assert!(press(&mut matcher, &mut keyboard, VirtualKeyCode::C).is_empty());
+
+        press(&mut matcher, &mut keyboard, VirtualKeyCode::A);
+        press(&mut matcher, &mut keyboard, VirtualKeyCode::B);
+        assert_eq!(press(&mut matcher, &mut keyboard, VirtualKeyCode::C), vec![KeyComboMatched { id }]);
+    }
+
+    #[test]
+    fn sequences_time_out_between_steps() {
+        let clock = ManualClock::new();
+        let mut keyboard = Keyboard::with_clock(clock.clone());
+        let mut matcher = KeyComboMatcher::new();
+        let steps = vec![KeyChord::new(VirtualKeyCode::G), KeyChord::new(VirtualKeyCode::G)];
+        let id = matcher.register_sequence(steps, Some(Duration::from_millis(300)), ModifierMatch::Exact);
+
+        press(&mut matcher, &mut keyboard, VirtualKeyCode::G);
+        clock.advance(Duration::from_millis(301));
+        assert!(press(&mut matcher, &mut keyboard, VirtualKeyCode::G).is_empty());
+
+        clock.advance(Duration::from_millis(300));
+        assert_eq!(press(&mut matcher, &mut keyboard, VirtualKeyCode::G), vec![KeyComboMatched { id }]);
+    }
+
+    #[test]
+    fn unregistered_combos_stop_matching() {
+        let mut keyboard = Keyboard::with_clock(ManualClock::new());
+        let mut matcher = KeyComboMatcher::new();
+        let id = matcher.register_chord(KeyChord::new(VirtualKeyCode::F5), ModifierMatch::Exact);
+        assert_eq!(matcher.register_sequence(vec![], None, ModifierMatch::Exact), id + 1);
+
+        assert_eq!(press(&mut matcher, &mut keyboard, VirtualKeyCode::F5), vec![KeyComboMatched { id }]);
+        matcher.unregister(id);
+        assert!(press(&mut matcher, &mut keyboard, VirtualKeyCode::F5).is_empty());
+    }
+}
@@ -1,30 +1,204 @@
-use winit::event::{VirtualKeyCode, ElementState};
+use std::time::{Duration, Instant};
 
-pub struct Keyboard {
-    pressed_keys: Vec<VirtualKeyCode>,
+use winit::event::{VirtualKeyCode, ElementState, ModifiersState};
+
+use crate::window::{Clock, SystemClock};
+
+pub struct Keyboard<C: Clock = SystemClock> {
+    clock: C,
+    pressed_keys: Vec<(VirtualKeyCode, Instant)>,
+    just_pressed_keys: Vec<VirtualKeyCode>,
+    just_released_keys: Vec<VirtualKeyCode>,
+    modifiers: ModifiersState,
 }
 
 impl Keyboard {
     pub fn new() -> Self {
+        Self::with_clock(SystemClock)
+    }
+}
+
+impl Default for Keyboard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C: Clock> Keyboard<C> {
+    pub fn with_clock(clock: C) -> Self {
         Self {
+            clock,
             pressed_keys: vec![],
+            just_pressed_keys: vec![],
+            just_released_keys: vec![],
+            modifiers: ModifiersState::empty(),
         }
     }
 
     pub fn handle_input(&mut self, input: &winit::event::KeyboardInput) {
         if let Some(keycode) = input.virtual_keycode {
-            if input.state == ElementState::Pressed {
-                self.pressed_keys.push(keycode);
+            self.handle_key(keycode, input.state);
+        }
+    }
+
+    pub fn handle_key(&mut self, keycode: VirtualKeyCode, state: ElementState) {
+        if state == ElementState::Pressed {
+            // Key repeat sends further pressed events while the key is held.
+            if self.is_key_pressed(keycode) {
+                return;
             }
-            if input.state == ElementState::Released {
-                if let Some(index) = self.pressed_keys.iter().position(|pressed_keycode| pressed_keycode == &keycode) {
-                    self.pressed_keys.remove(index);
-                }
+
+            self.pressed_keys.push((keycode, self.clock.now()));
+            self.just_pressed_keys.push(keycode);
+        }
+        if state == ElementState::Released {
+            if let Some(index) = self.pressed_keys.iter().position(|(pressed_keycode, _)| pressed_keycode == &keycode) {
+                self.pressed_keys.remove(index);
+                self.just_released_keys.push(keycode);
             }
         }
     }
 
+    pub fn set_modifiers(&mut self, modifiers: ModifiersState) {
+        self.modifiers = modifiers;
+    }
+
+    pub fn modifiers(&self) -> ModifiersState {
+        self.modifiers
+    }
+
+    pub fn end_frame(&mut self) {
+        self.just_pressed_keys.clear();
+        self.just_released_keys.clear();
+    }
+
+    pub fn release_all(&mut self) {
+        for (keycode, _) in self.pressed_keys.drain(..) {
+            self.just_released_keys.push(keycode);
+        }
+    }
+
     pub fn is_key_pressed(&self, key: VirtualKeyCode) -> bool {
-        self.pressed_keys.iter().any(|pressed_key| pressed_key == &key)
+        self.pressed_keys.iter().any(|(pressed_key, _)| pressed_key == &key)
+    }
+
+    pub fn just_pressed(&self, key: VirtualKeyCode) -> bool {
+        self.just_pressed_keys.iter().any(|pressed_key| pressed_key == &key)
+    }
+
+    pub fn just_released(&self, key: VirtualKeyCode) -> bool {
+        self.just_released_keys.iter().any(|released_key| released_key == &key)
+    }
+
+    pub fn held_duration(&self, key: VirtualKeyCode) -> Option<Duration> {
+        self.pressed_keys
+            .iter()
+            .find(|(pressed_key, _)| pressed_key == &key)
+            .map(|(_, pressed_at)| self.clock.now().saturating_duration_since(*pressed_at))
+    }
+
+    pub fn just_pressed_keys(&self) -> impl Iterator<Item = VirtualKeyCode> + '_ {
+        self.just_pressed_keys.iter().copied()
+    }
+
+    pub fn clock(&self) -> &C {
+        &self.clock
+    }
+
+    pub fn pressed_keys(&self) -> impl Iterator<Item = VirtualKeyCode> + '_ {
+        self.pressed_keys.iter().map(|(keycode, _)| *keycode)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::window::ManualClock;
+
+    #[test]
+    fn presses_and_releases_are_reported_for_one_frame() {
+        let mut keyboard = Keyboard::with_clock(ManualClock::new());
+
+        keyboard.handle_key(VirtualKeyCode::W, ElementState::Pressed);
+        assert!(keyboard.is_key_pressed(VirtualKeyCode::W));
+        assert!(keyboard.just_pressed(VirtualKeyCode::W));
+        assert!(!keyboard.just_released(VirtualKeyCode::W));
+
+        keyboard.end_frame();
+        assert!(keyboard.is_key_pressed(VirtualKeyCode::W));
+        assert!(!keyboard.just_pressed(VirtualKeyCode::W));
+
+        keyboard.handle_key(VirtualKeyCode::W, ElementState::Released);
+        assert!(!keyboard.is_key_pressed(VirtualKeyCode::W));
+        assert!(keyboard.just_released(VirtualKeyCode::W));
+
+        keyboard.end_frame();
+        assert!(!keyboard.just_released(VirtualKeyCode::W));
+
+        keyboard.handle_key(VirtualKeyCode::S, ElementState::Released);
+        assert!(!keyboard.just_released(VirtualKeyCode::S));
+    }
+
+    #[test]
+    fn key_repeat_is_not_a_new_press() {
+        let clock = ManualClock::new();
+        let mut keyboard = Keyboard::with_clock(clock.clone());
+
+        keyboard.handle_key(VirtualKeyCode::A, ElementState::Pressed);
+        keyboard.end_frame();
+        clock.advance(Duration::from_millis(500));
+        keyboard.handle_key(VirtualKeyCode::A, ElementState::Pressed);
+        keyboard.handle_key(VirtualKeyCode::A, ElementState::Pressed);
+
+        assert!(!keyboard.just_pressed(VirtualKeyCode::A));
+        assert_eq!(keyboard.pressed_keys().count(), 1);
+        assert_eq!(keyboard.held_duration(VirtualKeyCode::A), Some(Duration::from_millis(500)));
+    }
+
+    #[test]
+    fn held_duration_follows_the_clock() {
+        let clock = ManualClock::new();
+        let mut keyboard = Keyboard::with_clock(clock.clone());
+        assert_eq!(keyboard.held_duration(VirtualKeyCode::Space), None);
+
+        keyboard.handle_key(VirtualKeyCode::Space, ElementState::Pressed);
+        assert_eq!(keyboard.held_duration(VirtualKeyCode::Space), Some(Duration::from_secs(0)));
+
+        clock.advance(Duration::from_millis(250));
+        assert_eq!(keyboard.held_duration(VirtualKeyCode::Space), Some(Duration::from_millis(250)));
+
+        keyboard.handle_key(VirtualKeyCode::Space, ElementState::Released);
+        assert_eq!(keyboard.held_duration(VirtualKeyCode::Space), None);
+    }
+
+    #[test]
+    fn modifiers_are_tracked_separately_from_keys() {
+        let mut keyboard = Keyboard::with_clock(ManualClock::new());
+        assert!(keyboard.modifiers().is_empty());
+
+        keyboard.set_modifiers(ModifiersState::CTRL | ModifiersState::SHIFT);
+        assert!(keyboard.modifiers().ctrl());
+        assert!(keyboard.modifiers().shift());
+        assert!(!keyboard.modifiers().alt());
+        assert_eq!(keyboard.pressed_keys().count(), 0);
+
+        keyboard.end_frame();
+        assert_eq!(keyboard.modifiers(), ModifiersState::CTRL | ModifiersState::SHIFT);
+
+        keyboard.set_modifiers(ModifiersState::empty());
+        assert!(keyboard.modifiers().is_empty());
+    }
+
+    #[test]
+    fn release_all_reports_every_held_key() {
+        let mut keyboard = Keyboard::with_clock(ManualClock::new());
+        keyboard.handle_key(VirtualKeyCode::A, ElementState::Pressed);
+        keyboard.handle_key(VirtualKeyCode::D, ElementState::Pressed);
+        keyboard.end_frame();
+
+        keyboard.release_all();
+        assert_eq!(keyboard.pressed_keys().count(), 0);
+        assert!(keyboard.just_released(VirtualKeyCode::A));
+        assert!(keyboard.just_released(VirtualKeyCode::D));
     }
 }
@@ -1,3 +1,23 @@
+mod gamepad;
+mod gestures;
+mod input;
+mod input_event;
+mod input_map;
+mod input_recording;
+mod key_combos;
 mod keyboard;
+mod mouse;
+mod text;
+mod touch;
 
-pub use keyboard::Keyboard;
\ No newline at end of file
+pub use gamepad::*;
+pub use gestures::*;
+pub use input::Input;
+pub use input_event::InputEvent;
+pub use input_map::*;
+pub use input_recording::*;
+pub use key_combos::*;
+pub use keyboard::Keyboard;
+pub use mouse::Mouse;
+pub use text::*;
+pub use touch::*;
\ No newline at end of file
@@ -0,0 +1,222 @@
+use cgmath::Vector2;
+use winit::dpi::{LogicalPosition, PhysicalPosition};
+use winit::event::{ElementState, MouseButton, MouseScrollDelta};
+
+const DEFAULT_PIXELS_PER_LINE: f32 = 20.0;
+
+pub struct Mouse {
+    pressed_buttons: Vec<MouseButton>,
+    just_pressed_buttons: Vec<MouseButton>,
+    just_released_buttons: Vec<MouseButton>,
+    position: Option<PhysicalPosition<f64>>,
+    scale_factor: f64,
+    cursor_delta: Vector2<f64>,
+    motion_delta: Vector2<f64>,
+    scroll_lines: Vector2<f32>,
+    pixels_per_line: f32,
+}
+
+impl Mouse {
+    pub fn new() -> Self {
+        Self {
+            pressed_buttons: vec![],
+            just_pressed_buttons: vec![],
+            just_released_buttons: vec![],
+            position: None,
+            scale_factor: 1.0,
+            cursor_delta: Vector2::new(0.0, 0.0),
+            motion_delta: Vector2::new(0.0, 0.0),
+            scroll_lines: Vector2::new(0.0, 0.0),
+            pixels_per_line: DEFAULT_PIXELS_PER_LINE,
+        }
+    }
+
+    pub fn with_pixels_per_line(mut self, pixels_per_line: f32) -> Self {
+        self.pixels_per_line = pixels_per_line;
+        self
+    }
+
+    pub fn handle_input(&mut self, button: MouseButton, state: ElementState) {
+        if state == ElementState::Pressed {
+            if self.is_button_pressed(button) {
+                return;
+            }
+
+            self.pressed_buttons.push(button);
+            self.just_pressed_buttons.push(button);
+        }
+        if state == ElementState::Released {
+            if let Some(index) = self.pressed_buttons.iter().position(|pressed_button| pressed_button == &button) {
+                self.pressed_buttons.remove(index);
+                self.just_released_buttons.push(button);
+            }
+        }
+    }
+
+    pub fn handle_cursor_moved(&mut self, position: PhysicalPosition<f64>) {
+        if let Some(previous) = self.position {
+            self.cursor_delta.x += position.x - previous.x;
+            self.cursor_delta.y += position.y - previous.y;
+        }
+
+        self.position = Some(position);
+    }
+
+    pub fn handle_cursor_left(&mut self) {
+        self.position = None;
+    }
+
+    // Raw `DeviceEvent::MouseMotion` deltas, these keep arriving when the
+    // cursor is grabbed and no longer produces `CursorMoved` events.
+    pub fn handle_motion(&mut self, delta: (f64, f64)) {
+        self.motion_delta.x += delta.0;
+        self.motion_delta.y += delta.1;
+    }
+
+    pub fn handle_scroll(&mut self, delta: MouseScrollDelta) {
+        match delta {
+            MouseScrollDelta::LineDelta(x, y) => {
+                self.scroll_lines.x += x;
+                self.scroll_lines.y += y;
+            },
+            MouseScrollDelta::PixelDelta(position) => {
+                let position = position.to_logical::<f64>(self.scale_factor);
+                self.scroll_lines.x += position.x as f32 / self.pixels_per_line;
+                self.scroll_lines.y += position.y as f32 / self.pixels_per_line;
+            },
+        }
+    }
+
+    pub fn set_scale_factor(&mut self, scale_factor: f64) {
+        self.scale_factor = scale_factor;
+    }
+
+    pub fn end_frame(&mut self) {
+        self.just_pressed_buttons.clear();
+        self.just_released_buttons.clear();
+        self.cursor_delta = Vector2::new(0.0, 0.0);
+        self.motion_delta = Vector2::new(0.0, 0.0);
+        self.scroll_lines = Vector2::new(0.0, 0.0);
+    }
+
+    pub fn is_button_pressed(&self, button: MouseButton) -> bool {
+        self.pressed_buttons.iter().any(|pressed_button| pressed_button == &button)
+    }
+
+    pub fn just_pressed(&self, button: MouseButton) -> bool {
+        self.just_pressed_buttons.iter().any(|pressed_button| pressed_button == &button)
+    }
+
+    pub fn just_released(&self, button: MouseButton) -> bool {
+        self.just_released_buttons.iter().any(|released_button| released_button == &button)
+    }
+
+    pub fn position(&self) -> Option<PhysicalPosition<f64>> {
+        self.position
+    }
+
+    pub fn logical_position(&self) -> Option<LogicalPosition<f64>> {
+        self.position.map(|position| position.to_logical(self.scale_factor))
+    }
+
+    pub fn cursor_delta(&self) -> Vector2<f64> {
+        self.cursor_delta
+    }
+
+    pub fn motion_delta(&self) -> Vector2<f64> {
+        self.motion_delta
+    }
+
+    pub fn scroll_lines(&self) -> Vector2<f32> {
+        self.scroll_lines
+    }
+
+    pub fn scroll_pixels(&self) -> Vector2<f32> {
+        self.scroll_lines * self.pixels_per_line
+    }
+}
+
+impl Default for Mouse {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn button_edges_last_one_frame() {
+        let mut mouse = Mouse::new();
+
+        mouse.handle_input(MouseButton::Left, ElementState::Pressed);
+        mouse.handle_input(MouseButton::Left, ElementState::Pressed);
+        assert!(mouse.is_button_pressed(MouseButton::Left));
+        assert!(mouse.just_pressed(MouseButton::Left));
+
+        mouse.end_frame();
+        assert!(mouse.is_button_pressed(MouseButton::Left));
+        assert!(!mouse.just_pressed(MouseButton::Left));
+
+        mouse.handle_input(MouseButton::Left, ElementState::Released);
+        assert!(!mouse.is_button_pressed(MouseButton::Left));
+        assert!(mouse.just_released(MouseButton::Left));
+
+        mouse.end_frame();
+        assert!(!mouse.just_released(MouseButton::Left));
+
+        mouse.handle_input(MouseButton::Right, ElementState::Released);
+        assert!(!mouse.just_released(MouseButton::Right));
+    }
+
+    #[test]
+    fn deltas_accumulate_and_reset_each_frame() {
+        let mut mouse = Mouse::new();
+
+        mouse.handle_cursor_moved(PhysicalPosition::new(10.0, 10.0));
+        assert_eq!(mouse.cursor_delta(), Vector2::new(0.0, 0.0));
+        mouse.handle_cursor_moved(PhysicalPosition::new(15.0, 8.0));
+        mouse.handle_cursor_moved(PhysicalPosition::new(20.0, 4.0));
+        assert_eq!(mouse.cursor_delta(), Vector2::new(10.0, -6.0));
+
+        mouse.handle_motion((3.0, -1.0));
+        mouse.handle_motion((2.0, 4.0));
+        assert_eq!(mouse.motion_delta(), Vector2::new(5.0, 3.0));
+
+        mouse.end_frame();
+        assert_eq!(mouse.cursor_delta(), Vector2::new(0.0, 0.0));
+        assert_eq!(mouse.motion_delta(), Vector2::new(0.0, 0.0));
+        assert_eq!(mouse.position(), Some(PhysicalPosition::new(20.0, 4.0)));
+
+        mouse.handle_cursor_left();
+        mouse.handle_cursor_moved(PhysicalPosition::new(100.0, 100.0));
+        assert_eq!(mouse.cursor_delta(), Vector2::new(0.0, 0.0));
+    }
+
+    #[test]
+    fn pixel_scrolling_is_converted_to_lines() {
+        let mut mouse = Mouse::new().with_pixels_per_line(10.0);
+        mouse.set_scale_factor(2.0);
+
+        mouse.handle_scroll(MouseScrollDelta::PixelDelta(PhysicalPosition::new(0.0, 40.0)));
+        assert_eq!(mouse.scroll_lines(), Vector2::new(0.0, 2.0));
+
+        mouse.handle_scroll(MouseScrollDelta::LineDelta(1.0, -0.5));
+        assert_eq!(mouse.scroll_lines(), Vector2::new(1.0, 1.5));
+        assert_eq!(mouse.scroll_pixels(), Vector2::new(10.0, 15.0));
+
+        mouse.end_frame();
+        assert_eq!(mouse.scroll_lines(), Vector2::new(0.0, 0.0));
+    }
+
+    #[test]
+    fn logical_position_uses_the_scale_factor() {
+        let mut mouse = Mouse::new();
+        assert_eq!(mouse.logical_position(), None);
+
+        mouse.set_scale_factor(2.0);
+        mouse.handle_cursor_moved(PhysicalPosition::new(200.0, 100.0));
+        assert_eq!(mouse.logical_position(), Some(LogicalPosition::new(100.0, 50.0)));
+    }
+}
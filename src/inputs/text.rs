@@ -0,0 +1,169 @@
+use serde::{Deserialize, Serialize};
+use winit::event::{ElementState, ModifiersState, VirtualKeyCode};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum TextEvent {
+    Character(char),
+    Backspace,
+    Delete,
+    Enter,
+    Copy,
+    Cut,
+    Paste,
+    SelectAll,
+}
+
+impl TextEvent {
+    // Applies the editing part of the event to a plain string buffer,
+    // clipboard events are left to the caller.
+    pub fn apply(&self, buffer: &mut String) {
+        match self {
+            TextEvent::Character(character) => buffer.push(*character),
+            TextEvent::Backspace => {
+                buffer.pop();
+            },
+            TextEvent::Enter => buffer.push('\n'),
+            _ => {},
+        }
+    }
+}
+
+// winit 0.25 has no IME preedit events, composed text arrives as a series of
+// `ReceivedCharacter` events once the composition is committed.
+#[derive(Default)]
+pub struct TextInput {
+    events: Vec<TextEvent>,
+    text: String,
+    // Held keys, to tell OS key repeats apart from new presses.
+    pressed_keys: Vec<VirtualKeyCode>,
+}
+
+impl TextInput {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn handle_character(&mut self, character: char, modifiers: ModifiersState) {
+        match character {
+            '\u{8}' => self.events.push(TextEvent::Backspace),
+            '\u{7f}' => self.events.push(TextEvent::Delete),
+            '\r' | '\n' => self.events.push(TextEvent::Enter),
+            character if character.is_control() && character != '\t' => {},
+            // Characters typed while a shortcut modifier is held belong to the chord.
+            _ if is_shortcut_modifier(modifiers) => {},
+            character => {
+                self.text.push(character);
+                self.events.push(TextEvent::Character(character));
+            },
+        }
+    }
+
+    // Clipboard shortcuts fire once per press, not for every key repeat.
+    pub fn handle_key(&mut self, keycode: VirtualKeyCode, state: ElementState, modifiers: ModifiersState) {
+        if state == ElementState::Released {
+            self.pressed_keys.retain(|pressed_key| *pressed_key != keycode);
+            return;
+        }
+
+        if self.pressed_keys.contains(&keycode) {
+            return;
+        }
+        self.pressed_keys.push(keycode);
+
+        if !is_shortcut_modifier(modifiers) {
+            return;
+        }
+
+        let event = match keycode {
+            VirtualKeyCode::C => TextEvent::Copy,
+            VirtualKeyCode::X => TextEvent::Cut,
+            VirtualKeyCode::V => TextEvent::Paste,
+            VirtualKeyCode::A => TextEvent::SelectAll,
+            _ => return,
+        };
+        self.events.push(event);
+    }
+
+    pub fn end_frame(&mut self) {
+        self.events.clear();
+        self.text.clear();
+    }
+
+    pub fn events(&self) -> &[TextEvent] {
+        &self.events
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn apply(&self, buffer: &mut String) {
+        for event in self.events.iter() {
+            event.apply(buffer);
+        }
+    }
+}
+
+// Windows reports AltGr as Ctrl+Alt, characters typed with it are text.
+fn is_shortcut_modifier(modifiers: ModifiersState) -> bool {
+    if cfg!(target_os = "macos") {
+        modifiers.logo()
+    }
+    else {
+        modifiers.ctrl() && !modifiers.alt()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shortcut() -> ModifiersState {
+        match cfg!(target_os = "macos") {
+            true => ModifiersState::LOGO,
+            false => ModifiersState::CTRL,
+        }
+    }
+
+    #[test]
+    fn characters_typed_with_a_shortcut_modifier_are_dropped() {
+        let mut text = TextInput::new();
+        text.handle_character('a', ModifiersState::empty());
+        text.handle_character('c', shortcut());
+        assert_eq!(text.text(), "a");
+    }
+
+    #[test]
+    fn alt_gr_characters_are_text() {
+        let mut text = TextInput::new();
+        let alt_gr = ModifiersState::CTRL | ModifiersState::ALT;
+        text.handle_character('@', alt_gr);
+        text.handle_key(VirtualKeyCode::V, ElementState::Pressed, alt_gr);
+        assert_eq!(text.events(), &[TextEvent::Character('@')]);
+    }
+
+    #[test]
+    fn shortcuts_ignore_key_repeats() {
+        let mut text = TextInput::new();
+        text.handle_key(VirtualKeyCode::V, ElementState::Pressed, shortcut());
+        text.handle_key(VirtualKeyCode::V, ElementState::Pressed, shortcut());
+        text.handle_key(VirtualKeyCode::V, ElementState::Pressed, shortcut());
+        assert_eq!(text.events(), &[TextEvent::Paste]);
+
+        text.handle_key(VirtualKeyCode::V, ElementState::Released, shortcut());
+        text.handle_key(VirtualKeyCode::V, ElementState::Pressed, shortcut());
+        assert_eq!(text.events(), &[TextEvent::Paste, TextEvent::Paste]);
+    }
+
+    #[test]
+    fn control_characters_become_editing_events() {
+        let mut text = TextInput::new();
+        for character in "ab\u{8}\r".chars() {
+            text.handle_character(character, ModifiersState::empty());
+        }
+
+        let mut buffer = String::new();
+        text.apply(&mut buffer);
+        assert_eq!(buffer, "a\n");
+    }
+}
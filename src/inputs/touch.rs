@@ -0,0 +1,182 @@
+use std::time::Instant;
+
+use winit::dpi::PhysicalPosition;
+use winit::event::TouchPhase;
+
+use super::{
+    DoubleTapRecognizer, DragRecognizer, Gesture, GestureRecognizer, PinchRecognizer, RotateRecognizer, TapRecognizer,
+};
+
+pub type FingerId = u64;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TouchEvent {
+    pub id: FingerId,
+    pub phase: TouchPhase,
+    pub position: PhysicalPosition<f64>,
+    pub time: Instant,
+}
+
+impl TouchEvent {
+    pub fn from_touch(touch: &winit::event::Touch, time: Instant) -> Self {
+        Self {
+            id: touch.id,
+            phase: touch.phase,
+            position: touch.location,
+            time,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Finger {
+    pub id: FingerId,
+    pub phase: TouchPhase,
+    pub position: PhysicalPosition<f64>,
+    pub start_position: PhysicalPosition<f64>,
+    pub started_at: Instant,
+}
+
+pub struct Touch {
+    fingers: Vec<Finger>,
+    released_fingers: Vec<Finger>,
+    recognizers: Vec<Box<dyn GestureRecognizer>>,
+    gestures: Vec<Gesture>,
+}
+
+impl Touch {
+    pub fn new() -> Self {
+        Self::without_recognizers()
+            .with_recognizer(TapRecognizer::new())
+            .with_recognizer(DoubleTapRecognizer::new())
+            .with_recognizer(DragRecognizer::new())
+            .with_recognizer(PinchRecognizer::new())
+            .with_recognizer(RotateRecognizer::new())
+    }
+
+    pub fn without_recognizers() -> Self {
+        Self {
+            fingers: vec![],
+            released_fingers: vec![],
+            recognizers: vec![],
+            gestures: vec![],
+        }
+    }
+
+    pub fn with_recognizer<T: GestureRecognizer + 'static>(mut self, recognizer: T) -> Self {
+        self.recognizers.push(Box::new(recognizer));
+        self
+    }
+
+    pub fn handle_event(&mut self, event: TouchEvent) {
+        match event.phase {
+            TouchPhase::Started => {
+                self.fingers.retain(|finger| finger.id != event.id);
+                self.fingers.push(Finger {
+                    id: event.id,
+                    phase: event.phase,
+                    position: event.position,
+                    start_position: event.position,
+                    started_at: event.time,
+                });
+            },
+            TouchPhase::Moved => {
+                if let Some(finger) = self.fingers.iter_mut().find(|finger| finger.id == event.id) {
+                    finger.phase = event.phase;
+                    finger.position = event.position;
+                }
+            },
+            TouchPhase::Ended | TouchPhase::Cancelled => {
+                if let Some(index) = self.fingers.iter().position(|finger| finger.id == event.id) {
+                    let mut finger = self.fingers.remove(index);
+                    finger.phase = event.phase;
+                    finger.position = event.position;
+                    self.released_fingers.push(finger);
+                }
+            },
+        }
+
+        for recognizer in self.recognizers.iter_mut() {
+            if let Some(gesture) = recognizer.handle_event(&event) {
+                self.gestures.push(gesture);
+            }
+        }
+    }
+
+    pub fn end_frame(&mut self) {
+        self.released_fingers.clear();
+        self.gestures.clear();
+    }
+
+    pub fn fingers(&self) -> &[Finger] {
+        &self.fingers
+    }
+
+    pub fn finger(&self, id: FingerId) -> Option<&Finger> {
+        self.fingers.iter().find(|finger| finger.id == id)
+    }
+
+    pub fn released_fingers(&self) -> &[Finger] {
+        &self.released_fingers
+    }
+
+    pub fn gestures(&self) -> &[Gesture] {
+        &self.gestures
+    }
+}
+
+impl Default for Touch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use winit::event::{ElementState, MouseButton};
+
+    use super::*;
+    use crate::inputs::{Gesture, Input, InputEvent, InputMap};
+    use crate::window::{Clock, ManualClock};
+
+    #[test]
+    fn fingers_are_tracked_until_released() {
+        let clock = ManualClock::new();
+        let mut touch = Touch::without_recognizers();
+        let position = PhysicalPosition::new(1.0, 2.0);
+
+        touch.handle_event(TouchEvent { id: 7, phase: TouchPhase::Started, position, time: clock.now() });
+        touch.handle_event(TouchEvent { id: 7, phase: TouchPhase::Moved, position: PhysicalPosition::new(3.0, 4.0), time: clock.now() });
+        let finger = touch.finger(7).unwrap();
+        assert_eq!(finger.start_position, position);
+        assert_eq!(finger.position, PhysicalPosition::new(3.0, 4.0));
+
+        touch.handle_event(TouchEvent { id: 7, phase: TouchPhase::Ended, position, time: clock.now() });
+        assert!(touch.fingers().is_empty());
+        assert_eq!(touch.released_fingers().len(), 1);
+
+        touch.end_frame();
+        assert!(touch.released_fingers().is_empty());
+    }
+
+    #[test]
+    fn input_timestamps_touches_with_its_clock() {
+        let clock = ManualClock::new();
+        let mut input = Input::with_clock(clock.clone(), InputMap::new());
+        let position = PhysicalPosition::new(10.0, 10.0);
+
+        // Mouse input in between does not disturb the touch gestures.
+        input.handle_event(InputEvent::MouseButton(MouseButton::Left, ElementState::Pressed));
+        input.handle_event(InputEvent::Touch(0, TouchPhase::Started, position));
+        let started_at = clock.now();
+        clock.advance(Duration::from_millis(100));
+        input.handle_event(InputEvent::MouseButton(MouseButton::Left, ElementState::Released));
+        input.handle_event(InputEvent::Touch(0, TouchPhase::Ended, position));
+
+        assert_eq!(input.touch.released_fingers()[0].started_at, started_at);
+        assert!(input.touch.gestures().contains(&Gesture::Tap { position }));
+        assert!(input.mouse.just_released(MouseButton::Left));
+    }
+}
@@ -1,8 +1,12 @@
+// Lets the derive macros' `::webgpu::` paths resolve inside this crate too.
+extern crate self as webgpu;
+
 pub mod inputs;
 pub mod transforms;
+pub mod camera;
 pub mod config;
 // pub mod components;
 // pub mod systems;
 // pub mod ecs;
 pub mod window;
-// pub mod render;
\ No newline at end of file
+pub mod render;
\ No newline at end of file
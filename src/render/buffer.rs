@@ -0,0 +1,435 @@
+use std::error::Error;
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem::size_of;
+use std::ops::{Deref, DerefMut};
+
+use bytemuck::Pod;
+use wgpu::util::DeviceExt;
+
+// `queue.write_buffer` needs offsets and sizes that are multiples of this.
+pub const COPY_BUFFER_ALIGNMENT: u64 = wgpu::COPY_BUFFER_ALIGNMENT;
+// WGSL rounds the size of uniform structs up to a multiple of 16 bytes.
+pub const UNIFORM_BUFFER_ALIGNMENT: u64 = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BufferError {
+    MisalignedOffset { offset: u64, alignment: u64 },
+    MisalignedSize { size: u64, alignment: u64 },
+    OutOfBounds { offset: u64, size: u64, capacity: u64 },
+    ZeroSizedElement,
+}
+
+impl fmt::Display for BufferError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BufferError::MisalignedOffset { offset, alignment } => {
+                write!(f, "buffer offset {} is not a multiple of {}", offset, alignment)
+            },
+            BufferError::MisalignedSize { size, alignment } => {
+                write!(f, "buffer write of {} bytes is not a multiple of {}", size, alignment)
+            },
+            BufferError::OutOfBounds { offset, size, capacity } => {
+                write!(f, "writing {} bytes at offset {} exceeds the buffer capacity of {} bytes", size, offset, capacity)
+            },
+            BufferError::ZeroSizedElement => write!(f, "buffers of zero sized elements are not supported"),
+        }
+    }
+}
+
+impl Error for BufferError { }
+
+pub const fn align_to(size: u64, alignment: u64) -> u64 {
+    match size % alignment {
+        0 => size,
+        remainder => size + alignment - remainder,
+    }
+}
+
+// Checks a write of `size` bytes at byte `offset` into a buffer of `capacity` bytes.
+pub fn validate_write(offset: u64, size: u64, capacity: u64) -> Result<(), BufferError> {
+    if align_to(offset, COPY_BUFFER_ALIGNMENT) != offset {
+        return Err(BufferError::MisalignedOffset {
+            offset,
+            alignment: COPY_BUFFER_ALIGNMENT,
+        });
+    }
+
+    if align_to(size, COPY_BUFFER_ALIGNMENT) != size {
+        return Err(BufferError::MisalignedSize {
+            size,
+            alignment: COPY_BUFFER_ALIGNMENT,
+        });
+    }
+
+    if offset + size > capacity {
+        return Err(BufferError::OutOfBounds {
+            offset,
+            size,
+            capacity,
+        });
+    }
+
+    Ok(())
+}
+
+pub fn uniform_buffer_size<T>() -> u64 {
+    align_to(size_of::<T>() as u64, UNIFORM_BUFFER_ALIGNMENT)
+}
+
+// Capacity to grow to when `required` elements no longer fit.
+pub fn grown_capacity(capacity: usize, required: usize) -> usize {
+    required.max(capacity * 2)
+}
+
+// Byte offset of a write of `len` elements at element `offset` into a buffer
+// holding `capacity` elements. Partial writes are never padded, that would
+// overwrite the elements after them.
+pub fn validate_range_write<T>(offset: usize, len: usize, capacity: usize) -> Result<u64, BufferError> {
+    if size_of::<T>() == 0 {
+        return Err(BufferError::ZeroSizedElement);
+    }
+
+    let byte_offset = (offset * size_of::<T>()) as u64;
+    let size = (len * size_of::<T>()) as u64;
+    validate_write(byte_offset, size, (capacity * size_of::<T>()) as u64)?;
+    Ok(byte_offset)
+}
+
+fn buffer_size(bytes: u64) -> u64 {
+    align_to(bytes.max(COPY_BUFFER_ALIGNMENT), COPY_BUFFER_ALIGNMENT)
+}
+
+// `write_buffer` rejects sizes that are not a multiple of four bytes, for
+// example an odd number of u16 indices, so those get zero padded. Only for
+// writes that own everything after them.
+fn padded_bytes(bytes: &[u8]) -> std::borrow::Cow<'_, [u8]> {
+    let padded_len = align_to(bytes.len() as u64, COPY_BUFFER_ALIGNMENT) as usize;
+    if padded_len == bytes.len() {
+        return std::borrow::Cow::Borrowed(bytes);
+    }
+
+    let mut padded = bytes.to_vec();
+    padded.resize(padded_len, 0);
+    std::borrow::Cow::Owned(padded)
+}
+
+pub struct TypedBuffer<T: Pod> {
+    buffer: wgpu::Buffer,
+    label: Option<&'static str>,
+    usage: wgpu::BufferUsage,
+    len: usize,
+    capacity: usize,
+    _element: PhantomData<T>,
+}
+
+impl<T: Pod> TypedBuffer<T> {
+    pub fn new(device: &wgpu::Device, label: Option<&'static str>, usage: wgpu::BufferUsage, contents: &[T]) -> Self {
+        let usage = usage | wgpu::BufferUsage::COPY_DST;
+        let buffer = create_buffer(device, label, usage, contents, contents.len());
+
+        Self {
+            buffer,
+            label,
+            usage,
+            len: contents.len(),
+            capacity: contents.len(),
+            _element: PhantomData,
+        }
+    }
+
+    pub fn with_capacity(device: &wgpu::Device, label: Option<&'static str>, usage: wgpu::BufferUsage, capacity: usize) -> Self {
+        let usage = usage | wgpu::BufferUsage::COPY_DST;
+        let buffer = create_buffer::<T>(device, label, usage, &[], capacity);
+
+        Self {
+            buffer,
+            label,
+            usage,
+            len: 0,
+            capacity,
+            _element: PhantomData,
+        }
+    }
+
+    pub fn buffer(&self) -> &wgpu::Buffer {
+        &self.buffer
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn size_in_bytes(&self) -> u64 {
+        (self.len * size_of::<T>()) as u64
+    }
+
+    // Only the part of the buffer that holds elements, `None` when it is
+    // empty since wgpu does not allow empty slices.
+    pub fn slice(&self) -> Option<wgpu::BufferSlice<'_>> {
+        match self.is_empty() {
+            true => None,
+            false => Some(self.buffer.slice(..self.size_in_bytes())),
+        }
+    }
+
+    pub fn as_entire_binding(&self) -> wgpu::BindingResource<'_> {
+        self.buffer.as_entire_binding()
+    }
+
+    // Replaces the contents, reallocating when they no longer fit. Returns
+    // true when the buffer was reallocated, bind groups using it have to be
+    // recreated in that case.
+    pub fn write(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, contents: &[T]) -> bool {
+        if contents.len() > self.capacity {
+            let capacity = grown_capacity(self.capacity, contents.len());
+            self.buffer = create_buffer(device, self.label, self.usage, contents, capacity);
+            self.capacity = capacity;
+            self.len = contents.len();
+            return true;
+        }
+
+        if !contents.is_empty() {
+            queue.write_buffer(&self.buffer, 0, &padded_bytes(bytemuck::cast_slice(contents)));
+        }
+        self.len = contents.len();
+        false
+    }
+
+    // Overwrites elements starting at element `offset` without reallocating.
+    // The byte offset and size of the write have to be multiples of
+    // `COPY_BUFFER_ALIGNMENT`, for u16 elements that means an even offset
+    // and length.
+    pub fn write_range(&mut self, queue: &wgpu::Queue, offset: usize, contents: &[T]) -> Result<(), BufferError> {
+        let byte_offset = validate_range_write::<T>(offset, contents.len(), self.capacity)?;
+
+        if !contents.is_empty() {
+            queue.write_buffer(&self.buffer, byte_offset, bytemuck::cast_slice(contents));
+        }
+        self.len = self.len.max(offset + contents.len());
+        Ok(())
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+}
+
+fn create_buffer<T: Pod>(device: &wgpu::Device, label: Option<&'static str>, usage: wgpu::BufferUsage, contents: &[T], capacity: usize) -> wgpu::Buffer {
+    let size = buffer_size((capacity * size_of::<T>()) as u64);
+
+    if contents.is_empty() {
+        let buffer_descriptor = wgpu::BufferDescriptor {
+            label,
+            size,
+            usage,
+            mapped_at_creation: false,
+        };
+        return device.create_buffer(&buffer_descriptor);
+    }
+
+    let mut data = bytemuck::cast_slice::<T, u8>(contents).to_vec();
+    data.resize(size as usize, 0);
+
+    let buffer_descriptor = wgpu::util::BufferInitDescriptor {
+        label,
+        contents: &data,
+        usage,
+    };
+    device.create_buffer_init(&buffer_descriptor)
+}
+
+pub struct VertexBuffer<T: Pod>(TypedBuffer<T>);
+
+impl<T: Pod> VertexBuffer<T> {
+    pub fn new(device: &wgpu::Device, label: Option<&'static str>, contents: &[T]) -> Self {
+        Self(TypedBuffer::new(device, label, wgpu::BufferUsage::VERTEX, contents))
+    }
+
+    pub fn with_capacity(device: &wgpu::Device, label: Option<&'static str>, capacity: usize) -> Self {
+        Self(TypedBuffer::with_capacity(device, label, wgpu::BufferUsage::VERTEX, capacity))
+    }
+}
+
+impl<T: Pod> Deref for VertexBuffer<T> {
+    type Target = TypedBuffer<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T: Pod> DerefMut for VertexBuffer<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+pub trait IndexElement: Pod {
+    const FORMAT: wgpu::IndexFormat;
+}
+
+impl IndexElement for u16 {
+    const FORMAT: wgpu::IndexFormat = wgpu::IndexFormat::Uint16;
+}
+
+impl IndexElement for u32 {
+    const FORMAT: wgpu::IndexFormat = wgpu::IndexFormat::Uint32;
+}
+
+pub struct IndexBuffer<I: IndexElement>(TypedBuffer<I>);
+
+impl<I: IndexElement> IndexBuffer<I> {
+    pub fn new(device: &wgpu::Device, label: Option<&'static str>, contents: &[I]) -> Self {
+        Self(TypedBuffer::new(device, label, wgpu::BufferUsage::INDEX, contents))
+    }
+
+    pub fn with_capacity(device: &wgpu::Device, label: Option<&'static str>, capacity: usize) -> Self {
+        Self(TypedBuffer::with_capacity(device, label, wgpu::BufferUsage::INDEX, capacity))
+    }
+
+    pub fn format(&self) -> wgpu::IndexFormat {
+        I::FORMAT
+    }
+}
+
+impl<I: IndexElement> Deref for IndexBuffer<I> {
+    type Target = TypedBuffer<I>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<I: IndexElement> DerefMut for IndexBuffer<I> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+pub struct StorageBuffer<T: Pod>(TypedBuffer<T>);
+
+impl<T: Pod> StorageBuffer<T> {
+    pub fn new(device: &wgpu::Device, label: Option<&'static str>, contents: &[T]) -> Self {
+        Self(TypedBuffer::new(device, label, wgpu::BufferUsage::STORAGE, contents))
+    }
+
+    pub fn with_capacity(device: &wgpu::Device, label: Option<&'static str>, capacity: usize) -> Self {
+        Self(TypedBuffer::with_capacity(device, label, wgpu::BufferUsage::STORAGE, capacity))
+    }
+}
+
+impl<T: Pod> Deref for StorageBuffer<T> {
+    type Target = TypedBuffer<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T: Pod> DerefMut for StorageBuffer<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+// Holds a single value, sized up to the uniform alignment so a struct without
+// trailing padding can still be bound to a WGSL uniform block.
+pub struct UniformBuffer<T: Pod> {
+    buffer: wgpu::Buffer,
+    _value: PhantomData<T>,
+}
+
+impl<T: Pod> UniformBuffer<T> {
+    pub fn new(device: &wgpu::Device, label: Option<&'static str>, value: &T) -> Self {
+        let mut data = bytemuck::bytes_of(value).to_vec();
+        data.resize(uniform_buffer_size::<T>() as usize, 0);
+
+        let buffer_descriptor = wgpu::util::BufferInitDescriptor {
+            label,
+            contents: &data,
+            usage: wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
+        };
+
+        Self {
+            buffer: device.create_buffer_init(&buffer_descriptor),
+            _value: PhantomData,
+        }
+    }
+
+    pub fn buffer(&self) -> &wgpu::Buffer {
+        &self.buffer
+    }
+
+    pub fn size_in_bytes(&self) -> u64 {
+        uniform_buffer_size::<T>()
+    }
+
+    pub fn as_entire_binding(&self) -> wgpu::BindingResource<'_> {
+        self.buffer.as_entire_binding()
+    }
+
+    pub fn write(&self, queue: &wgpu::Queue, value: &T) {
+        queue.write_buffer(&self.buffer, 0, &padded_bytes(bytemuck::bytes_of(value)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn align_to_rounds_up_to_the_alignment() {
+        assert_eq!(align_to(0, 4), 0);
+        assert_eq!(align_to(1, 4), 4);
+        assert_eq!(align_to(4, 4), 4);
+        assert_eq!(align_to(17, 16), 32);
+    }
+
+    #[test]
+    fn validate_write_rejects_out_of_bounds_and_misaligned_writes() {
+        assert_eq!(validate_write(0, 16, 16), Ok(()));
+        assert_eq!(validate_write(8, 8, 16), Ok(()));
+        assert_eq!(validate_write(8, 12, 16), Err(BufferError::OutOfBounds { offset: 8, size: 12, capacity: 16 }));
+        assert_eq!(validate_write(2, 4, 16), Err(BufferError::MisalignedOffset { offset: 2, alignment: COPY_BUFFER_ALIGNMENT }));
+        assert_eq!(validate_write(4, 6, 16), Err(BufferError::MisalignedSize { size: 6, alignment: COPY_BUFFER_ALIGNMENT }));
+    }
+
+    #[test]
+    fn range_writes_of_u16_must_cover_whole_words() {
+        assert_eq!(validate_range_write::<u16>(2, 4, 8), Ok(4));
+        assert_eq!(validate_range_write::<u16>(1, 2, 8), Err(BufferError::MisalignedOffset { offset: 2, alignment: COPY_BUFFER_ALIGNMENT }));
+        assert_eq!(validate_range_write::<u16>(0, 1, 8), Err(BufferError::MisalignedSize { size: 2, alignment: COPY_BUFFER_ALIGNMENT }));
+        assert_eq!(validate_range_write::<u16>(6, 4, 8), Err(BufferError::OutOfBounds { offset: 12, size: 8, capacity: 16 }));
+        assert_eq!(validate_range_write::<()>(0, 1, 8), Err(BufferError::ZeroSizedElement));
+    }
+
+    #[test]
+    fn grown_capacity_at_least_doubles() {
+        assert_eq!(grown_capacity(0, 3), 3);
+        assert_eq!(grown_capacity(4, 5), 8);
+        assert_eq!(grown_capacity(4, 20), 20);
+    }
+
+    #[test]
+    fn uniform_buffer_size_is_a_multiple_of_16() {
+        assert_eq!(uniform_buffer_size::<[f32; 3]>(), 16);
+        assert_eq!(uniform_buffer_size::<[f32; 4]>(), 16);
+        assert_eq!(uniform_buffer_size::<[f32; 5]>(), 32);
+    }
+
+    #[test]
+    fn padded_bytes_zero_fills_to_four_bytes() {
+        assert_eq!(&*padded_bytes(&[1, 2, 3, 4]), &[1, 2, 3, 4]);
+        assert_eq!(&*padded_bytes(&[1, 2]), &[1, 2, 0, 0]);
+        assert_eq!(&*padded_bytes(&[1, 2, 3, 4, 5]), &[1, 2, 3, 4, 5, 0, 0, 0]);
+    }
+}
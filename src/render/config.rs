@@ -0,0 +1,262 @@
+use std::error::Error;
+use std::path::PathBuf;
+use serde::Deserialize;
+
+use crate::config::{is_not_found, load_configuration};
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum GpuBackends {
+    Primary,
+    Secondary,
+    All,
+    Vulkan,
+    Metal,
+    Dx12,
+    Dx11,
+    Gl,
+    BrowserWebGpu,
+}
+
+impl GpuBackends {
+    pub fn to_backend_bit(&self) -> wgpu::BackendBit {
+        match self {
+            GpuBackends::Primary => wgpu::BackendBit::PRIMARY,
+            GpuBackends::Secondary => wgpu::BackendBit::SECONDARY,
+            GpuBackends::All => wgpu::BackendBit::all(),
+            GpuBackends::Vulkan => wgpu::BackendBit::VULKAN,
+            GpuBackends::Metal => wgpu::BackendBit::METAL,
+            GpuBackends::Dx12 => wgpu::BackendBit::DX12,
+            GpuBackends::Dx11 => wgpu::BackendBit::DX11,
+            GpuBackends::Gl => wgpu::BackendBit::GL,
+            GpuBackends::BrowserWebGpu => wgpu::BackendBit::BROWSER_WEBGPU,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum GpuPowerPreference {
+    LowPower,
+    HighPerformance,
+}
+
+impl GpuPowerPreference {
+    pub fn to_power_preference(&self) -> wgpu::PowerPreference {
+        match self {
+            GpuPowerPreference::LowPower => wgpu::PowerPreference::LowPower,
+            GpuPowerPreference::HighPerformance => wgpu::PowerPreference::HighPerformance,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum GpuFeature {
+    DepthClamping,
+    TextureCompressionBc,
+    TextureCompressionEtc2,
+    TextureCompressionAstcLdr,
+    TimestampQuery,
+    PipelineStatisticsQuery,
+    MappablePrimaryBuffers,
+    SampledTextureBindingArray,
+    SampledTextureArrayDynamicIndexing,
+    SampledTextureArrayNonUniformIndexing,
+    UnsizedBindingArray,
+    MultiDrawIndirect,
+    MultiDrawIndirectCount,
+    PushConstants,
+    AddressModeClampToBorder,
+    NonFillPolygonMode,
+    ShaderFloat64,
+    VertexAttribute64Bit,
+    ConservativeRasterization,
+    BufferBindingArray,
+    VertexWritableStorage,
+    ClearCommands,
+}
+
+impl GpuFeature {
+    pub fn to_features(&self) -> wgpu::Features {
+        match self {
+            GpuFeature::DepthClamping => wgpu::Features::DEPTH_CLAMPING,
+            GpuFeature::TextureCompressionBc => wgpu::Features::TEXTURE_COMPRESSION_BC,
+            GpuFeature::TextureCompressionEtc2 => wgpu::Features::TEXTURE_COMPRESSION_ETC2,
+            GpuFeature::TextureCompressionAstcLdr => wgpu::Features::TEXTURE_COMPRESSION_ASTC_LDR,
+            GpuFeature::TimestampQuery => wgpu::Features::TIMESTAMP_QUERY,
+            GpuFeature::PipelineStatisticsQuery => wgpu::Features::PIPELINE_STATISTICS_QUERY,
+            GpuFeature::MappablePrimaryBuffers => wgpu::Features::MAPPABLE_PRIMARY_BUFFERS,
+            GpuFeature::SampledTextureBindingArray => wgpu::Features::SAMPLED_TEXTURE_BINDING_ARRAY,
+            GpuFeature::SampledTextureArrayDynamicIndexing => wgpu::Features::SAMPLED_TEXTURE_ARRAY_DYNAMIC_INDEXING,
+            GpuFeature::SampledTextureArrayNonUniformIndexing => wgpu::Features::SAMPLED_TEXTURE_ARRAY_NON_UNIFORM_INDEXING,
+            GpuFeature::UnsizedBindingArray => wgpu::Features::UNSIZED_BINDING_ARRAY,
+            GpuFeature::MultiDrawIndirect => wgpu::Features::MULTI_DRAW_INDIRECT,
+            GpuFeature::MultiDrawIndirectCount => wgpu::Features::MULTI_DRAW_INDIRECT_COUNT,
+            GpuFeature::PushConstants => wgpu::Features::PUSH_CONSTANTS,
+            GpuFeature::AddressModeClampToBorder => wgpu::Features::ADDRESS_MODE_CLAMP_TO_BORDER,
+            GpuFeature::NonFillPolygonMode => wgpu::Features::NON_FILL_POLYGON_MODE,
+            GpuFeature::ShaderFloat64 => wgpu::Features::SHADER_FLOAT64,
+            GpuFeature::VertexAttribute64Bit => wgpu::Features::VERTEX_ATTRIBUTE_64BIT,
+            GpuFeature::ConservativeRasterization => wgpu::Features::CONSERVATIVE_RASTERIZATION,
+            GpuFeature::BufferBindingArray => wgpu::Features::BUFFER_BINDING_ARRAY,
+            GpuFeature::VertexWritableStorage => wgpu::Features::VERTEX_WRITABLE_STORAGE,
+            GpuFeature::ClearCommands => wgpu::Features::CLEAR_COMMANDS,
+        }
+    }
+}
+
+// Every limit left out falls back to `wgpu::Limits::default()`.
+#[derive(Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct GpuLimits {
+    pub max_texture_dimension_1d: Option<u32>,
+    pub max_texture_dimension_2d: Option<u32>,
+    pub max_texture_dimension_3d: Option<u32>,
+    pub max_texture_array_layers: Option<u32>,
+    pub max_bind_groups: Option<u32>,
+    pub max_dynamic_uniform_buffers_per_pipeline_layout: Option<u32>,
+    pub max_dynamic_storage_buffers_per_pipeline_layout: Option<u32>,
+    pub max_sampled_textures_per_shader_stage: Option<u32>,
+    pub max_samplers_per_shader_stage: Option<u32>,
+    pub max_storage_buffers_per_shader_stage: Option<u32>,
+    pub max_storage_textures_per_shader_stage: Option<u32>,
+    pub max_uniform_buffers_per_shader_stage: Option<u32>,
+    pub max_uniform_buffer_binding_size: Option<u32>,
+    pub max_storage_buffer_binding_size: Option<u32>,
+    pub max_vertex_buffers: Option<u32>,
+    pub max_vertex_attributes: Option<u32>,
+    pub max_vertex_buffer_array_stride: Option<u32>,
+    pub max_push_constant_size: Option<u32>,
+}
+
+impl GpuLimits {
+    pub fn to_limits(&self) -> wgpu::Limits {
+        let defaults = wgpu::Limits::default();
+
+        wgpu::Limits {
+            max_texture_dimension_1d: self.max_texture_dimension_1d.unwrap_or(defaults.max_texture_dimension_1d),
+            max_texture_dimension_2d: self.max_texture_dimension_2d.unwrap_or(defaults.max_texture_dimension_2d),
+            max_texture_dimension_3d: self.max_texture_dimension_3d.unwrap_or(defaults.max_texture_dimension_3d),
+            max_texture_array_layers: self.max_texture_array_layers.unwrap_or(defaults.max_texture_array_layers),
+            max_bind_groups: self.max_bind_groups.unwrap_or(defaults.max_bind_groups),
+            max_dynamic_uniform_buffers_per_pipeline_layout: self.max_dynamic_uniform_buffers_per_pipeline_layout.unwrap_or(defaults.max_dynamic_uniform_buffers_per_pipeline_layout),
+            max_dynamic_storage_buffers_per_pipeline_layout: self.max_dynamic_storage_buffers_per_pipeline_layout.unwrap_or(defaults.max_dynamic_storage_buffers_per_pipeline_layout),
+            max_sampled_textures_per_shader_stage: self.max_sampled_textures_per_shader_stage.unwrap_or(defaults.max_sampled_textures_per_shader_stage),
+            max_samplers_per_shader_stage: self.max_samplers_per_shader_stage.unwrap_or(defaults.max_samplers_per_shader_stage),
+            max_storage_buffers_per_shader_stage: self.max_storage_buffers_per_shader_stage.unwrap_or(defaults.max_storage_buffers_per_shader_stage),
+            max_storage_textures_per_shader_stage: self.max_storage_textures_per_shader_stage.unwrap_or(defaults.max_storage_textures_per_shader_stage),
+            max_uniform_buffers_per_shader_stage: self.max_uniform_buffers_per_shader_stage.unwrap_or(defaults.max_uniform_buffers_per_shader_stage),
+            max_uniform_buffer_binding_size: self.max_uniform_buffer_binding_size.unwrap_or(defaults.max_uniform_buffer_binding_size),
+            max_storage_buffer_binding_size: self.max_storage_buffer_binding_size.unwrap_or(defaults.max_storage_buffer_binding_size),
+            max_vertex_buffers: self.max_vertex_buffers.unwrap_or(defaults.max_vertex_buffers),
+            max_vertex_attributes: self.max_vertex_attributes.unwrap_or(defaults.max_vertex_attributes),
+            max_vertex_buffer_array_stride: self.max_vertex_buffer_array_stride.unwrap_or(defaults.max_vertex_buffer_array_stride),
+            max_push_constant_size: self.max_push_constant_size.unwrap_or(defaults.max_push_constant_size),
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct GpuConfiguration {
+    pub backends: GpuBackends,
+    pub power_preference: GpuPowerPreference,
+    #[serde(default)]
+    pub required_features: Vec<GpuFeature>,
+    #[serde(default)]
+    pub limits: GpuLimits,
+}
+
+impl GpuConfiguration {
+    pub fn new() -> Self {
+        Self::load(PathBuf::from("./gpu.config")).unwrap_or_else(|error| {
+            log::warn!("failed to load gpu.config, using the default configuration: {}", error);
+            Self::default()
+        })
+    }
+
+    // A missing file is the default configuration, a malformed one an error.
+    pub fn load(file_path: PathBuf) -> Result<Self, Box<dyn Error>> {
+        match load_configuration(file_path) {
+            Ok(config) => Ok(config),
+            Err(error) if is_not_found(error.as_ref()) => Ok(Self::default()),
+            Err(error) => Err(error),
+        }
+    }
+
+    pub fn features(&self) -> wgpu::Features {
+        self.required_features
+            .iter()
+            .fold(wgpu::Features::empty(), |features, feature| features | feature.to_features())
+    }
+}
+
+impl Default for GpuConfiguration {
+    fn default() -> Self {
+        default_gpu_configuration()
+    }
+}
+
+fn default_gpu_configuration() -> GpuConfiguration {
+    GpuConfiguration {
+        backends: GpuBackends::Primary,
+        power_preference: GpuPowerPreference::LowPower,
+        required_features: vec![],
+        limits: GpuLimits::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    fn parse(json: &str) -> Result<GpuConfiguration, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    #[test]
+    fn optional_fields_fall_back_to_defaults() {
+        let config = parse(r#"{"backends": "Vulkan", "power_preference": "HighPerformance"}"#).unwrap();
+        assert_eq!(config.backends.to_backend_bit(), wgpu::BackendBit::VULKAN);
+        assert_eq!(config.power_preference.to_power_preference(), wgpu::PowerPreference::HighPerformance);
+        assert_eq!(config.features(), wgpu::Features::empty());
+        assert_eq!(config.limits.to_limits(), wgpu::Limits::default());
+    }
+
+    #[test]
+    fn features_and_limits_are_read() {
+        let config = parse(r#"{
+            "backends": "Primary",
+            "power_preference": "LowPower",
+            "required_features": ["NonFillPolygonMode", "PushConstants"],
+            "limits": {"max_bind_groups": 8, "max_push_constant_size": 128}
+        }"#).unwrap();
+
+        assert_eq!(config.features(), wgpu::Features::NON_FILL_POLYGON_MODE | wgpu::Features::PUSH_CONSTANTS);
+        let limits = config.limits.to_limits();
+        assert_eq!(limits.max_bind_groups, 8);
+        assert_eq!(limits.max_push_constant_size, 128);
+        assert_eq!(limits.max_vertex_buffers, wgpu::Limits::default().max_vertex_buffers);
+    }
+
+    #[test]
+    fn unknown_values_are_errors() {
+        assert!(parse(r#"{"backends": "Glide", "power_preference": "LowPower"}"#).is_err());
+        assert!(parse(r#"{"backends": "All", "power_preference": "LowPower", "required_features": ["Raytracing"]}"#).is_err());
+        assert!(parse(r#"{"power_preference": "LowPower"}"#).is_err());
+    }
+
+    #[test]
+    fn only_a_missing_file_loads_the_default() {
+        let directory = std::env::temp_dir().join(format!("webgpu-gpu-config-test-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let malformed = directory.join("malformed.config");
+        fs::write(&malformed, r#"{"backends": "Primary""#).unwrap();
+
+        let missing = GpuConfiguration::load(directory.join("missing.config"));
+        let malformed = GpuConfiguration::load(malformed);
+        fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(missing.unwrap(), GpuConfiguration::default());
+        assert!(malformed.is_err());
+    }
+}
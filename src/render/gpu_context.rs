@@ -0,0 +1,195 @@
+use std::error::Error;
+use std::fmt;
+
+use super::GpuConfiguration;
+
+pub struct GpuContext {
+    pub instance: wgpu::Instance,
+    pub adapter: wgpu::Adapter,
+    pub device: wgpu::Device,
+    pub queue: wgpu::Queue,
+}
+
+#[derive(Debug)]
+pub struct RejectedAdapter {
+    pub info: wgpu::AdapterInfo,
+    pub missing_features: wgpu::Features,
+    pub unsupported_limits: Vec<String>,
+    pub supports_surface: bool,
+}
+
+#[derive(Debug)]
+pub enum GpuContextError {
+    NoAdapters { backends: wgpu::BackendBit },
+    NoMatchingAdapter { rejected: Vec<RejectedAdapter> },
+    RequestDevice(wgpu::RequestDeviceError),
+}
+
+impl fmt::Display for GpuContextError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GpuContextError::NoAdapters { backends } => {
+                write!(f, "no graphics adapters found for backends {:?}", backends)
+            },
+            GpuContextError::NoMatchingAdapter { rejected } => {
+                write!(f, "no graphics adapter matches the configuration")?;
+                for adapter in rejected.iter() {
+                    write!(f, "\n  {} ({:?}):", adapter.info.name, adapter.info.backend)?;
+                    if !adapter.supports_surface {
+                        write!(f, " cannot present to the window surface;")?;
+                    }
+                    if !adapter.missing_features.is_empty() {
+                        write!(f, " missing features {:?};", adapter.missing_features)?;
+                    }
+                    if !adapter.unsupported_limits.is_empty() {
+                        write!(f, " unsupported limits {};", adapter.unsupported_limits.join(", "))?;
+                    }
+                }
+                Ok(())
+            },
+            GpuContextError::RequestDevice(error) => write!(f, "failed to create device and queue: {}", error),
+        }
+    }
+}
+
+impl Error for GpuContextError { }
+
+impl GpuContext {
+    pub async fn new(config: &GpuConfiguration) -> Result<Self, GpuContextError> {
+        let instance = wgpu::Instance::new(config.backends.to_backend_bit());
+
+        Self::from_instance(instance, None, config).await
+    }
+
+    pub async fn with_surface(window: &winit::window::Window, config: &GpuConfiguration) -> Result<(Self, wgpu::Surface), GpuContextError> {
+        let instance = wgpu::Instance::new(config.backends.to_backend_bit());
+        let surface = unsafe { instance.create_surface(window) };
+
+        let context = Self::from_instance(instance, Some(&surface), config).await?;
+        Ok((context, surface))
+    }
+
+    async fn from_instance(instance: wgpu::Instance, surface: Option<&wgpu::Surface>, config: &GpuConfiguration) -> Result<Self, GpuContextError> {
+        let adapter = select_adapter(&instance, surface, config).await?;
+
+        let device_descriptor = wgpu::DeviceDescriptor {
+            features: config.features(),
+            limits: config.limits.to_limits(),
+            label: Some("Device"),
+        };
+        let trace_path = None;
+        let (device, queue) = adapter
+            .request_device(&device_descriptor, trace_path)
+            .await
+            .map_err(GpuContextError::RequestDevice)?;
+
+        Ok(Self {
+            instance,
+            adapter,
+            device,
+            queue,
+        })
+    }
+
+    pub fn adapter_info(&self) -> wgpu::AdapterInfo {
+        self.adapter.get_info()
+    }
+}
+
+async fn select_adapter(instance: &wgpu::Instance, surface: Option<&wgpu::Surface>, config: &GpuConfiguration) -> Result<wgpu::Adapter, GpuContextError> {
+    let adapter_options = wgpu::RequestAdapterOptions {
+        power_preference: config.power_preference.to_power_preference(),
+        compatible_surface: surface,
+    };
+
+    if let Some(adapter) = instance.request_adapter(&adapter_options).await {
+        if check_adapter(&adapter, surface, config).is_none() {
+            return Ok(adapter);
+        }
+    }
+
+    // The preferred adapter did not fit, fall back to any adapter that does
+    // and remember why the others were rejected.
+    let backends = config.backends.to_backend_bit();
+    let mut rejected = vec![];
+    for adapter in instance.enumerate_adapters(backends) {
+        match check_adapter(&adapter, surface, config) {
+            None => return Ok(adapter),
+            Some(rejection) => rejected.push(rejection),
+        }
+    }
+
+    if rejected.is_empty() {
+        return Err(GpuContextError::NoAdapters { backends });
+    }
+
+    Err(GpuContextError::NoMatchingAdapter { rejected })
+}
+
+fn check_adapter(adapter: &wgpu::Adapter, surface: Option<&wgpu::Surface>, config: &GpuConfiguration) -> Option<RejectedAdapter> {
+    let missing_features = config.features() - adapter.features();
+    let unsupported_limits = unsupported_limits(&config.limits.to_limits(), &adapter.limits());
+    let supports_surface = surface.map_or(true, |surface| adapter.get_swap_chain_preferred_format(surface).is_some());
+
+    if missing_features.is_empty() && unsupported_limits.is_empty() && supports_surface {
+        return None;
+    }
+
+    Some(RejectedAdapter {
+        info: adapter.get_info(),
+        missing_features,
+        unsupported_limits,
+        supports_surface,
+    })
+}
+
+pub fn unsupported_limits(required: &wgpu::Limits, supported: &wgpu::Limits) -> Vec<String> {
+    let limits = [
+        ("max_texture_dimension_1d", required.max_texture_dimension_1d, supported.max_texture_dimension_1d),
+        ("max_texture_dimension_2d", required.max_texture_dimension_2d, supported.max_texture_dimension_2d),
+        ("max_texture_dimension_3d", required.max_texture_dimension_3d, supported.max_texture_dimension_3d),
+        ("max_texture_array_layers", required.max_texture_array_layers, supported.max_texture_array_layers),
+        ("max_bind_groups", required.max_bind_groups, supported.max_bind_groups),
+        ("max_dynamic_uniform_buffers_per_pipeline_layout", required.max_dynamic_uniform_buffers_per_pipeline_layout, supported.max_dynamic_uniform_buffers_per_pipeline_layout),
+        ("max_dynamic_storage_buffers_per_pipeline_layout", required.max_dynamic_storage_buffers_per_pipeline_layout, supported.max_dynamic_storage_buffers_per_pipeline_layout),
+        ("max_sampled_textures_per_shader_stage", required.max_sampled_textures_per_shader_stage, supported.max_sampled_textures_per_shader_stage),
+        ("max_samplers_per_shader_stage", required.max_samplers_per_shader_stage, supported.max_samplers_per_shader_stage),
+        ("max_storage_buffers_per_shader_stage", required.max_storage_buffers_per_shader_stage, supported.max_storage_buffers_per_shader_stage),
+        ("max_storage_textures_per_shader_stage", required.max_storage_textures_per_shader_stage, supported.max_storage_textures_per_shader_stage),
+        ("max_uniform_buffers_per_shader_stage", required.max_uniform_buffers_per_shader_stage, supported.max_uniform_buffers_per_shader_stage),
+        ("max_uniform_buffer_binding_size", required.max_uniform_buffer_binding_size, supported.max_uniform_buffer_binding_size),
+        ("max_storage_buffer_binding_size", required.max_storage_buffer_binding_size, supported.max_storage_buffer_binding_size),
+        ("max_vertex_buffers", required.max_vertex_buffers, supported.max_vertex_buffers),
+        ("max_vertex_attributes", required.max_vertex_attributes, supported.max_vertex_attributes),
+        ("max_vertex_buffer_array_stride", required.max_vertex_buffer_array_stride, supported.max_vertex_buffer_array_stride),
+        ("max_push_constant_size", required.max_push_constant_size, supported.max_push_constant_size),
+    ];
+
+    limits
+        .iter()
+        .filter(|(_, required, supported)| required > supported)
+        .map(|(name, required, supported)| format!("{} (requested {}, adapter supports {})", name, required, supported))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_limits_above_the_adapter_are_unsupported() {
+        let supported = wgpu::Limits::default();
+        assert!(unsupported_limits(&supported, &supported).is_empty());
+
+        let required = wgpu::Limits {
+            max_bind_groups: supported.max_bind_groups + 1,
+            max_vertex_buffers: supported.max_vertex_buffers - 1,
+            ..wgpu::Limits::default()
+        };
+        assert_eq!(unsupported_limits(&required, &supported), vec![format!(
+            "max_bind_groups (requested {}, adapter supports {})",
+            supported.max_bind_groups + 1,
+            supported.max_bind_groups
+        )]);
+    }
+}
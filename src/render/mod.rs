@@ -1 +1,25 @@
-pub mod buffer;
\ No newline at end of file
+mod config;
+mod gpu_context;
+mod pipeline;
+mod reflection;
+mod render_graph;
+mod shader;
+mod shader_layout;
+mod shader_watcher;
+mod surface;
+mod vertex_layout;
+
+pub mod buffer;
+
+pub use {
+    config::*,
+    gpu_context::*,
+    pipeline::*,
+    reflection::*,
+    render_graph::*,
+    shader::*,
+    shader_layout::*,
+    shader_watcher::*,
+    surface::*,
+    vertex_layout::*,
+};
\ No newline at end of file
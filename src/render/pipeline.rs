@@ -0,0 +1,652 @@
+use std::collections::HashMap;
+
+use super::{ReflectionError, ShaderReflection, ShaderType, DEFAULT_DEPTH_FORMAT};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BlendMode {
+    Replace,
+    Alpha,
+    PremultipliedAlpha,
+    Additive,
+    Multiply,
+}
+
+impl BlendMode {
+    pub fn to_blend_state(self) -> wgpu::BlendState {
+        match self {
+            BlendMode::Replace => wgpu::BlendState::REPLACE,
+            BlendMode::Alpha => wgpu::BlendState::ALPHA_BLENDING,
+            BlendMode::PremultipliedAlpha => wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING,
+            BlendMode::Additive => wgpu::BlendState {
+                color: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::One,
+                    dst_factor: wgpu::BlendFactor::One,
+                    operation: wgpu::BlendOperation::Add,
+                },
+                alpha: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::One,
+                    dst_factor: wgpu::BlendFactor::One,
+                    operation: wgpu::BlendOperation::Add,
+                },
+            },
+            BlendMode::Multiply => wgpu::BlendState {
+                color: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::Dst,
+                    dst_factor: wgpu::BlendFactor::Zero,
+                    operation: wgpu::BlendOperation::Add,
+                },
+                alpha: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::DstAlpha,
+                    dst_factor: wgpu::BlendFactor::Zero,
+                    operation: wgpu::BlendOperation::Add,
+                },
+            },
+        }
+    }
+}
+
+// Depth state that can be hashed, wgpu keeps the depth bias as floats.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DepthState {
+    pub format: wgpu::TextureFormat,
+    pub write_enabled: bool,
+    pub compare: wgpu::CompareFunction,
+    pub stencil: wgpu::StencilState,
+    bias_constant: i32,
+    bias_slope_scale: u32,
+    bias_clamp: u32,
+}
+
+impl DepthState {
+    pub fn new(format: wgpu::TextureFormat) -> Self {
+        Self {
+            format,
+            write_enabled: true,
+            compare: wgpu::CompareFunction::Less,
+            stencil: wgpu::StencilState::default(),
+            bias_constant: 0,
+            bias_slope_scale: 0.0f32.to_bits(),
+            bias_clamp: 0.0f32.to_bits(),
+        }
+    }
+
+    pub fn with_bias(mut self, constant: i32, slope_scale: f32, clamp: f32) -> Self {
+        self.bias_constant = constant;
+        self.bias_slope_scale = slope_scale.to_bits();
+        self.bias_clamp = clamp.to_bits();
+        self
+    }
+
+    pub fn to_depth_stencil_state(&self) -> wgpu::DepthStencilState {
+        wgpu::DepthStencilState {
+            format: self.format,
+            depth_write_enabled: self.write_enabled,
+            depth_compare: self.compare,
+            stencil: self.stencil.clone(),
+            bias: wgpu::DepthBiasState {
+                constant: self.bias_constant,
+                slope_scale: f32::from_bits(self.bias_slope_scale),
+                clamp: f32::from_bits(self.bias_clamp),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct VertexBufferDescriptor {
+    pub array_stride: wgpu::BufferAddress,
+    pub step_mode: wgpu::InputStepMode,
+    pub attributes: Vec<wgpu::VertexAttribute>,
+}
+
+impl VertexBufferDescriptor {
+    pub fn to_vertex_buffer_layout(&self) -> wgpu::VertexBufferLayout<'_> {
+        wgpu::VertexBufferLayout {
+            array_stride: self.array_stride,
+            step_mode: self.step_mode,
+            attributes: &self.attributes,
+        }
+    }
+}
+
+impl From<wgpu::VertexBufferLayout<'_>> for VertexBufferDescriptor {
+    fn from(layout: wgpu::VertexBufferLayout<'_>) -> Self {
+        Self {
+            array_stride: layout.array_stride,
+            step_mode: layout.step_mode,
+            attributes: layout.attributes.to_vec(),
+        }
+    }
+}
+
+// Everything about a render pipeline except the shader module and pipeline
+// layout, owned and hashable so it can key the pipeline cache.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PipelineDescriptor {
+    pub vertex_entry_point: String,
+    pub fragment_entry_point: Option<String>,
+    pub vertex_buffers: Vec<VertexBufferDescriptor>,
+    pub color_targets: Vec<wgpu::ColorTargetState>,
+    pub primitive: wgpu::PrimitiveState,
+    pub depth: Option<DepthState>,
+    pub multisample: wgpu::MultisampleState,
+}
+
+impl Default for PipelineDescriptor {
+    fn default() -> Self {
+        Self {
+            vertex_entry_point: "main".to_string(),
+            fragment_entry_point: Some("main".to_string()),
+            vertex_buffers: vec![],
+            color_targets: vec![],
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: Some(wgpu::Face::Back),
+                polygon_mode: wgpu::PolygonMode::Fill,
+                clamp_depth: false,
+                conservative: false,
+            },
+            depth: Some(DepthState::new(DEFAULT_DEPTH_FORMAT)),
+            multisample: wgpu::MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+        }
+    }
+}
+
+type CheckBinding = fn(&ShaderReflection, u32, u32) -> Result<(), ReflectionError>;
+
+// Defaults to triangle lists with back-face culling and a depth test with
+// `Less` against a `DEFAULT_DEPTH_FORMAT` depth buffer.
+#[derive(Debug, Clone, Default)]
+pub struct PipelineBuilder {
+    label: Option<String>,
+    descriptor: PipelineDescriptor,
+    bindings: Vec<(u32, u32, CheckBinding)>,
+}
+
+impl PipelineBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_label(mut self, label: &str) -> Self {
+        self.label = Some(label.to_string());
+        self
+    }
+
+    pub fn with_vertex_entry_point(mut self, entry_point: &str) -> Self {
+        self.descriptor.vertex_entry_point = entry_point.to_string();
+        self
+    }
+
+    pub fn with_fragment_entry_point(mut self, entry_point: &str) -> Self {
+        self.descriptor.fragment_entry_point = Some(entry_point.to_string());
+        self
+    }
+
+    // For depth only passes.
+    pub fn without_fragment(mut self) -> Self {
+        self.descriptor.fragment_entry_point = None;
+        self
+    }
+
+    pub fn with_vertex_buffer(mut self, layout: wgpu::VertexBufferLayout) -> Self {
+        self.descriptor.vertex_buffers.push(layout.into());
+        self
+    }
+
+    // Buffers bound to the pipeline, checked against the shader by `check`
+    // like the vertex buffers.
+    pub fn with_uniform<T: ShaderType>(mut self, group: u32, binding: u32) -> Self {
+        self.bindings.push((group, binding, ShaderReflection::check_uniform::<T>));
+        self
+    }
+
+    pub fn with_storage<T: ShaderType>(mut self, group: u32, binding: u32) -> Self {
+        self.bindings.push((group, binding, ShaderReflection::check_storage::<T>));
+        self
+    }
+
+    pub fn with_color_target(self, format: wgpu::TextureFormat, blend_mode: BlendMode) -> Self {
+        self.with_color_target_state(wgpu::ColorTargetState {
+            format,
+            blend: Some(blend_mode.to_blend_state()),
+            write_mask: wgpu::ColorWrite::ALL,
+        })
+    }
+
+    pub fn with_color_target_state(mut self, color_target: wgpu::ColorTargetState) -> Self {
+        self.descriptor.color_targets.push(color_target);
+        self
+    }
+
+    pub fn with_topology(mut self, topology: wgpu::PrimitiveTopology) -> Self {
+        self.descriptor.primitive.topology = topology;
+        self
+    }
+
+    pub fn with_front_face(mut self, front_face: wgpu::FrontFace) -> Self {
+        self.descriptor.primitive.front_face = front_face;
+        self
+    }
+
+    pub fn with_cull_mode(mut self, cull_mode: Option<wgpu::Face>) -> Self {
+        self.descriptor.primitive.cull_mode = cull_mode;
+        self
+    }
+
+    pub fn with_polygon_mode(mut self, polygon_mode: wgpu::PolygonMode) -> Self {
+        self.descriptor.primitive.polygon_mode = polygon_mode;
+        self
+    }
+
+    pub fn with_depth(mut self, depth: DepthState) -> Self {
+        self.descriptor.depth = Some(depth);
+        self
+    }
+
+    pub fn with_depth_compare(mut self, compare: wgpu::CompareFunction) -> Self {
+        let depth = self.descriptor.depth.get_or_insert_with(|| DepthState::new(DEFAULT_DEPTH_FORMAT));
+        depth.compare = compare;
+        self
+    }
+
+    pub fn with_depth_write(mut self, write_enabled: bool) -> Self {
+        let depth = self.descriptor.depth.get_or_insert_with(|| DepthState::new(DEFAULT_DEPTH_FORMAT));
+        depth.write_enabled = write_enabled;
+        self
+    }
+
+    pub fn without_depth(mut self) -> Self {
+        self.descriptor.depth = None;
+        self
+    }
+
+    pub fn with_sample_count(mut self, count: u32) -> Self {
+        self.descriptor.multisample.count = count;
+        self
+    }
+
+    pub fn label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+
+    pub fn descriptor(&self) -> &PipelineDescriptor {
+        &self.descriptor
+    }
+
+    pub fn check(&self, reflection: &ShaderReflection) -> Result<(), ReflectionError> {
+        let vertex_buffers = self
+            .descriptor
+            .vertex_buffers
+            .iter()
+            .map(VertexBufferDescriptor::to_vertex_buffer_layout)
+            .collect::<Vec<_>>();
+
+        reflection.check_vertex_buffers(&self.descriptor.vertex_entry_point, &vertex_buffers)?;
+
+        for (group, binding, check_binding) in &self.bindings {
+            check_binding(reflection, *group, *binding)?;
+        }
+        Ok(())
+    }
+
+    // Checks the builder against the shader before building the pipeline.
+    pub fn build_checked(
+        &self,
+        device: &wgpu::Device,
+        layout: &wgpu::PipelineLayout,
+        shader_module: &wgpu::ShaderModule,
+        reflection: &ShaderReflection,
+    ) -> Result<wgpu::RenderPipeline, ReflectionError> {
+        self.check(reflection)?;
+        Ok(self.build(device, layout, shader_module))
+    }
+
+    pub fn build(&self, device: &wgpu::Device, layout: &wgpu::PipelineLayout, shader_module: &wgpu::ShaderModule) -> wgpu::RenderPipeline {
+        let descriptor = &self.descriptor;
+        let vertex_buffers = descriptor
+            .vertex_buffers
+            .iter()
+            .map(VertexBufferDescriptor::to_vertex_buffer_layout)
+            .collect::<Vec<_>>();
+
+        let vertex_state = wgpu::VertexState {
+            module: shader_module,
+            entry_point: &descriptor.vertex_entry_point,
+            buffers: &vertex_buffers,
+        };
+        let fragment_state = descriptor
+            .fragment_entry_point
+            .as_ref()
+            .map(|entry_point| wgpu::FragmentState {
+                module: shader_module,
+                entry_point,
+                targets: &descriptor.color_targets,
+            });
+
+        let render_pipeline_descriptor = wgpu::RenderPipelineDescriptor {
+            label: self.label.as_deref(),
+            layout: Some(layout),
+            vertex: vertex_state,
+            fragment: fragment_state,
+            primitive: descriptor.primitive,
+            depth_stencil: descriptor.depth.as_ref().map(DepthState::to_depth_stencil_state),
+            multisample: descriptor.multisample,
+        };
+        device.create_render_pipeline(&render_pipeline_descriptor)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ShaderId(usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PipelineLayoutId(usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PipelineId(usize);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PipelineCacheStatistics {
+    pub hits: u64,
+    pub misses: u64,
+    pub pipelines: usize,
+}
+
+impl PipelineCacheStatistics {
+    pub fn hit_rate(&self) -> f64 {
+        match self.hits + self.misses {
+            0 => 0.0,
+            requests => self.hits as f64 / requests as f64,
+        }
+    }
+}
+
+type PipelineKey = (ShaderId, PipelineLayoutId, PipelineDescriptor);
+
+struct CachedShader {
+    module: wgpu::ShaderModule,
+    reflection: Option<ShaderReflection>,
+}
+
+struct CachedPipeline {
+    builder: PipelineBuilder,
+    key: PipelineKey,
+    pipeline: wgpu::RenderPipeline,
+}
+
+// Owns shader modules, pipeline layouts and the pipelines created from them.
+// Requesting a pipeline with a descriptor that was seen before returns the
+// existing one, so materials that only differ in their bind groups share it.
+#[derive(Default)]
+pub struct PipelineCache {
+    shaders: Vec<CachedShader>,
+    layouts: Vec<wgpu::PipelineLayout>,
+    pipelines: Vec<CachedPipeline>,
+    lookup: HashMap<PipelineKey, PipelineId>,
+    statistics: PipelineCacheStatistics,
+}
+
+impl PipelineCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_shader(&mut self, module: wgpu::ShaderModule) -> ShaderId {
+        self.shaders.push(CachedShader {
+            module,
+            reflection: None,
+        });
+        ShaderId(self.shaders.len() - 1)
+    }
+
+    // Pipelines using a shader added with its reflection have their vertex
+    // buffers and bindings checked against the shader before they are created.
+    pub fn add_reflected_shader(&mut self, module: wgpu::ShaderModule, reflection: ShaderReflection) -> ShaderId {
+        self.shaders.push(CachedShader {
+            module,
+            reflection: Some(reflection),
+        });
+        ShaderId(self.shaders.len() - 1)
+    }
+
+    // Swaps the module of a shader, for example after a hot reload, and
+    // rebuilds every pipeline using it. With a reflection, every pipeline is
+    // checked against the new shader first. When a check fails nothing is
+    // replaced and the previous shader and pipelines stay in use.
+    pub fn replace_shader(
+        &mut self,
+        device: &wgpu::Device,
+        id: ShaderId,
+        module: wgpu::ShaderModule,
+        reflection: Option<ShaderReflection>,
+    ) -> Result<(), ReflectionError> {
+        let builders: Vec<(usize, PipelineBuilder)> = self.pipelines
+            .iter()
+            .enumerate()
+            .filter(|(_, cached)| cached.key.0 == id)
+            .map(|(index, cached)| (index, cached.builder.clone()))
+            .collect();
+
+        if let Some(reflection) = &reflection {
+            for (_, builder) in builders.iter() {
+                builder.check(reflection)?;
+            }
+        }
+
+        self.shaders[id.0] = CachedShader {
+            module,
+            reflection,
+        };
+
+        for (index, builder) in builders {
+            let layout = self.pipelines[index].key.1;
+            self.pipelines[index].pipeline = builder.build(device, &self.layouts[layout.0], &self.shaders[id.0].module);
+        }
+        Ok(())
+    }
+
+    pub fn add_layout(&mut self, layout: wgpu::PipelineLayout) -> PipelineLayoutId {
+        self.layouts.push(layout);
+        PipelineLayoutId(self.layouts.len() - 1)
+    }
+
+    pub fn create_layout(&mut self, device: &wgpu::Device, label: Option<&str>, bind_group_layouts: &[&wgpu::BindGroupLayout]) -> PipelineLayoutId {
+        let pipeline_layout_descriptor = wgpu::PipelineLayoutDescriptor {
+            label,
+            bind_group_layouts,
+            push_constant_ranges: &[],
+        };
+        self.add_layout(device.create_pipeline_layout(&pipeline_layout_descriptor))
+    }
+
+    pub fn get_or_create(
+        &mut self,
+        device: &wgpu::Device,
+        shader: ShaderId,
+        layout: PipelineLayoutId,
+        builder: &PipelineBuilder,
+    ) -> Result<PipelineId, ReflectionError> {
+        let key = (shader, layout, builder.descriptor().clone());
+        if let Some(id) = self.find(&key) {
+            return Ok(id);
+        }
+
+        let cached_shader = &self.shaders[shader.0];
+        if let Some(reflection) = &cached_shader.reflection {
+            builder.check(reflection)?;
+        }
+
+        let pipeline = builder.build(device, &self.layouts[layout.0], &cached_shader.module);
+        let id = PipelineId(self.pipelines.len());
+        self.pipelines.push(CachedPipeline {
+            builder: builder.clone(),
+            key: key.clone(),
+            pipeline,
+        });
+        self.lookup.insert(key, id);
+
+        self.statistics.pipelines = self.pipelines.len();
+        Ok(id)
+    }
+
+    // Counts the request as a hit or a miss, a miss is counted even when
+    // creating the pipeline then fails its check.
+    fn find(&mut self, key: &PipelineKey) -> Option<PipelineId> {
+        match self.lookup.get(key) {
+            Some(id) => {
+                self.statistics.hits += 1;
+                Some(*id)
+            },
+            None => {
+                self.statistics.misses += 1;
+                None
+            },
+        }
+    }
+
+    pub fn pipeline(&self, id: PipelineId) -> &wgpu::RenderPipeline {
+        &self.pipelines[id.0].pipeline
+    }
+
+    pub fn statistics(&self) -> PipelineCacheStatistics {
+        self.statistics
+    }
+
+    pub fn reset_statistics(&mut self) {
+        self.statistics = PipelineCacheStatistics {
+            pipelines: self.pipelines.len(),
+            ..PipelineCacheStatistics::default()
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHADER: &str = r#"
+[[block]]
+struct Uniforms {
+    view_projection: mat4x4<f32>;
+};
+[[group(0), binding(0)]]
+var<uniform> uniforms: Uniforms;
+
+[[stage(vertex)]]
+fn main([[location(0)]] position: vec3<f32>) -> [[builtin(position)]] vec4<f32> {
+    return uniforms.view_projection * vec4<f32>(position, 1.0);
+}
+"#;
+
+    fn reflect() -> ShaderReflection {
+        ShaderReflection::new(&naga::front::wgsl::parse_str(SHADER).unwrap()).unwrap()
+    }
+
+    fn builder() -> PipelineBuilder {
+        PipelineBuilder::new().with_vertex_buffer(wgpu::VertexBufferLayout {
+            array_stride: 12,
+            step_mode: wgpu::InputStepMode::Vertex,
+            attributes: &wgpu::vertex_attr_array![0 => Float32x3],
+        })
+    }
+
+    #[test]
+    fn check_covers_vertex_buffers_and_bindings() {
+        let reflection = reflect();
+        assert_eq!(builder().with_uniform::<[[f32; 4]; 4]>(0, 0).check(&reflection), Ok(()));
+        assert_eq!(builder().with_uniform::<[f32; 4]>(0, 0).check(&reflection), Err(ReflectionError::UniformSize {
+            group: 0,
+            binding: 0,
+            shader_size: 64,
+            rust_size: 16,
+        }));
+        assert_eq!(builder().with_uniform::<[f32; 4]>(1, 0).check(&reflection), Err(ReflectionError::MissingBinding {
+            group: 1,
+            binding: 0,
+        }));
+        assert_eq!(PipelineBuilder::new().with_uniform::<[[f32; 4]; 4]>(0, 0).check(&reflection), Err(ReflectionError::MissingVertexInput {
+            location: 0,
+            name: Some("position".to_string()),
+        }));
+    }
+
+    fn hash(descriptor: &PipelineDescriptor) -> u64 {
+        use std::hash::{Hash, Hasher};
+
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        descriptor.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn equal_builders_have_equal_keys() {
+        let a = builder().with_color_target(wgpu::TextureFormat::Bgra8UnormSrgb, BlendMode::Alpha);
+        let b = builder()
+            .with_label("another label")
+            .with_uniform::<[[f32; 4]; 4]>(0, 0)
+            .with_color_target(wgpu::TextureFormat::Bgra8UnormSrgb, BlendMode::Alpha);
+
+        assert_eq!(a.descriptor(), b.descriptor());
+        assert_eq!(hash(a.descriptor()), hash(b.descriptor()));
+    }
+
+    #[test]
+    fn pipeline_state_changes_the_key() {
+        let base = builder().with_color_target(wgpu::TextureFormat::Bgra8UnormSrgb, BlendMode::Alpha);
+        let variants = vec![
+            base.clone().with_color_target(wgpu::TextureFormat::Bgra8UnormSrgb, BlendMode::Additive),
+            base.clone().with_cull_mode(None),
+            base.clone().with_topology(wgpu::PrimitiveTopology::LineList),
+            base.clone().with_depth_compare(wgpu::CompareFunction::Greater),
+            base.clone().with_depth(DepthState::new(DEFAULT_DEPTH_FORMAT).with_bias(2, 1.5, 0.0)),
+            base.clone().without_depth(),
+            base.clone().with_sample_count(4),
+            base.clone().with_fragment_entry_point("fs_other"),
+            base.clone().without_fragment(),
+            PipelineBuilder::new().with_color_target(wgpu::TextureFormat::Bgra8UnormSrgb, BlendMode::Alpha),
+        ];
+
+        for (index, variant) in variants.iter().enumerate() {
+            assert_ne!(variant.descriptor(), base.descriptor(), "variant {}", index);
+            assert_ne!(hash(variant.descriptor()), hash(base.descriptor()), "variant {}", index);
+        }
+    }
+
+    #[test]
+    fn depth_bias_survives_the_conversion() {
+        let depth = DepthState::new(DEFAULT_DEPTH_FORMAT).with_bias(-3, 0.5, 0.25);
+        let state = depth.to_depth_stencil_state();
+        assert_eq!(state.bias.constant, -3);
+        assert_eq!(state.bias.slope_scale, 0.5);
+        assert_eq!(state.bias.clamp, 0.25);
+        assert_eq!(depth, DepthState::new(DEFAULT_DEPTH_FORMAT).with_bias(-3, 0.5, 0.25));
+    }
+
+    #[test]
+    fn lookups_count_hits_and_misses() {
+        let mut cache = PipelineCache::new();
+        let key = (ShaderId(0), PipelineLayoutId(0), builder().descriptor().clone());
+        let other_key = (ShaderId(1), PipelineLayoutId(0), builder().descriptor().clone());
+
+        assert_eq!(cache.find(&key), None);
+        cache.lookup.insert(key.clone(), PipelineId(0));
+        assert_eq!(cache.find(&key), Some(PipelineId(0)));
+        assert_eq!(cache.find(&key), Some(PipelineId(0)));
+        assert_eq!(cache.find(&other_key), None);
+
+        let statistics = cache.statistics();
+        assert_eq!((statistics.hits, statistics.misses), (2, 2));
+        assert!((statistics.hit_rate() - 0.5).abs() < 1e-9);
+
+        cache.reset_statistics();
+        assert_eq!(cache.statistics(), PipelineCacheStatistics::default());
+        assert_eq!(cache.statistics().hit_rate(), 0.0);
+    }
+}
@@ -0,0 +1,699 @@
+use std::error::Error;
+use std::fmt;
+use std::num::NonZeroU64;
+
+use super::buffer::align_to;
+use super::ShaderType;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReflectionError {
+    Validation(String),
+    EntryPointNotFound(String),
+    MissingBinding { group: u32, binding: u32 },
+    BindingType { group: u32, binding: u32, shader_type: wgpu::BindingType },
+    UniformSize { group: u32, binding: u32, shader_size: u64, rust_size: u64 },
+    StorageSize { group: u32, binding: u32, shader_size: u64, rust_size: u64 },
+    MissingVertexInput { location: u32, name: Option<String> },
+    VertexFormat { location: u32, name: Option<String>, shader_format: wgpu::VertexFormat, buffer_format: wgpu::VertexFormat },
+}
+
+impl fmt::Display for ReflectionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReflectionError::Validation(message) => write!(f, "shader validation failed: {}", message),
+            ReflectionError::EntryPointNotFound(name) => write!(f, "shader has no entry point \"{}\"", name),
+            ReflectionError::MissingBinding { group, binding } => {
+                write!(f, "shader has no resource at group {} binding {}", group, binding)
+            },
+            ReflectionError::BindingType { group, binding, shader_type } => {
+                write!(f, "resource at group {} binding {} is a {:?} in the shader", group, binding, shader_type)
+            },
+            ReflectionError::UniformSize { group, binding, shader_size, rust_size } => write!(
+                f,
+                "uniform buffer at group {} binding {} is {} bytes in the shader but {} bytes on the Rust side",
+                group, binding, shader_size, rust_size
+            ),
+            ReflectionError::StorageSize { group, binding, shader_size, rust_size } => write!(
+                f,
+                "storage buffer at group {} binding {} is {} bytes in the shader but {} bytes on the Rust side",
+                group, binding, shader_size, rust_size
+            ),
+            ReflectionError::MissingVertexInput { location, name } => write!(
+                f,
+                "vertex input {} at location {} is not provided by any vertex buffer",
+                name.as_deref().unwrap_or("<unnamed>"),
+                location
+            ),
+            ReflectionError::VertexFormat { location, name, shader_format, buffer_format } => write!(
+                f,
+                "vertex input {} at location {} is {:?} in the shader but {:?} in the vertex buffer",
+                name.as_deref().unwrap_or("<unnamed>"),
+                location,
+                shader_format,
+                buffer_format
+            ),
+        }
+    }
+}
+
+impl Error for ReflectionError { }
+
+#[derive(Debug, Clone)]
+pub struct ReflectedBinding {
+    pub group: u32,
+    pub binding: u32,
+    pub name: Option<String>,
+    pub ty: wgpu::BindingType,
+    // Size of the buffer contents in bytes, 0 for textures and samplers.
+    pub size: u64,
+    pub visibility: wgpu::ShaderStage,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReflectedVertexInput {
+    pub location: u32,
+    pub name: Option<String>,
+    pub format: wgpu::VertexFormat,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReflectedEntryPoint {
+    pub name: String,
+    pub stage: wgpu::ShaderStage,
+    pub vertex_inputs: Vec<ReflectedVertexInput>,
+}
+
+// Resource bindings and vertex inputs of a WGSL module, used to build bind
+// group layouts and to check the Rust side data against the shader.
+#[derive(Debug, Clone)]
+pub struct ShaderReflection {
+    bindings: Vec<ReflectedBinding>,
+    entry_points: Vec<ReflectedEntryPoint>,
+}
+
+impl ShaderReflection {
+    pub fn new(module: &naga::Module) -> Result<Self, ReflectionError> {
+        let mut validator = naga::valid::Validator::new(naga::valid::ValidationFlags::all(), naga::valid::Capabilities::all());
+        let info = validator
+            .validate(module)
+            .map_err(|error| ReflectionError::Validation(error.to_string()))?;
+
+        let all_stages = module
+            .entry_points
+            .iter()
+            .fold(wgpu::ShaderStage::NONE, |stages, entry_point| stages | to_shader_stage(entry_point.stage));
+
+        let mut bindings = vec![];
+        for (handle, variable) in module.global_variables.iter() {
+            let resource_binding = match &variable.binding {
+                Some(resource_binding) => resource_binding,
+                None => continue,
+            };
+
+            let ty = match binding_type(module, variable) {
+                Some(ty) => ty,
+                None => continue,
+            };
+
+            let visibility = module
+                .entry_points
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| !info.get_entry_point(*index)[handle].is_empty())
+                .fold(wgpu::ShaderStage::NONE, |stages, (_, entry_point)| stages | to_shader_stage(entry_point.stage));
+
+            let size = match ty {
+                wgpu::BindingType::Buffer { min_binding_size, .. } => min_binding_size.map_or(0, NonZeroU64::get),
+                _ => 0,
+            };
+
+            bindings.push(ReflectedBinding {
+                group: resource_binding.group,
+                binding: resource_binding.binding,
+                name: variable.name.clone(),
+                ty,
+                size,
+                // Resources no entry point uses still have to be visible somewhere.
+                visibility: if visibility.is_empty() { all_stages } else { visibility },
+            });
+        }
+        bindings.sort_by_key(|binding| (binding.group, binding.binding));
+
+        let entry_points = module
+            .entry_points
+            .iter()
+            .map(|entry_point| ReflectedEntryPoint {
+                name: entry_point.name.clone(),
+                stage: to_shader_stage(entry_point.stage),
+                vertex_inputs: match entry_point.stage {
+                    naga::ShaderStage::Vertex => vertex_inputs(module, &entry_point.function),
+                    _ => vec![],
+                },
+            })
+            .collect();
+
+        Ok(Self {
+            bindings,
+            entry_points,
+        })
+    }
+
+    pub fn bindings(&self) -> &[ReflectedBinding] {
+        &self.bindings
+    }
+
+    pub fn binding(&self, group: u32, binding: u32) -> Option<&ReflectedBinding> {
+        self.bindings
+            .iter()
+            .find(|reflected| reflected.group == group && reflected.binding == binding)
+    }
+
+    pub fn bind_group_count(&self) -> u32 {
+        self.bindings.iter().map(|binding| binding.group + 1).max().unwrap_or(0)
+    }
+
+    pub fn bind_group_layout_entries(&self, group: u32) -> Vec<wgpu::BindGroupLayoutEntry> {
+        self.bindings
+            .iter()
+            .filter(|binding| binding.group == group)
+            .map(|binding| wgpu::BindGroupLayoutEntry {
+                binding: binding.binding,
+                visibility: binding.visibility,
+                ty: binding.ty,
+                count: None,
+            })
+            .collect()
+    }
+
+    pub fn create_bind_group_layout(&self, device: &wgpu::Device, group: u32, label: Option<&str>) -> wgpu::BindGroupLayout {
+        let entries = self.bind_group_layout_entries(group);
+        let bind_group_layout_descriptor = wgpu::BindGroupLayoutDescriptor {
+            label,
+            entries: &entries,
+        };
+        device.create_bind_group_layout(&bind_group_layout_descriptor)
+    }
+
+    pub fn entry_points(&self) -> &[ReflectedEntryPoint] {
+        &self.entry_points
+    }
+
+    pub fn vertex_inputs(&self, entry_point: &str) -> Option<&[ReflectedVertexInput]> {
+        self.entry_points
+            .iter()
+            .find(|reflected| reflected.stage == wgpu::ShaderStage::VERTEX && reflected.name == entry_point)
+            .map(|reflected| reflected.vertex_inputs.as_slice())
+    }
+
+    // Checks that every vertex input of the entry point is provided by one of
+    // the buffers with a compatible format.
+    pub fn check_vertex_buffers(&self, entry_point: &str, buffers: &[wgpu::VertexBufferLayout]) -> Result<(), ReflectionError> {
+        let inputs = self
+            .vertex_inputs(entry_point)
+            .ok_or_else(|| ReflectionError::EntryPointNotFound(entry_point.to_string()))?;
+
+        for input in inputs {
+            let attribute = buffers
+                .iter()
+                .flat_map(|buffer| buffer.attributes.iter())
+                .find(|attribute| attribute.shader_location == input.location);
+
+            match attribute {
+                Some(attribute) if vertex_format_shape(attribute.format) != vertex_format_shape(input.format) => {
+                    return Err(ReflectionError::VertexFormat {
+                        location: input.location,
+                        name: input.name.clone(),
+                        shader_format: input.format,
+                        buffer_format: attribute.format,
+                    });
+                },
+                Some(_) => { },
+                None => {
+                    return Err(ReflectionError::MissingVertexInput {
+                        location: input.location,
+                        name: input.name.clone(),
+                    });
+                },
+            }
+        }
+
+        Ok(())
+    }
+
+    // Compares the size of a uniform or storage buffer binding with the
+    // size of the data bound to it.
+    pub fn check_buffer_size(&self, group: u32, binding: u32, size: u64) -> Result<(), ReflectionError> {
+        let reflected = self
+            .binding(group, binding)
+            .ok_or(ReflectionError::MissingBinding { group, binding })?;
+
+        if reflected.size == size {
+            return Ok(());
+        }
+
+        match reflected.ty {
+            wgpu::BindingType::Buffer { ty: wgpu::BufferBindingType::Uniform, .. } => Err(ReflectionError::UniformSize {
+                group,
+                binding,
+                shader_size: reflected.size,
+                rust_size: size,
+            }),
+            wgpu::BindingType::Buffer { ty: wgpu::BufferBindingType::Storage { .. }, .. } => Err(ReflectionError::StorageSize {
+                group,
+                binding,
+                shader_size: reflected.size,
+                rust_size: size,
+            }),
+            shader_type => Err(ReflectionError::BindingType {
+                group,
+                binding,
+                shader_type,
+            }),
+        }
+    }
+
+    // WGSL rounds the size of a struct up to its alignment, so a Rust struct
+    // without trailing padding still matches.
+    pub fn check_uniform<T: ShaderType>(&self, group: u32, binding: u32) -> Result<(), ReflectionError> {
+        self.check_buffer_binding(group, binding, wgpu::BufferBindingType::Uniform, shader_type_size::<T>())
+    }
+
+    pub fn check_storage<T: ShaderType>(&self, group: u32, binding: u32) -> Result<(), ReflectionError> {
+        let read_only = match self.binding(group, binding).map(|reflected| reflected.ty) {
+            Some(wgpu::BindingType::Buffer { ty: wgpu::BufferBindingType::Storage { read_only }, .. }) => read_only,
+            _ => false,
+        };
+        self.check_buffer_binding(group, binding, wgpu::BufferBindingType::Storage { read_only }, shader_type_size::<T>())
+    }
+
+    fn check_buffer_binding(&self, group: u32, binding: u32, buffer_type: wgpu::BufferBindingType, size: u64) -> Result<(), ReflectionError> {
+        let reflected = self
+            .binding(group, binding)
+            .ok_or(ReflectionError::MissingBinding { group, binding })?;
+
+        match reflected.ty {
+            wgpu::BindingType::Buffer { ty, .. } if ty == buffer_type => self.check_buffer_size(group, binding, size),
+            shader_type => Err(ReflectionError::BindingType {
+                group,
+                binding,
+                shader_type,
+            }),
+        }
+    }
+}
+
+fn shader_type_size<T: ShaderType>() -> u64 {
+    align_to(T::SIZE, T::ALIGN)
+}
+
+fn to_shader_stage(stage: naga::ShaderStage) -> wgpu::ShaderStage {
+    match stage {
+        naga::ShaderStage::Vertex => wgpu::ShaderStage::VERTEX,
+        naga::ShaderStage::Fragment => wgpu::ShaderStage::FRAGMENT,
+        naga::ShaderStage::Compute => wgpu::ShaderStage::COMPUTE,
+    }
+}
+
+fn binding_type(module: &naga::Module, variable: &naga::GlobalVariable) -> Option<wgpu::BindingType> {
+    let size = |ty: naga::Handle<naga::Type>| NonZeroU64::new(module.types[ty].inner.span(&module.constants) as u64);
+
+    match variable.class {
+        naga::StorageClass::Uniform => Some(wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: size(variable.ty),
+        }),
+        naga::StorageClass::Storage => Some(wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Storage {
+                read_only: !variable.storage_access.contains(naga::StorageAccess::STORE),
+            },
+            has_dynamic_offset: false,
+            min_binding_size: size(variable.ty),
+        }),
+        naga::StorageClass::Handle => match &module.types[variable.ty].inner {
+            naga::TypeInner::Sampler { comparison } => Some(wgpu::BindingType::Sampler {
+                filtering: true,
+                comparison: *comparison,
+            }),
+            naga::TypeInner::Image { dim, arrayed, class } => {
+                let view_dimension = view_dimension(*dim, *arrayed);
+                match class {
+                    naga::ImageClass::Sampled { kind, multi } => Some(wgpu::BindingType::Texture {
+                        sample_type: match kind {
+                            naga::ScalarKind::Sint => wgpu::TextureSampleType::Sint,
+                            naga::ScalarKind::Uint => wgpu::TextureSampleType::Uint,
+                            _ => wgpu::TextureSampleType::Float { filterable: true },
+                        },
+                        view_dimension,
+                        multisampled: *multi,
+                    }),
+                    naga::ImageClass::Depth => Some(wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Depth,
+                        view_dimension,
+                        multisampled: false,
+                    }),
+                    naga::ImageClass::Storage(format) => Some(wgpu::BindingType::StorageTexture {
+                        access: storage_texture_access(variable.storage_access),
+                        format: texture_format(*format),
+                        view_dimension,
+                    }),
+                }
+            },
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn view_dimension(dim: naga::ImageDimension, arrayed: bool) -> wgpu::TextureViewDimension {
+    match (dim, arrayed) {
+        (naga::ImageDimension::D1, _) => wgpu::TextureViewDimension::D1,
+        (naga::ImageDimension::D2, false) => wgpu::TextureViewDimension::D2,
+        (naga::ImageDimension::D2, true) => wgpu::TextureViewDimension::D2Array,
+        (naga::ImageDimension::D3, _) => wgpu::TextureViewDimension::D3,
+        (naga::ImageDimension::Cube, false) => wgpu::TextureViewDimension::Cube,
+        (naga::ImageDimension::Cube, true) => wgpu::TextureViewDimension::CubeArray,
+    }
+}
+
+fn storage_texture_access(access: naga::StorageAccess) -> wgpu::StorageTextureAccess {
+    if access.contains(naga::StorageAccess::LOAD | naga::StorageAccess::STORE) {
+        wgpu::StorageTextureAccess::ReadWrite
+    } else if access.contains(naga::StorageAccess::STORE) {
+        wgpu::StorageTextureAccess::WriteOnly
+    } else {
+        wgpu::StorageTextureAccess::ReadOnly
+    }
+}
+
+fn texture_format(format: naga::StorageFormat) -> wgpu::TextureFormat {
+    use naga::StorageFormat as Sf;
+    use wgpu::TextureFormat as Tf;
+
+    match format {
+        Sf::R8Unorm => Tf::R8Unorm,
+        Sf::R8Snorm => Tf::R8Snorm,
+        Sf::R8Uint => Tf::R8Uint,
+        Sf::R8Sint => Tf::R8Sint,
+        Sf::R16Uint => Tf::R16Uint,
+        Sf::R16Sint => Tf::R16Sint,
+        Sf::R16Float => Tf::R16Float,
+        Sf::Rg8Unorm => Tf::Rg8Unorm,
+        Sf::Rg8Snorm => Tf::Rg8Snorm,
+        Sf::Rg8Uint => Tf::Rg8Uint,
+        Sf::Rg8Sint => Tf::Rg8Sint,
+        Sf::R32Uint => Tf::R32Uint,
+        Sf::R32Sint => Tf::R32Sint,
+        Sf::R32Float => Tf::R32Float,
+        Sf::Rg16Uint => Tf::Rg16Uint,
+        Sf::Rg16Sint => Tf::Rg16Sint,
+        Sf::Rg16Float => Tf::Rg16Float,
+        Sf::Rgba8Unorm => Tf::Rgba8Unorm,
+        Sf::Rgba8Snorm => Tf::Rgba8Snorm,
+        Sf::Rgba8Uint => Tf::Rgba8Uint,
+        Sf::Rgba8Sint => Tf::Rgba8Sint,
+        Sf::Rgb10a2Unorm => Tf::Rgb10a2Unorm,
+        Sf::Rg11b10Float => Tf::Rg11b10Float,
+        Sf::Rg32Uint => Tf::Rg32Uint,
+        Sf::Rg32Sint => Tf::Rg32Sint,
+        Sf::Rg32Float => Tf::Rg32Float,
+        Sf::Rgba16Uint => Tf::Rgba16Uint,
+        Sf::Rgba16Sint => Tf::Rgba16Sint,
+        Sf::Rgba16Float => Tf::Rgba16Float,
+        Sf::Rgba32Uint => Tf::Rgba32Uint,
+        Sf::Rgba32Sint => Tf::Rgba32Sint,
+        Sf::Rgba32Float => Tf::Rgba32Float,
+    }
+}
+
+// Vertex inputs are either arguments with a location or members of struct
+// arguments with a location.
+fn vertex_inputs(module: &naga::Module, function: &naga::Function) -> Vec<ReflectedVertexInput> {
+    let mut inputs = vec![];
+
+    for argument in function.arguments.iter() {
+        match &module.types[argument.ty].inner {
+            naga::TypeInner::Struct { members, .. } => {
+                for member in members {
+                    inputs.extend(vertex_input(module, &member.binding, &member.name, member.ty));
+                }
+            },
+            _ => inputs.extend(vertex_input(module, &argument.binding, &argument.name, argument.ty)),
+        }
+    }
+
+    inputs.sort_by_key(|input| input.location);
+    inputs
+}
+
+fn vertex_input(module: &naga::Module, binding: &Option<naga::Binding>, name: &Option<String>, ty: naga::Handle<naga::Type>) -> Option<ReflectedVertexInput> {
+    let location = match binding {
+        Some(naga::Binding::Location { location, .. }) => *location,
+        _ => return None,
+    };
+
+    let (kind, components) = match module.types[ty].inner {
+        naga::TypeInner::Scalar { kind, .. } => (kind, 1),
+        naga::TypeInner::Vector { kind, size, .. } => (kind, size as u32),
+        _ => return None,
+    };
+
+    let format = match (kind, components) {
+        (naga::ScalarKind::Float, 1) => wgpu::VertexFormat::Float32,
+        (naga::ScalarKind::Float, 2) => wgpu::VertexFormat::Float32x2,
+        (naga::ScalarKind::Float, 3) => wgpu::VertexFormat::Float32x3,
+        (naga::ScalarKind::Float, _) => wgpu::VertexFormat::Float32x4,
+        (naga::ScalarKind::Uint, 1) => wgpu::VertexFormat::Uint32,
+        (naga::ScalarKind::Uint, 2) => wgpu::VertexFormat::Uint32x2,
+        (naga::ScalarKind::Uint, 3) => wgpu::VertexFormat::Uint32x3,
+        (naga::ScalarKind::Uint, _) => wgpu::VertexFormat::Uint32x4,
+        (naga::ScalarKind::Sint, 1) => wgpu::VertexFormat::Sint32,
+        (naga::ScalarKind::Sint, 2) => wgpu::VertexFormat::Sint32x2,
+        (naga::ScalarKind::Sint, 3) => wgpu::VertexFormat::Sint32x3,
+        (naga::ScalarKind::Sint, _) => wgpu::VertexFormat::Sint32x4,
+        (naga::ScalarKind::Bool, _) => return None,
+    };
+
+    Some(ReflectedVertexInput {
+        location,
+        name: name.clone(),
+        format,
+    })
+}
+
+#[derive(Debug, PartialEq)]
+enum VertexKind {
+    Float,
+    Uint,
+    Sint,
+}
+
+// The type the shader sees for a vertex format, normalized formats read as floats.
+fn vertex_format_shape(format: wgpu::VertexFormat) -> (VertexKind, u32) {
+    use wgpu::VertexFormat as Vf;
+
+    match format {
+        Vf::Uint8x2 | Vf::Uint16x2 | Vf::Uint32x2 => (VertexKind::Uint, 2),
+        Vf::Uint8x4 | Vf::Uint16x4 | Vf::Uint32x4 => (VertexKind::Uint, 4),
+        Vf::Uint32 => (VertexKind::Uint, 1),
+        Vf::Uint32x3 => (VertexKind::Uint, 3),
+        Vf::Sint8x2 | Vf::Sint16x2 | Vf::Sint32x2 => (VertexKind::Sint, 2),
+        Vf::Sint8x4 | Vf::Sint16x4 | Vf::Sint32x4 => (VertexKind::Sint, 4),
+        Vf::Sint32 => (VertexKind::Sint, 1),
+        Vf::Sint32x3 => (VertexKind::Sint, 3),
+        Vf::Float32 | Vf::Float64 => (VertexKind::Float, 1),
+        Vf::Unorm8x2 | Vf::Snorm8x2 | Vf::Unorm16x2 | Vf::Snorm16x2 | Vf::Float16x2 | Vf::Float32x2 | Vf::Float64x2 => {
+            (VertexKind::Float, 2)
+        },
+        Vf::Float32x3 | Vf::Float64x3 => (VertexKind::Float, 3),
+        Vf::Unorm8x4 | Vf::Snorm8x4 | Vf::Unorm16x4 | Vf::Snorm16x4 | Vf::Float16x4 | Vf::Float32x4 | Vf::Float64x4 => {
+            (VertexKind::Float, 4)
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHADER: &str = r#"
+[[block]]
+struct Camera {
+    view_projection: mat4x4<f32>;
+};
+[[block]]
+struct Lights {
+    colors: array<vec4<f32>, 4>;
+};
+[[group(0), binding(0)]]
+var<uniform> camera: Camera;
+[[group(0), binding(1)]]
+var<storage> lights: [[access(read)]] Lights;
+[[group(1), binding(0)]]
+var diffuse_texture: texture_2d<f32>;
+[[group(1), binding(1)]]
+var diffuse_sampler: sampler;
+[[block]]
+struct Material {
+    color: vec3<f32>;
+};
+[[group(2), binding(0)]]
+var<uniform> material: Material;
+
+struct VertexInput {
+    [[location(0)]] position: vec3<f32>;
+    [[location(1)]] uv: vec2<f32>;
+};
+struct VertexOutput {
+    [[builtin(position)]] position: vec4<f32>;
+    [[location(0)]] uv: vec2<f32>;
+};
+
+[[stage(vertex)]]
+fn vs_main(input: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    out.position = camera.view_projection * vec4<f32>(input.position, 1.0);
+    out.uv = input.uv;
+    return out;
+}
+
+[[stage(fragment)]]
+fn fs_main(input: VertexOutput) -> [[location(0)]] vec4<f32> {
+    return textureSample(diffuse_texture, diffuse_sampler, input.uv) * lights.colors[0] * camera.view_projection[0] * vec4<f32>(material.color, 1.0);
+}
+"#;
+
+    fn reflect() -> ShaderReflection {
+        ShaderReflection::new(&naga::front::wgsl::parse_str(SHADER).unwrap()).unwrap()
+    }
+
+    fn vertex_buffer(attributes: &[wgpu::VertexAttribute]) -> wgpu::VertexBufferLayout<'_> {
+        wgpu::VertexBufferLayout {
+            array_stride: 20,
+            step_mode: wgpu::InputStepMode::Vertex,
+            attributes,
+        }
+    }
+
+    #[test]
+    fn bind_group_layout_entries_match_the_shader() {
+        let reflection = reflect();
+        assert_eq!(reflection.bind_group_count(), 3);
+
+        let buffers = reflection.bind_group_layout_entries(0);
+        assert_eq!(buffers.len(), 2);
+        assert_eq!(buffers[0].binding, 0);
+        assert_eq!(buffers[0].visibility, wgpu::ShaderStage::VERTEX | wgpu::ShaderStage::FRAGMENT);
+        assert_eq!(buffers[0].ty, wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: NonZeroU64::new(64),
+        });
+        assert_eq!(buffers[1].binding, 1);
+        assert_eq!(buffers[1].visibility, wgpu::ShaderStage::FRAGMENT);
+        assert_eq!(buffers[1].ty, wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Storage { read_only: true },
+            has_dynamic_offset: false,
+            min_binding_size: NonZeroU64::new(64),
+        });
+
+        let textures = reflection.bind_group_layout_entries(1);
+        assert_eq!(textures.len(), 2);
+        assert_eq!(textures[0].visibility, wgpu::ShaderStage::FRAGMENT);
+        assert_eq!(textures[0].ty, wgpu::BindingType::Texture {
+            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+            view_dimension: wgpu::TextureViewDimension::D2,
+            multisampled: false,
+        });
+        assert_eq!(textures[1].visibility, wgpu::ShaderStage::FRAGMENT);
+        assert_eq!(textures[1].ty, wgpu::BindingType::Sampler {
+            filtering: true,
+            comparison: false,
+        });
+    }
+
+    #[test]
+    fn buffer_size_mismatches_are_reported() {
+        let reflection = reflect();
+        assert_eq!(reflection.check_uniform::<[[f32; 4]; 4]>(0, 0), Ok(()));
+        assert_eq!(reflection.check_uniform::<[f32; 4]>(0, 0), Err(ReflectionError::UniformSize {
+            group: 0,
+            binding: 0,
+            shader_size: 64,
+            rust_size: 16,
+        }));
+        assert_eq!(reflection.check_storage::<[[f32; 4]; 4]>(0, 1), Ok(()));
+        assert_eq!(reflection.check_storage::<[f32; 4]>(0, 1), Err(ReflectionError::StorageSize {
+            group: 0,
+            binding: 1,
+            shader_size: 64,
+            rust_size: 16,
+        }));
+        assert_eq!(reflection.check_uniform::<[f32; 4]>(3, 0), Err(ReflectionError::MissingBinding {
+            group: 3,
+            binding: 0,
+        }));
+    }
+
+    #[test]
+    fn trailing_struct_padding_is_not_required() {
+        let reflection = reflect();
+        assert_eq!(reflection.binding(2, 0).unwrap().size, 16);
+        assert_eq!(reflection.check_uniform::<[f32; 3]>(2, 0), Ok(()));
+        assert_eq!(reflection.check_buffer_size(2, 0, 12), Err(ReflectionError::UniformSize {
+            group: 2,
+            binding: 0,
+            shader_size: 16,
+            rust_size: 12,
+        }));
+    }
+
+    #[test]
+    fn buffers_of_the_wrong_kind_are_reported() {
+        let reflection = reflect();
+        assert!(matches!(
+            reflection.check_uniform::<[[f32; 4]; 4]>(0, 1),
+            Err(ReflectionError::BindingType { group: 0, binding: 1, shader_type: wgpu::BindingType::Buffer { .. } })
+        ));
+        assert!(matches!(
+            reflection.check_storage::<[[f32; 4]; 4]>(0, 0),
+            Err(ReflectionError::BindingType { group: 0, binding: 0, shader_type: wgpu::BindingType::Buffer { .. } })
+        ));
+        assert!(matches!(
+            reflection.check_uniform::<[f32; 4]>(1, 0),
+            Err(ReflectionError::BindingType { group: 1, binding: 0, shader_type: wgpu::BindingType::Texture { .. } })
+        ));
+    }
+
+    #[test]
+    fn vertex_inputs_without_a_buffer_are_reported() {
+        let reflection = reflect();
+        let attributes = wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x2];
+        assert_eq!(reflection.check_vertex_buffers("vs_main", &[vertex_buffer(&attributes)]), Ok(()));
+
+        let attributes = wgpu::vertex_attr_array![0 => Float32x3];
+        assert_eq!(reflection.check_vertex_buffers("vs_main", &[vertex_buffer(&attributes)]), Err(ReflectionError::MissingVertexInput {
+            location: 1,
+            name: Some("uv".to_string()),
+        }));
+        assert_eq!(
+            reflection.check_vertex_buffers("fs_main", &[vertex_buffer(&attributes)]),
+            Err(ReflectionError::EntryPointNotFound("fs_main".to_string()))
+        );
+    }
+
+    #[test]
+    fn vertex_formats_of_a_different_shape_are_reported() {
+        let reflection = reflect();
+        let attributes = wgpu::vertex_attr_array![0 => Float32x3, 1 => Unorm16x2];
+        assert_eq!(reflection.check_vertex_buffers("vs_main", &[vertex_buffer(&attributes)]), Ok(()));
+
+        let attributes = wgpu::vertex_attr_array![0 => Float32x3, 1 => Uint32x2];
+        assert_eq!(reflection.check_vertex_buffers("vs_main", &[vertex_buffer(&attributes)]), Err(ReflectionError::VertexFormat {
+            location: 1,
+            name: Some("uv".to_string()),
+            shader_format: wgpu::VertexFormat::Float32x2,
+            buffer_format: wgpu::VertexFormat::Uint32x2,
+        }));
+    }
+}
@@ -0,0 +1,847 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RenderGraphError {
+    Cycle(Vec<String>),
+    UninitializedRead { pass: String, resource: String },
+    MissingImport(String),
+}
+
+impl fmt::Display for RenderGraphError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenderGraphError::Cycle(passes) => write!(f, "render passes {} depend on each other", passes.join(", ")),
+            RenderGraphError::UninitializedRead { pass, resource } => {
+                write!(f, "pass \"{}\" reads \"{}\" before any pass writes it", pass, resource)
+            },
+            RenderGraphError::MissingImport(resource) => write!(f, "imported resource \"{}\" was not provided", resource),
+        }
+    }
+}
+
+impl Error for RenderGraphError { }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ResourceId(usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PassId(usize);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ClearValue {
+    Color(wgpu::Color),
+    Depth(f32),
+}
+
+impl ClearValue {
+    pub fn default_for(format: wgpu::TextureFormat) -> Self {
+        match format {
+            wgpu::TextureFormat::Depth32Float | wgpu::TextureFormat::Depth24Plus | wgpu::TextureFormat::Depth24PlusStencil8 => {
+                ClearValue::Depth(1.0)
+            },
+            _ => ClearValue::Color(wgpu::Color::TRANSPARENT),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TransientTexture {
+    pub width: u32,
+    pub height: u32,
+    pub format: wgpu::TextureFormat,
+    pub usage: wgpu::TextureUsage,
+}
+
+impl TransientTexture {
+    pub fn new(width: u32, height: u32, format: wgpu::TextureFormat, usage: wgpu::TextureUsage) -> Self {
+        Self {
+            width: width.max(1),
+            height: height.max(1),
+            format,
+            usage,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TransientBuffer {
+    pub size: wgpu::BufferAddress,
+    pub usage: wgpu::BufferUsage,
+}
+
+// Resources created by the graph itself. Two transient resources with equal
+// descriptors whose lifetimes do not overlap share the same allocation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TransientResource {
+    Texture(TransientTexture),
+    Buffer(TransientBuffer),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ResourceOrigin {
+    Transient(TransientResource),
+    ImportedTexture,
+    ImportedBuffer,
+}
+
+struct Resource {
+    name: String,
+    origin: ResourceOrigin,
+    clear: Option<ClearValue>,
+}
+
+// Declares what a pass reads and writes. Writes to a resource are applied in
+// the order their passes were added, passes that only read a resource see
+// the result of all writes to it.
+#[derive(Debug, Clone)]
+pub struct PassBuilder {
+    name: String,
+    reads: Vec<ResourceId>,
+    writes: Vec<ResourceId>,
+    side_effects: bool,
+}
+
+impl PassBuilder {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            reads: vec![],
+            writes: vec![],
+            side_effects: false,
+        }
+    }
+
+    pub fn reads(mut self, resource: ResourceId) -> Self {
+        if !self.reads.contains(&resource) {
+            self.reads.push(resource);
+        }
+        self
+    }
+
+    pub fn writes(mut self, resource: ResourceId) -> Self {
+        if !self.writes.contains(&resource) {
+            self.writes.push(resource);
+        }
+        self
+    }
+
+    // Keeps the pass even when nothing reads what it writes, for example a
+    // pass that only reads back data to the CPU.
+    pub fn with_side_effects(mut self) -> Self {
+        self.side_effects = true;
+        self
+    }
+}
+
+type ExecutePass<'a> = Box<dyn FnOnce(&mut PassContext<'_>) + 'a>;
+
+struct Pass<'a> {
+    declaration: PassBuilder,
+    execute: Option<ExecutePass<'a>>,
+}
+
+impl Pass<'_> {
+    fn accesses(&self) -> impl Iterator<Item = &ResourceId> {
+        self.declaration.reads.iter().chain(self.declaration.writes.iter())
+    }
+}
+
+// Passes that write an imported resource or have side effects are the roots
+// of the graph, every other pass is culled unless a kept pass depends on it.
+// A graph is usually built, compiled and executed once per frame.
+#[derive(Default)]
+pub struct RenderGraph<'a> {
+    resources: Vec<Resource>,
+    passes: Vec<Pass<'a>>,
+}
+
+impl<'a> RenderGraph<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn add_resource(&mut self, name: &str, origin: ResourceOrigin, clear: Option<ClearValue>) -> ResourceId {
+        self.resources.push(Resource {
+            name: name.to_string(),
+            origin,
+            clear,
+        });
+        ResourceId(self.resources.len() - 1)
+    }
+
+    // Transient textures are cleared by the first pass writing them.
+    pub fn create_texture(&mut self, name: &str, texture: TransientTexture) -> ResourceId {
+        let clear = ClearValue::default_for(texture.format);
+        self.add_resource(name, ResourceOrigin::Transient(TransientResource::Texture(texture)), Some(clear))
+    }
+
+    pub fn create_buffer(&mut self, name: &str, buffer: TransientBuffer) -> ResourceId {
+        self.add_resource(name, ResourceOrigin::Transient(TransientResource::Buffer(buffer)), None)
+    }
+
+    // The view of an imported texture, like the swap chain frame, is passed
+    // to `execute`. Without a clear value its contents are loaded.
+    pub fn import_texture(&mut self, name: &str, clear: Option<ClearValue>) -> ResourceId {
+        self.add_resource(name, ResourceOrigin::ImportedTexture, clear)
+    }
+
+    pub fn import_buffer(&mut self, name: &str) -> ResourceId {
+        self.add_resource(name, ResourceOrigin::ImportedBuffer, None)
+    }
+
+    pub fn set_clear_value(&mut self, resource: ResourceId, clear: Option<ClearValue>) {
+        self.resources[resource.0].clear = clear;
+    }
+
+    pub fn resource_name(&self, resource: ResourceId) -> &str {
+        &self.resources[resource.0].name
+    }
+
+    pub fn pass_name(&self, pass: PassId) -> &str {
+        &self.passes[pass.0].declaration.name
+    }
+
+    pub fn add_pass<F>(&mut self, declaration: PassBuilder, execute: F) -> PassId
+    where
+        F: FnOnce(&mut PassContext<'_>) + 'a,
+    {
+        self.passes.push(Pass {
+            declaration,
+            execute: Some(Box::new(execute)),
+        });
+        PassId(self.passes.len() - 1)
+    }
+
+    fn is_imported(&self, resource: ResourceId) -> bool {
+        !matches!(self.resources[resource.0].origin, ResourceOrigin::Transient(_))
+    }
+
+    // Orders the passes, culls the ones nothing depends on, decides which
+    // pass clears each resource and assigns transient resources to
+    // allocations. Nothing here touches the GPU.
+    pub fn compile(&self) -> Result<CompiledGraph, RenderGraphError> {
+        let mut writers: Vec<Vec<usize>> = vec![vec![]; self.resources.len()];
+        for (index, pass) in self.passes.iter().enumerate() {
+            for resource in &pass.declaration.writes {
+                writers[resource.0].push(index);
+            }
+        }
+
+        let kept = self.cull(&writers);
+        let kept_writers: Vec<Vec<usize>> = writers
+            .iter()
+            .map(|writers| writers.iter().copied().filter(|&writer| kept[writer]).collect())
+            .collect();
+
+        let order = self.sort(&kept, &kept_writers)?;
+        let culled = (0..self.passes.len()).filter(|&index| !kept[index]).map(PassId).collect();
+
+        let mut clear_passes = vec![None; self.resources.len()];
+        for (resource, writers) in kept_writers.iter().enumerate() {
+            if self.resources[resource].clear.is_some() {
+                clear_passes[resource] = writers.first().copied().map(PassId);
+            }
+        }
+
+        let mut lifetimes: Vec<Option<ResourceLifetime>> = vec![None; self.resources.len()];
+        for (position, pass) in order.iter().enumerate() {
+            for resource in self.passes[pass.0].accesses() {
+                let lifetime = lifetimes[resource.0].get_or_insert(ResourceLifetime {
+                    first: position,
+                    last: position,
+                });
+                lifetime.last = position;
+            }
+        }
+
+        let (aliases, physical_resources) = self.alias(&lifetimes);
+
+        Ok(CompiledGraph {
+            order,
+            culled,
+            lifetimes,
+            clear_passes,
+            aliases,
+            physical_resources,
+        })
+    }
+
+    fn cull(&self, writers: &[Vec<usize>]) -> Vec<bool> {
+        let mut kept = vec![false; self.passes.len()];
+        let mut stack: Vec<usize> = self
+            .passes
+            .iter()
+            .enumerate()
+            .filter(|(_, pass)| {
+                pass.declaration.side_effects || pass.declaration.writes.iter().any(|&resource| self.is_imported(resource))
+            })
+            .map(|(index, _)| index)
+            .collect();
+
+        while let Some(index) = stack.pop() {
+            if kept[index] {
+                continue;
+            }
+            kept[index] = true;
+
+            // Readers depend on every write, writers on the writes before them.
+            let declaration = &self.passes[index].declaration;
+            for resource in &declaration.reads {
+                stack.extend(writers[resource.0].iter().filter(|&&writer| writer != index));
+            }
+            for resource in &declaration.writes {
+                stack.extend(writers[resource.0].iter().filter(|&&writer| writer < index));
+            }
+        }
+
+        kept
+    }
+
+    fn sort(&self, kept: &[bool], writers: &[Vec<usize>]) -> Result<Vec<PassId>, RenderGraphError> {
+        let mut dependents: Vec<Vec<usize>> = vec![vec![]; self.passes.len()];
+        let mut dependencies = vec![0usize; self.passes.len()];
+        let mut add_edge = |from: usize, to: usize| {
+            dependents[from].push(to);
+            dependencies[to] += 1;
+        };
+
+        for writers in writers {
+            for pair in writers.windows(2) {
+                add_edge(pair[0], pair[1]);
+            }
+        }
+
+        for (index, pass) in self.passes.iter().enumerate().filter(|(index, _)| kept[*index]) {
+            for resource in &pass.declaration.reads {
+                let resource_writers = &writers[resource.0];
+                let initialized = match resource_writers.iter().position(|&writer| writer == index) {
+                    Some(position) => position > 0,
+                    None => match resource_writers.last() {
+                        Some(&writer) => {
+                            add_edge(writer, index);
+                            true
+                        },
+                        None => false,
+                    },
+                };
+
+                if !initialized && !self.is_imported(*resource) {
+                    return Err(RenderGraphError::UninitializedRead {
+                        pass: pass.declaration.name.clone(),
+                        resource: self.resources[resource.0].name.clone(),
+                    });
+                }
+            }
+        }
+
+        // Among passes that are ready the one added first runs first, so the
+        // order is stable and follows the order passes were added in.
+        let mut ready: BinaryHeap<Reverse<usize>> = (0..self.passes.len())
+            .filter(|&index| kept[index] && dependencies[index] == 0)
+            .map(Reverse)
+            .collect();
+        let mut order = vec![];
+        while let Some(Reverse(index)) = ready.pop() {
+            order.push(PassId(index));
+            for &dependent in &dependents[index] {
+                dependencies[dependent] -= 1;
+                if dependencies[dependent] == 0 {
+                    ready.push(Reverse(dependent));
+                }
+            }
+        }
+
+        let kept_count = kept.iter().filter(|&&kept| kept).count();
+        if order.len() < kept_count {
+            let passes = (0..self.passes.len())
+                .filter(|&index| kept[index] && dependencies[index] > 0)
+                .map(|index| self.passes[index].declaration.name.clone())
+                .collect();
+            return Err(RenderGraphError::Cycle(passes));
+        }
+
+        Ok(order)
+    }
+
+    fn alias(&self, lifetimes: &[Option<ResourceLifetime>]) -> (Vec<Option<usize>>, Vec<TransientResource>) {
+        let mut transients: Vec<(usize, TransientResource, ResourceLifetime)> = self
+            .resources
+            .iter()
+            .enumerate()
+            .filter_map(|(index, resource)| match (resource.origin, lifetimes[index]) {
+                (ResourceOrigin::Transient(transient), Some(lifetime)) => Some((index, transient, lifetime)),
+                _ => None,
+            })
+            .collect();
+        transients.sort_by_key(|(index, _, lifetime)| (lifetime.first, *index));
+
+        let mut aliases = vec![None; self.resources.len()];
+        let mut physical_resources = vec![];
+        let mut physical_last_use: Vec<usize> = vec![];
+
+        for (index, transient, lifetime) in transients {
+            let reusable = physical_resources
+                .iter()
+                .zip(&physical_last_use)
+                .position(|(physical, &last_use)| *physical == transient && last_use < lifetime.first);
+
+            let physical = match reusable {
+                Some(physical) => physical,
+                None => {
+                    physical_resources.push(transient);
+                    physical_last_use.push(0);
+                    physical_resources.len() - 1
+                },
+            };
+            physical_last_use[physical] = lifetime.last;
+            aliases[index] = Some(physical);
+        }
+
+        (aliases, physical_resources)
+    }
+
+    // Runs the compiled passes into a single command encoder and submits it.
+    pub fn execute(
+        self,
+        compiled: &CompiledGraph,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        transient_resources: &mut TransientResources,
+        imports: &ImportedResources<'_>,
+    ) -> Result<(), RenderGraphError> {
+        let RenderGraph { resources, mut passes } = self;
+
+        transient_resources.allocate(device, &compiled.physical_resources);
+
+        let mut textures = vec![None; resources.len()];
+        let mut views = vec![None; resources.len()];
+        let mut buffers = vec![None; resources.len()];
+        for (index, resource) in resources.iter().enumerate() {
+            if compiled.lifetimes[index].is_none() {
+                continue;
+            }
+
+            let id = ResourceId(index);
+            match resource.origin {
+                ResourceOrigin::Transient(_) => match compiled.aliases[index].map(|physical| &transient_resources.allocations[physical].1) {
+                    Some(Allocation::Texture(texture, view)) => {
+                        textures[index] = Some(texture);
+                        views[index] = Some(view);
+                    },
+                    Some(Allocation::Buffer(buffer)) => buffers[index] = Some(buffer),
+                    None => { },
+                },
+                ResourceOrigin::ImportedTexture => match imports.textures.get(&id) {
+                    Some(view) => views[index] = Some(*view),
+                    None => return Err(RenderGraphError::MissingImport(resource.name.clone())),
+                },
+                ResourceOrigin::ImportedBuffer => match imports.buffers.get(&id) {
+                    Some(buffer) => buffers[index] = Some(*buffer),
+                    None => return Err(RenderGraphError::MissingImport(resource.name.clone())),
+                },
+            }
+        }
+
+        let command_encoder_descriptor = wgpu::CommandEncoderDescriptor {
+            label: Some("Render Graph Encoder"),
+        };
+        let mut encoder = device.create_command_encoder(&command_encoder_descriptor);
+
+        for &pass in &compiled.order {
+            let execute = match passes[pass.0].execute.take() {
+                Some(execute) => execute,
+                None => continue,
+            };
+
+            let mut context = PassContext {
+                device,
+                queue,
+                encoder: &mut encoder,
+                resources: PassResources {
+                    pass,
+                    resources: &resources,
+                    textures: &textures,
+                    views: &views,
+                    buffers: &buffers,
+                    compiled,
+                },
+            };
+            execute(&mut context);
+        }
+
+        queue.submit(std::iter::once(encoder.finish()));
+        Ok(())
+    }
+}
+
+// Positions in the execution order of the first and last pass using a resource.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResourceLifetime {
+    pub first: usize,
+    pub last: usize,
+}
+
+impl ResourceLifetime {
+    pub fn overlaps(&self, other: &ResourceLifetime) -> bool {
+        self.first <= other.last && other.first <= self.last
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompiledGraph {
+    order: Vec<PassId>,
+    culled: Vec<PassId>,
+    lifetimes: Vec<Option<ResourceLifetime>>,
+    clear_passes: Vec<Option<PassId>>,
+    aliases: Vec<Option<usize>>,
+    physical_resources: Vec<TransientResource>,
+}
+
+impl CompiledGraph {
+    pub fn order(&self) -> &[PassId] {
+        &self.order
+    }
+
+    pub fn culled(&self) -> &[PassId] {
+        &self.culled
+    }
+
+    pub fn is_culled(&self, pass: PassId) -> bool {
+        self.culled.contains(&pass)
+    }
+
+    // `None` for resources no remaining pass uses.
+    pub fn lifetime(&self, resource: ResourceId) -> Option<ResourceLifetime> {
+        self.lifetimes[resource.0]
+    }
+
+    pub fn clear_pass(&self, resource: ResourceId) -> Option<PassId> {
+        self.clear_passes[resource.0]
+    }
+
+    pub fn clears(&self, pass: PassId, resource: ResourceId) -> bool {
+        self.clear_passes[resource.0] == Some(pass)
+    }
+
+    // Index into `physical_resources` of the allocation backing a transient
+    // resource.
+    pub fn physical_resource(&self, resource: ResourceId) -> Option<usize> {
+        self.aliases[resource.0]
+    }
+
+    pub fn physical_resources(&self) -> &[TransientResource] {
+        &self.physical_resources
+    }
+}
+
+#[derive(Default)]
+pub struct ImportedResources<'r> {
+    textures: HashMap<ResourceId, &'r wgpu::TextureView>,
+    buffers: HashMap<ResourceId, &'r wgpu::Buffer>,
+}
+
+impl<'r> ImportedResources<'r> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_texture(mut self, resource: ResourceId, view: &'r wgpu::TextureView) -> Self {
+        self.textures.insert(resource, view);
+        self
+    }
+
+    pub fn with_buffer(mut self, resource: ResourceId, buffer: &'r wgpu::Buffer) -> Self {
+        self.buffers.insert(resource, buffer);
+        self
+    }
+}
+
+enum Allocation {
+    Texture(wgpu::Texture, wgpu::TextureView),
+    Buffer(wgpu::Buffer),
+}
+
+impl Allocation {
+    fn new(device: &wgpu::Device, resource: &TransientResource) -> Self {
+        match resource {
+            TransientResource::Texture(texture) => {
+                let texture_descriptor = wgpu::TextureDescriptor {
+                    label: Some("transient texture"),
+                    size: wgpu::Extent3d {
+                        width: texture.width,
+                        height: texture.height,
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format: texture.format,
+                    usage: texture.usage,
+                };
+                let texture = device.create_texture(&texture_descriptor);
+                let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+                Allocation::Texture(texture, view)
+            },
+            TransientResource::Buffer(buffer) => {
+                let buffer_descriptor = wgpu::BufferDescriptor {
+                    label: Some("transient buffer"),
+                    size: buffer.size,
+                    usage: buffer.usage,
+                    mapped_at_creation: false,
+                };
+                Allocation::Buffer(device.create_buffer(&buffer_descriptor))
+            },
+        }
+    }
+}
+
+// Keeps the allocations of transient resources alive between frames so a
+// graph with the same resources does not allocate again.
+#[derive(Default)]
+pub struct TransientResources {
+    allocations: Vec<(TransientResource, Allocation)>,
+}
+
+impl TransientResources {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.allocations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.allocations.is_empty()
+    }
+
+    // Allocations that are not needed anymore, for example after a resize,
+    // are released.
+    fn allocate(&mut self, device: &wgpu::Device, resources: &[TransientResource]) {
+        let mut previous = std::mem::take(&mut self.allocations);
+        for resource in resources {
+            let allocation = match previous.iter().position(|(previous, _)| previous == resource) {
+                Some(index) => previous.swap_remove(index).1,
+                None => Allocation::new(device, resource),
+            };
+            self.allocations.push((*resource, allocation));
+        }
+    }
+}
+
+// The resources of the graph as seen from the pass being executed.
+pub struct PassResources<'r> {
+    pass: PassId,
+    resources: &'r [Resource],
+    textures: &'r [Option<&'r wgpu::Texture>],
+    views: &'r [Option<&'r wgpu::TextureView>],
+    buffers: &'r [Option<&'r wgpu::Buffer>],
+    compiled: &'r CompiledGraph,
+}
+
+impl<'r> PassResources<'r> {
+    // Only transient textures, imported textures are provided as views.
+    pub fn texture(&self, resource: ResourceId) -> &'r wgpu::Texture {
+        self.textures[resource.0].unwrap_or_else(|| panic!("\"{}\" is not a transient texture used by the graph", self.resources[resource.0].name))
+    }
+
+    pub fn texture_view(&self, resource: ResourceId) -> &'r wgpu::TextureView {
+        self.views[resource.0].unwrap_or_else(|| panic!("\"{}\" is not a texture used by the graph", self.resources[resource.0].name))
+    }
+
+    pub fn buffer(&self, resource: ResourceId) -> &'r wgpu::Buffer {
+        self.buffers[resource.0].unwrap_or_else(|| panic!("\"{}\" is not a buffer used by the graph", self.resources[resource.0].name))
+    }
+
+    fn clear_value(&self, resource: ResourceId) -> Option<ClearValue> {
+        match self.compiled.clears(self.pass, resource) {
+            true => self.resources[resource.0].clear,
+            false => None,
+        }
+    }
+
+    pub fn color_attachment(&self, resource: ResourceId) -> wgpu::RenderPassColorAttachment<'r> {
+        let load = match self.clear_value(resource) {
+            Some(ClearValue::Color(color)) => wgpu::LoadOp::Clear(color),
+            Some(ClearValue::Depth(_)) => wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+            None => wgpu::LoadOp::Load,
+        };
+
+        wgpu::RenderPassColorAttachment {
+            view: self.texture_view(resource),
+            resolve_target: None,
+            ops: wgpu::Operations {
+                load,
+                store: true,
+            },
+        }
+    }
+
+    pub fn depth_stencil_attachment(&self, resource: ResourceId) -> wgpu::RenderPassDepthStencilAttachment<'r> {
+        let load = match self.clear_value(resource) {
+            Some(ClearValue::Depth(depth)) => wgpu::LoadOp::Clear(depth),
+            Some(ClearValue::Color(_)) => wgpu::LoadOp::Clear(1.0),
+            None => wgpu::LoadOp::Load,
+        };
+
+        wgpu::RenderPassDepthStencilAttachment {
+            view: self.texture_view(resource),
+            depth_ops: Some(wgpu::Operations {
+                load,
+                store: true,
+            }),
+            stencil_ops: None,
+        }
+    }
+}
+
+pub struct PassContext<'r> {
+    pub device: &'r wgpu::Device,
+    pub queue: &'r wgpu::Queue,
+    pub encoder: &'r mut wgpu::CommandEncoder,
+    pub resources: PassResources<'r>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn color_texture() -> TransientTexture {
+        TransientTexture::new(640, 480, wgpu::TextureFormat::Rgba8UnormSrgb, wgpu::TextureUsage::RENDER_ATTACHMENT | wgpu::TextureUsage::SAMPLED)
+    }
+
+    fn names(graph: &RenderGraph<'_>, passes: &[PassId]) -> Vec<String> {
+        passes.iter().map(|&pass| graph.pass_name(pass).to_string()).collect()
+    }
+
+    #[test]
+    fn passes_run_in_the_order_they_were_added_unless_a_dependency_comes_later() {
+        let mut graph = RenderGraph::new();
+        let frame = graph.import_texture("frame", None);
+        let overlay = graph.import_texture("overlay", None);
+        let scene = graph.create_texture("scene", color_texture());
+        let shadow = graph.create_texture("shadow", color_texture());
+
+        graph.add_pass(PassBuilder::new("composite").reads(scene).writes(frame), |_| { });
+        graph.add_pass(PassBuilder::new("ui").writes(overlay), |_| { });
+        graph.add_pass(PassBuilder::new("shadow").writes(shadow), |_| { });
+        graph.add_pass(PassBuilder::new("scene").reads(shadow).writes(scene), |_| { });
+
+        let compiled = graph.compile().unwrap();
+        assert_eq!(names(&graph, compiled.order()), ["ui", "shadow", "scene", "composite"]);
+        assert_eq!(graph.compile().unwrap(), compiled);
+    }
+
+    #[test]
+    fn passes_that_feed_nothing_are_culled() {
+        let mut graph = RenderGraph::new();
+        let frame = graph.import_texture("frame", None);
+        let scene = graph.create_texture("scene", color_texture());
+        let debug = graph.create_texture("debug", color_texture());
+
+        graph.add_pass(PassBuilder::new("scene").writes(scene), |_| { });
+        graph.add_pass(PassBuilder::new("debug").reads(scene).writes(debug), |_| { });
+        graph.add_pass(PassBuilder::new("readback").reads(scene).with_side_effects(), |_| { });
+        graph.add_pass(PassBuilder::new("present").reads(scene).writes(frame), |_| { });
+
+        let compiled = graph.compile().unwrap();
+        assert_eq!(names(&graph, compiled.order()), ["scene", "readback", "present"]);
+        assert_eq!(names(&graph, compiled.culled()), ["debug"]);
+        assert_eq!(compiled.lifetime(debug), None);
+        assert_eq!(compiled.physical_resource(debug), None);
+    }
+
+    #[test]
+    fn reading_a_transient_nobody_writes_is_an_error() {
+        let mut graph = RenderGraph::new();
+        let frame = graph.import_texture("frame", None);
+        let history = graph.import_texture("history", None);
+        let scene = graph.create_texture("scene", color_texture());
+
+        graph.add_pass(PassBuilder::new("present").reads(history).reads(scene).writes(frame), |_| { });
+
+        assert_eq!(graph.compile(), Err(RenderGraphError::UninitializedRead {
+            pass: "present".to_string(),
+            resource: "scene".to_string(),
+        }));
+    }
+
+    #[test]
+    fn passes_reading_each_others_output_are_a_cycle() {
+        let mut graph = RenderGraph::new();
+        let frame = graph.import_texture("frame", None);
+        let a = graph.create_texture("a", color_texture());
+        let b = graph.create_texture("b", color_texture());
+
+        graph.add_pass(PassBuilder::new("first").reads(b).writes(a).writes(frame), |_| { });
+        graph.add_pass(PassBuilder::new("second").reads(a).writes(b), |_| { });
+
+        assert_eq!(graph.compile(), Err(RenderGraphError::Cycle(vec!["first".to_string(), "second".to_string()])));
+    }
+
+    #[test]
+    fn the_first_kept_writer_clears() {
+        let mut graph = RenderGraph::new();
+        let frame = graph.import_texture("frame", Some(ClearValue::Color(wgpu::Color::BLACK)));
+        let loaded = graph.import_texture("loaded", None);
+        let scene = graph.create_texture("scene", color_texture());
+        let unused = graph.create_texture("unused", color_texture());
+
+        graph.add_pass(PassBuilder::new("unused").writes(unused), |_| { });
+        let opaque = graph.add_pass(PassBuilder::new("opaque").writes(scene).writes(loaded), |_| { });
+        graph.add_pass(PassBuilder::new("transparent").writes(scene), |_| { });
+        let present = graph.add_pass(PassBuilder::new("present").reads(scene).writes(frame), |_| { });
+        let overlay = graph.add_pass(PassBuilder::new("overlay").writes(frame), |_| { });
+
+        let compiled = graph.compile().unwrap();
+        assert_eq!(compiled.clear_pass(scene), Some(opaque));
+        assert_eq!(compiled.clear_pass(frame), Some(present));
+        assert!(compiled.clears(present, frame));
+        assert!(!compiled.clears(overlay, frame));
+        assert_eq!(compiled.clear_pass(loaded), None);
+        assert_eq!(compiled.clear_pass(unused), None);
+    }
+
+    #[test]
+    fn transients_with_disjoint_lifetimes_share_an_allocation() {
+        let mut graph = RenderGraph::new();
+        let frame = graph.import_texture("frame", None);
+        let small = TransientTexture::new(320, 240, wgpu::TextureFormat::Rgba8UnormSrgb, color_texture().usage);
+        let scene = graph.create_texture("scene", color_texture());
+        let bloom = graph.create_texture("bloom", small);
+        let blurred = graph.create_texture("blurred", color_texture());
+
+        graph.add_pass(PassBuilder::new("scene").writes(scene), |_| { });
+        graph.add_pass(PassBuilder::new("bloom").reads(scene).writes(bloom), |_| { });
+        graph.add_pass(PassBuilder::new("blur").reads(bloom).writes(blurred), |_| { });
+        graph.add_pass(PassBuilder::new("present").reads(blurred).writes(frame), |_| { });
+
+        let compiled = graph.compile().unwrap();
+        assert_eq!(compiled.lifetime(scene), Some(ResourceLifetime { first: 0, last: 1 }));
+        assert_eq!(compiled.lifetime(blurred), Some(ResourceLifetime { first: 2, last: 3 }));
+        assert_eq!(compiled.physical_resource(scene), compiled.physical_resource(blurred));
+        assert_ne!(compiled.physical_resource(scene), compiled.physical_resource(bloom));
+        assert_eq!(compiled.physical_resources(), [TransientResource::Texture(color_texture()), TransientResource::Texture(small)]);
+        assert_eq!(compiled.physical_resource(frame), None);
+    }
+
+    #[test]
+    fn transients_with_overlapping_lifetimes_do_not_share_an_allocation() {
+        let mut graph = RenderGraph::new();
+        let frame = graph.import_texture("frame", None);
+        let scene = graph.create_texture("scene", color_texture());
+        let blurred = graph.create_texture("blurred", color_texture());
+
+        graph.add_pass(PassBuilder::new("scene").writes(scene), |_| { });
+        graph.add_pass(PassBuilder::new("blur").reads(scene).writes(blurred), |_| { });
+        graph.add_pass(PassBuilder::new("present").reads(blurred).writes(frame), |_| { });
+
+        let compiled = graph.compile().unwrap();
+        assert!(compiled.lifetime(scene).unwrap().overlaps(&compiled.lifetime(blurred).unwrap()));
+        assert_ne!(compiled.physical_resource(scene), compiled.physical_resource(blurred));
+        assert_eq!(compiled.physical_resources().len(), 2);
+    }
+}
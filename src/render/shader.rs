@@ -0,0 +1,521 @@
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceLocation {
+    pub file: String,
+    pub line: usize,
+}
+
+impl fmt::Display for SourceLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.file, self.line)
+    }
+}
+
+#[derive(Debug)]
+pub enum ShaderErrorKind {
+    NotFound(String),
+    Io(String, std::io::Error),
+    UnknownDirective(String),
+    MissingArgument(String),
+    UnexpectedDirective(String),
+    UnterminatedConditional,
+    Parse(String),
+    Validation(String),
+}
+
+#[derive(Debug)]
+pub struct ShaderError {
+    pub kind: ShaderErrorKind,
+    pub location: Option<SourceLocation>,
+}
+
+impl ShaderError {
+    fn new(kind: ShaderErrorKind, location: Option<SourceLocation>) -> Self {
+        Self {
+            kind,
+            location,
+        }
+    }
+}
+
+impl fmt::Display for ShaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(location) = &self.location {
+            write!(f, "{}: ", location)?;
+        }
+
+        match &self.kind {
+            ShaderErrorKind::NotFound(file) => write!(f, "shader file \"{}\" not found", file),
+            ShaderErrorKind::Io(file, error) => write!(f, "failed to read shader file \"{}\": {}", file, error),
+            ShaderErrorKind::UnknownDirective(directive) => write!(f, "unknown directive #{}", directive),
+            ShaderErrorKind::MissingArgument(directive) => write!(f, "#{} is missing its argument", directive),
+            ShaderErrorKind::UnexpectedDirective(directive) => write!(f, "#{} without a matching #ifdef", directive),
+            ShaderErrorKind::UnterminatedConditional => write!(f, "#ifdef without a matching #endif"),
+            ShaderErrorKind::Parse(message) => write!(f, "{}", message),
+            ShaderErrorKind::Validation(message) => write!(f, "shader validation failed: {}", message),
+        }
+    }
+}
+
+impl Error for ShaderError { }
+
+// Preprocessed WGSL together with the original file and line of every line.
+#[derive(Debug, Clone, Default)]
+pub struct PreprocessedShader {
+    pub source: String,
+    // Files on disk the shader was built from, in-memory sources are left out.
+    pub paths: Vec<PathBuf>,
+    line_map: Vec<SourceLocation>,
+}
+
+impl PreprocessedShader {
+    fn push_line(&mut self, line: &str, location: SourceLocation) {
+        self.source.push_str(line);
+        self.source.push('\n');
+        self.line_map.push(location);
+    }
+
+    // Maps a 1-based line of the preprocessed source back to where it came from.
+    pub fn original_location(&self, line: usize) -> Option<&SourceLocation> {
+        line.checked_sub(1).and_then(|index| self.line_map.get(index))
+    }
+
+    pub fn parse(&self) -> Result<naga::Module, ShaderError> {
+        let module = naga::front::wgsl::parse_str(&self.source).map_err(|error| {
+            let (line, _) = error.location(&self.source);
+            let location = self.original_location(line).cloned();
+            ShaderError::new(ShaderErrorKind::Parse(error.to_string()), location)
+        })?;
+
+        let mut validator = naga::valid::Validator::new(naga::valid::ValidationFlags::all(), naga::valid::Capabilities::all());
+        validator.validate(&module).map_err(|error| {
+            // Validation errors carry no spans, only the name of what failed
+            // to validate, so the location is that of its declaration.
+            let location = validation_error_declaration(&error)
+                .and_then(|(keyword, name)| self.declaration_line(keyword, name))
+                .and_then(|line| self.original_location(line))
+                .cloned();
+            ShaderError::new(ShaderErrorKind::Validation(error_with_sources(&error)), location)
+        })?;
+
+        Ok(module)
+    }
+
+    // Line number, starting at 1, of the first line declaring `name` after `keyword`.
+    fn declaration_line(&self, keyword: &str, name: &str) -> Option<usize> {
+        self.source
+            .lines()
+            .position(|line| {
+                let mut tokens = line.split(|c: char| !(c.is_alphanumeric() || c == '_')).filter(|token| !token.is_empty());
+                tokens.any(|token| token == keyword) && tokens.next() == Some(name)
+            })
+            .map(|index| index + 1)
+    }
+
+    pub fn create_shader_module(&self, device: &wgpu::Device, label: &str) -> wgpu::ShaderModule {
+        let shader_module_descriptor = wgpu::ShaderModuleDescriptor {
+            label: Some(label),
+            flags: wgpu::ShaderFlags::all(),
+            source: wgpu::ShaderSource::Wgsl(self.source.as_str().into()),
+        };
+        device.create_shader_module(&shader_module_descriptor)
+    }
+}
+
+struct Conditional {
+    active: bool,
+    parent_active: bool,
+    seen_else: bool,
+    location: SourceLocation,
+}
+
+// Loads WGSL with a small preprocessor on top:
+//
+//     #include "common.wgsl"
+//     #define NAME [value]
+//     #undef NAME
+//     #ifdef NAME / #ifndef NAME / #else / #endif
+//
+// Every file is included at most once. Defines with a value are substituted
+// wherever their name appears as an identifier.
+#[derive(Debug, Clone, Default)]
+pub struct ShaderLoader {
+    search_paths: Vec<PathBuf>,
+    sources: HashMap<String, String>,
+    defines: HashMap<String, String>,
+}
+
+impl ShaderLoader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_search_path<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.search_paths.push(path.into());
+        self
+    }
+
+    // Registers an in-memory source, for example from `include_str!`, which
+    // takes precedence over files on disk with the same name.
+    pub fn with_source(mut self, name: &str, source: &str) -> Self {
+        self.sources.insert(name.to_string(), source.to_string());
+        self
+    }
+
+    pub fn with_define(mut self, name: &str) -> Self {
+        self.define(name, "");
+        self
+    }
+
+    pub fn with_define_value(mut self, name: &str, value: &str) -> Self {
+        self.define(name, value);
+        self
+    }
+
+    pub fn define(&mut self, name: &str, value: &str) {
+        self.defines.insert(name.to_string(), value.to_string());
+    }
+
+    pub fn undefine(&mut self, name: &str) {
+        self.defines.remove(name);
+    }
+
+    pub fn is_defined(&self, name: &str) -> bool {
+        self.defines.contains_key(name)
+    }
+
+    pub fn preprocess(&self, name: &str) -> Result<PreprocessedShader, ShaderError> {
+        let mut output = PreprocessedShader::default();
+        self.preprocess_into(name, &mut output)?;
+        Ok(output)
+    }
+
+    // Like `preprocess`, but on failure `output` still holds everything up to
+    // the error, including the paths of the files read so far.
+    pub fn preprocess_into(&self, name: &str, output: &mut PreprocessedShader) -> Result<(), ShaderError> {
+        let mut defines = self.defines.clone();
+        let mut included = HashSet::new();
+
+        self.process_file(name, None, None, &mut defines, &mut included, output)
+    }
+
+    pub fn create_shader_module(&self, device: &wgpu::Device, name: &str) -> Result<wgpu::ShaderModule, ShaderError> {
+        let shader = self.preprocess(name)?;
+        shader.parse()?;
+
+        Ok(shader.create_shader_module(device, name))
+    }
+
+    fn process_file(
+        &self,
+        name: &str,
+        parent_directory: Option<&Path>,
+        include_location: Option<&SourceLocation>,
+        defines: &mut HashMap<String, String>,
+        included: &mut HashSet<String>,
+        output: &mut PreprocessedShader,
+    ) -> Result<(), ShaderError> {
+        let (file, source, path) = self.read_source(name, parent_directory, include_location)?;
+        // Files on disk can be reached through different relative paths.
+        let key = match path.as_ref().and_then(|path| fs::canonicalize(path).ok()) {
+            Some(canonical_path) => canonical_path.to_string_lossy().to_string(),
+            None => file.clone(),
+        };
+        if !included.insert(key) {
+            return Ok(());
+        }
+        output.paths.extend(path);
+
+        let directory = Path::new(&file).parent().map(Path::to_path_buf);
+        let mut conditionals: Vec<Conditional> = vec![];
+
+        for (index, line) in source.lines().enumerate() {
+            let location = SourceLocation {
+                file: file.clone(),
+                line: index + 1,
+            };
+            let active = match conditionals.last() {
+                Some(conditional) => conditional.active,
+                None => true,
+            };
+
+            let directive = match line.trim_start().strip_prefix('#') {
+                Some(directive) => directive.trim(),
+                None => {
+                    if active {
+                        output.push_line(&substitute_defines(line, defines), location);
+                    }
+                    continue;
+                },
+            };
+
+            let (directive, argument) = match directive.find(char::is_whitespace) {
+                Some(index) => (&directive[..index], directive[index..].trim()),
+                None => (directive, ""),
+            };
+
+            match directive {
+                "ifdef" | "ifndef" => {
+                    let name = required_argument(directive, argument, &location)?;
+                    let condition = defines.contains_key(name) == (directive == "ifdef");
+                    conditionals.push(Conditional {
+                        active: active && condition,
+                        parent_active: active,
+                        seen_else: false,
+                        location,
+                    });
+                },
+                "else" => match conditionals.last_mut() {
+                    Some(conditional) if !conditional.seen_else => {
+                        conditional.active = conditional.parent_active && !conditional.active;
+                        conditional.seen_else = true;
+                    },
+                    _ => return Err(ShaderError::new(ShaderErrorKind::UnexpectedDirective(directive.to_string()), Some(location))),
+                },
+                "endif" => {
+                    if conditionals.pop().is_none() {
+                        return Err(ShaderError::new(ShaderErrorKind::UnexpectedDirective(directive.to_string()), Some(location)));
+                    }
+                },
+                _ if !active => { },
+                "define" => {
+                    let definition = required_argument(directive, argument, &location)?;
+                    let (name, value) = match definition.find(char::is_whitespace) {
+                        Some(index) => (&definition[..index], definition[index..].trim()),
+                        None => (definition, ""),
+                    };
+                    defines.insert(name.to_string(), value.to_string());
+                },
+                "undef" => {
+                    let name = required_argument(directive, argument, &location)?;
+                    defines.remove(name);
+                },
+                "include" => {
+                    let include = argument.trim_matches(|c| c == '"' || c == '<' || c == '>');
+                    let include = required_argument(directive, include, &location)?;
+                    self.process_file(include, directory.as_deref(), Some(&location), defines, included, output)?;
+                },
+                _ => return Err(ShaderError::new(ShaderErrorKind::UnknownDirective(directive.to_string()), Some(location))),
+            }
+        }
+
+        match conditionals.pop() {
+            Some(conditional) => Err(ShaderError::new(ShaderErrorKind::UnterminatedConditional, Some(conditional.location))),
+            None => Ok(()),
+        }
+    }
+
+    // Looks for a source in the registered sources, next to the including
+    // file, in the search paths and finally relative to the working directory.
+    fn read_source(&self, name: &str, parent_directory: Option<&Path>, include_location: Option<&SourceLocation>) -> Result<(String, String, Option<PathBuf>), ShaderError> {
+        if let Some(source) = self.sources.get(name) {
+            return Ok((name.to_string(), source.clone(), None));
+        }
+
+        let candidates = parent_directory
+            .into_iter()
+            .chain(self.search_paths.iter().map(PathBuf::as_path))
+            .map(|directory| directory.join(name))
+            .chain(std::iter::once(PathBuf::from(name)));
+
+        for path in candidates {
+            if path.is_file() {
+                let file = path.to_string_lossy().to_string();
+                return match fs::read_to_string(&path) {
+                    Ok(source) => Ok((file, source, Some(path))),
+                    Err(error) => Err(ShaderError::new(ShaderErrorKind::Io(file, error), include_location.cloned())),
+                };
+            }
+        }
+
+        Err(ShaderError::new(ShaderErrorKind::NotFound(name.to_string()), include_location.cloned()))
+    }
+}
+
+fn validation_error_declaration(error: &naga::valid::ValidationError) -> Option<(&'static str, &str)> {
+    match error {
+        naga::valid::ValidationError::Function { name, .. } => Some(("fn", name)),
+        naga::valid::ValidationError::EntryPoint { name, .. } => Some(("fn", name)),
+        naga::valid::ValidationError::Type { name, .. } => Some(("struct", name)),
+        _ => None,
+    }
+}
+
+// Naga puts the actual problem in the source of its errors.
+fn error_with_sources(error: &dyn Error) -> String {
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(error) = source {
+        message.push_str(": ");
+        message.push_str(&error.to_string());
+        source = error.source();
+    }
+    message
+}
+
+fn required_argument<'a>(directive: &str, argument: &'a str, location: &SourceLocation) -> Result<&'a str, ShaderError> {
+    if argument.is_empty() {
+        return Err(ShaderError::new(ShaderErrorKind::MissingArgument(directive.to_string()), Some(location.clone())));
+    }
+    Ok(argument)
+}
+
+fn substitute_defines(line: &str, defines: &HashMap<String, String>) -> String {
+    let mut output = String::with_capacity(line.len());
+    let mut identifier = String::new();
+
+    for c in line.chars().chain(std::iter::once('\n')) {
+        if c.is_alphanumeric() || c == '_' {
+            identifier.push(c);
+            continue;
+        }
+
+        match defines.get(&identifier) {
+            Some(value) if !value.is_empty() && !identifier.starts_with(|c: char| c.is_ascii_digit()) => output.push_str(value),
+            _ => output.push_str(&identifier),
+        }
+        identifier.clear();
+
+        if c != '\n' {
+            output.push(c);
+        }
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn files_reached_through_different_paths_are_included_once() {
+        let directory = std::env::temp_dir().join(format!("webgpu-shader-test-{}", std::process::id()));
+        let shaders = directory.join("shaders");
+        fs::create_dir_all(&shaders).unwrap();
+        fs::write(shaders.join("common.wgsl"), "let common: f32 = 1.0;\n").unwrap();
+        fs::write(shaders.join("main.wgsl"), "#include \"./common.wgsl\"\n#include \"../shaders/common.wgsl\"\n").unwrap();
+
+        let shader = ShaderLoader::new().preprocess(shaders.join("main.wgsl").to_str().unwrap());
+        fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(shader.unwrap().source.matches("let common").count(), 1);
+    }
+
+    #[test]
+    fn in_memory_sources_are_included_once() {
+        let loader = ShaderLoader::new()
+            .with_source("common.wgsl", "let common: f32 = 1.0;")
+            .with_source("main.wgsl", "#include \"common.wgsl\"\n#include \"common.wgsl\"");
+        assert_eq!(loader.preprocess("main.wgsl").unwrap().source.matches("let common").count(), 1);
+    }
+
+    #[test]
+    fn validation_errors_point_at_the_failing_function() {
+        let loader = ShaderLoader::new()
+            .with_source("common.wgsl", "fn broken() -> f32 {\n    return 1u;\n}")
+            .with_source("main.wgsl", "// main\n#include \"common.wgsl\"");
+        let error = loader.preprocess("main.wgsl").unwrap().parse().unwrap_err();
+
+        assert!(matches!(error.kind, ShaderErrorKind::Validation(ref message) if message.contains("broken")), "{}", error);
+        assert_eq!(error.location, Some(SourceLocation {
+            file: "common.wgsl".to_string(),
+            line: 1,
+        }));
+    }
+
+    fn lines(shader: &PreprocessedShader) -> Vec<&str> {
+        shader.source.lines().collect()
+    }
+
+    #[test]
+    fn defines_are_substituted_by_whole_identifiers() {
+        let loader = ShaderLoader::new()
+            .with_define_value("COUNT", "8")
+            .with_source("main.wgsl", "#define SIZE 4\nlet size: u32 = SIZE;\nlet count: u32 = COUNT;\nlet SIZE_2: u32 = 2u;\n#undef SIZE\nlet after: u32 = SIZE;");
+        let shader = loader.preprocess("main.wgsl").unwrap();
+
+        assert_eq!(lines(&shader), vec![
+            "let size: u32 = 4;",
+            "let count: u32 = 8;",
+            "let SIZE_2: u32 = 2u;",
+            "let after: u32 = SIZE;",
+        ]);
+    }
+
+    #[test]
+    fn ifdef_and_else_pick_one_branch() {
+        let source = "#ifdef SHADOWS\nshadows\n#else\nno shadows\n#endif\n#ifndef SHADOWS\nnot defined\n#endif\nalways";
+        let loader = ShaderLoader::new().with_source("main.wgsl", source);
+        assert_eq!(lines(&loader.preprocess("main.wgsl").unwrap()), vec!["no shadows", "not defined", "always"]);
+
+        let loader = loader.with_define("SHADOWS");
+        assert_eq!(lines(&loader.preprocess("main.wgsl").unwrap()), vec!["shadows", "always"]);
+    }
+
+    #[test]
+    fn nested_conditionals_need_every_parent_active() {
+        let source = "#ifdef OUTER\n#ifdef INNER\nboth\n#else\nouter only\n#endif\n#else\n#ifdef INNER\ninner only\n#else\nneither\n#endif\n#endif";
+        let loader = ShaderLoader::new().with_source("main.wgsl", source);
+        let preprocess = |defines: &[&str]| {
+            let mut loader = loader.clone();
+            for define in defines {
+                loader.define(define, "");
+            }
+            loader.preprocess("main.wgsl").unwrap().source
+        };
+
+        assert_eq!(preprocess(&["OUTER", "INNER"]), "both\n");
+        assert_eq!(preprocess(&["OUTER"]), "outer only\n");
+        assert_eq!(preprocess(&["INNER"]), "inner only\n");
+        assert_eq!(preprocess(&[]), "neither\n");
+    }
+
+    #[test]
+    fn defines_in_inactive_branches_are_ignored() {
+        let loader = ShaderLoader::new().with_source("main.wgsl", "#ifdef MISSING\n#define VALUE 1\n#endif\nVALUE");
+        assert_eq!(lines(&loader.preprocess("main.wgsl").unwrap()), vec!["VALUE"]);
+    }
+
+    #[test]
+    fn unbalanced_conditionals_are_errors() {
+        let loader = ShaderLoader::new()
+            .with_source("unterminated.wgsl", "line\n#ifdef A\n")
+            .with_source("extra_else.wgsl", "#ifdef A\n#else\n#else\n#endif")
+            .with_source("extra_endif.wgsl", "#endif");
+
+        let error = loader.preprocess("unterminated.wgsl").unwrap_err();
+        assert!(matches!(error.kind, ShaderErrorKind::UnterminatedConditional));
+        assert_eq!(error.location, Some(SourceLocation {
+            file: "unterminated.wgsl".to_string(),
+            line: 2,
+        }));
+
+        let error = loader.preprocess("extra_else.wgsl").unwrap_err();
+        assert!(matches!(error.kind, ShaderErrorKind::UnexpectedDirective(ref directive) if directive == "else"));
+        assert_eq!(error.location.unwrap().line, 3);
+
+        let error = loader.preprocess("extra_endif.wgsl").unwrap_err();
+        assert!(matches!(error.kind, ShaderErrorKind::UnexpectedDirective(ref directive) if directive == "endif"));
+    }
+
+    #[test]
+    fn parse_errors_point_at_the_included_file() {
+        let loader = ShaderLoader::new()
+            .with_define("HELPERS")
+            .with_source("common.wgsl", "fn one() -> f32 {\n    return 1.0 +;\n}\n")
+            .with_source("main.wgsl", "// main\n#ifdef HELPERS\n#include \"common.wgsl\"\n#endif\nfn two() -> f32 {\n    return 2.0;\n}");
+        let error = loader.preprocess("main.wgsl").unwrap().parse().unwrap_err();
+
+        assert!(matches!(error.kind, ShaderErrorKind::Parse(_)), "{}", error);
+        assert_eq!(error.location, Some(SourceLocation {
+            file: "common.wgsl".to_string(),
+            line: 2,
+        }), "{}", error);
+    }
+}
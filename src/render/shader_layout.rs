@@ -0,0 +1,120 @@
+pub use webgpu_derive::ShaderType;
+
+/// Alignment and size of a type in WGSL host-shareable memory. Deriving it
+/// for a struct also checks at compile time that the `#[repr(C)]` layout of
+/// the struct matches the WGSL layout.
+///
+/// ```
+/// use webgpu::render::ShaderType;
+///
+/// #[repr(C)]
+/// #[derive(ShaderType)]
+/// struct Light {
+///     intensity: f32,
+///     #[padding]
+///     _padding: [u32; 3],
+///     color: [f32; 3],
+///     #[padding]
+///     _color_padding: u32,
+/// }
+/// ```
+///
+/// A `vec3<f32>` is aligned to 16 bytes, so it can not follow an `f32`
+/// directly:
+///
+/// ```compile_fail
+/// use webgpu::render::ShaderType;
+///
+/// #[repr(C)]
+/// #[derive(ShaderType)]
+/// struct Light {
+///     intensity: f32,
+///     color: [f32; 3],
+///     #[padding]
+///     _color_padding: u32,
+/// }
+/// ```
+///
+/// Uniform buffer structs are rounded up to 16 bytes:
+///
+/// ```compile_fail
+/// use webgpu::render::ShaderType;
+///
+/// #[repr(C)]
+/// #[derive(ShaderType)]
+/// struct Light {
+///     intensity: f32,
+///     #[padding]
+///     _padding: [u32; 3],
+///     color: [f32; 3],
+/// }
+/// ```
+///
+/// Without `#[repr(C)]` the field order is not guaranteed:
+///
+/// ```compile_fail
+/// use webgpu::render::ShaderType;
+///
+/// #[repr(align(16))]
+/// #[derive(ShaderType)]
+/// struct Color {
+///     color: [f32; 4],
+/// }
+/// ```
+pub trait ShaderType {
+    const ALIGN: u64;
+    const SIZE: u64;
+    // Structs inside uniform buffers are aligned to 16 bytes (std140).
+    const UNIFORM_ALIGN: u64 = Self::ALIGN;
+}
+
+pub const fn max_align(a: u64, b: u64) -> u64 {
+    if a > b {
+        a
+    } else {
+        b
+    }
+}
+
+macro_rules! impl_shader_scalar {
+    ($($scalar:ty),*) => {
+        $(
+            impl ShaderType for $scalar {
+                const ALIGN: u64 = 4;
+                const SIZE: u64 = 4;
+            }
+
+            impl ShaderType for [$scalar; 2] {
+                const ALIGN: u64 = 8;
+                const SIZE: u64 = 8;
+            }
+
+            impl ShaderType for [$scalar; 3] {
+                const ALIGN: u64 = 16;
+                const SIZE: u64 = 12;
+            }
+
+            impl ShaderType for [$scalar; 4] {
+                const ALIGN: u64 = 16;
+                const SIZE: u64 = 16;
+            }
+        )*
+    };
+}
+
+impl_shader_scalar!(f32, u32, i32);
+
+// Matrices are stored as arrays of column vectors, so a mat3x3 takes up 48
+// bytes with each column padded to 16.
+macro_rules! impl_shader_matrix {
+    ($($columns:literal x $rows:literal),*) => {
+        $(
+            impl ShaderType for [[f32; $rows]; $columns] {
+                const ALIGN: u64 = <[f32; $rows]>::ALIGN;
+                const SIZE: u64 = $columns * super::buffer::align_to(<[f32; $rows]>::SIZE, <[f32; $rows]>::ALIGN);
+            }
+        )*
+    };
+}
+
+impl_shader_matrix!(2 x 2, 2 x 3, 2 x 4, 3 x 2, 3 x 3, 3 x 4, 4 x 2, 4 x 3, 4 x 4);
@@ -0,0 +1,282 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+use super::{PreprocessedShader, ShaderError, ShaderLoader};
+
+pub const DEFAULT_SHADER_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+// Watches the files on disk a shader was built from, including everything it
+// pulled in with #include. Modification times are polled since there is no
+// file system notification available.
+pub struct ShaderWatcher {
+    name: String,
+    files: Vec<(PathBuf, Option<SystemTime>)>,
+    poll_interval: Duration,
+    last_poll: Option<Instant>,
+}
+
+impl ShaderWatcher {
+    pub fn new(name: &str, shader: &PreprocessedShader) -> Self {
+        Self {
+            name: name.to_string(),
+            files: watch_files(shader),
+            poll_interval: DEFAULT_SHADER_POLL_INTERVAL,
+            last_poll: None,
+        }
+    }
+
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn files(&self) -> impl Iterator<Item = &Path> {
+        self.files.iter().map(|(path, _)| path.as_path())
+    }
+
+    // Returns true when a watched file changed since the last poll. Polls
+    // closer together than the poll interval are skipped.
+    pub fn poll(&mut self) -> bool {
+        let now = Instant::now();
+        if self.last_poll.map_or(false, |last_poll| now.duration_since(last_poll) < self.poll_interval) {
+            return false;
+        }
+        self.last_poll = Some(now);
+
+        let mut changed = false;
+        for (path, modified) in self.files.iter_mut() {
+            let current = modified_time(path);
+            if current != *modified {
+                *modified = current;
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    // Preprocesses and validates the shader again. The watched files are
+    // updated either way, so a broken shader is retried once it is saved again.
+    // When preprocessing fails halfway, the files read up to the error are
+    // watched in addition to the previous ones.
+    pub fn reload(&mut self, loader: &ShaderLoader) -> Result<PreprocessedShader, ShaderError> {
+        let mut shader = PreprocessedShader::default();
+        if let Err(error) = loader.preprocess_into(&self.name, &mut shader) {
+            for (path, modified) in watch_files(&shader) {
+                if !self.files.iter().any(|(watched_path, _)| *watched_path == path) {
+                    self.files.push((path, modified));
+                }
+            }
+            return Err(error);
+        }
+
+        self.files = watch_files(&shader);
+        shader.parse()?;
+        Ok(shader)
+    }
+}
+
+fn watch_files(shader: &PreprocessedShader) -> Vec<(PathBuf, Option<SystemTime>)> {
+    shader
+        .paths
+        .iter()
+        .map(|path| (path.clone(), modified_time(path)))
+        .collect()
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+type BuildPipeline = Box<dyn Fn(&wgpu::Device, &wgpu::ShaderModule) -> wgpu::RenderPipeline>;
+
+// A render pipeline that is rebuilt whenever its shader changes on disk. When
+// the new shader fails to compile, or the pipeline built from it fails
+// validation, the error is logged and the previous pipeline stays in use.
+pub struct HotReloadPipeline {
+    watcher: ShaderWatcher,
+    build: BuildPipeline,
+    pipeline: wgpu::RenderPipeline,
+}
+
+impl HotReloadPipeline {
+    pub fn new<F>(device: &wgpu::Device, loader: &ShaderLoader, name: &str, build: F) -> Result<Self, ShaderError>
+    where
+        F: Fn(&wgpu::Device, &wgpu::ShaderModule) -> wgpu::RenderPipeline + 'static,
+    {
+        let shader = loader.preprocess(name)?;
+        shader.parse()?;
+
+        let watcher = ShaderWatcher::new(name, &shader);
+        let shader_module = shader.create_shader_module(device, name);
+        let pipeline = build(device, &shader_module);
+
+        Ok(Self {
+            watcher,
+            build: Box::new(build),
+            pipeline,
+        })
+    }
+
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.watcher = self.watcher.with_poll_interval(poll_interval);
+        self
+    }
+
+    pub fn pipeline(&self) -> &wgpu::RenderPipeline {
+        &self.pipeline
+    }
+
+    pub fn watcher(&self) -> &ShaderWatcher {
+        &self.watcher
+    }
+
+    // Returns true when the pipeline was rebuilt.
+    pub fn update(&mut self, device: &wgpu::Device, loader: &ShaderLoader) -> bool {
+        if !self.watcher.poll() {
+            return false;
+        }
+
+        match self.watcher.reload(loader) {
+            Ok(shader) => {
+                let build = &self.build;
+                let name = self.watcher.name();
+                let rebuilt = catch_validation_errors(device, || {
+                    let shader_module = shader.create_shader_module(device, name);
+                    build(device, &shader_module)
+                });
+
+                match rebuilt {
+                    Ok(pipeline) => {
+                        self.pipeline = pipeline;
+                        log::info!("reloaded shader {}", self.watcher.name());
+                        true
+                    },
+                    Err(error) => {
+                        log::error!("failed to rebuild the pipeline of shader {}: {}", self.watcher.name(), error);
+                        false
+                    },
+                }
+            },
+            Err(error) => {
+                log::error!("failed to reload shader {}: {}", self.watcher.name(), error);
+                false
+            },
+        }
+    }
+}
+
+// wgpu 0.9 has no error scopes, so validation errors raised while `build`
+// runs are caught by a temporary uncaptured error handler. Afterwards errors
+// are logged and fatal again like with wgpu's default handler, which also
+// replaces a handler set with `Device::on_uncaptured_error`.
+fn catch_validation_errors<T>(device: &wgpu::Device, build: impl FnOnce() -> T) -> Result<T, wgpu::Error> {
+    let caught = Arc::new(Mutex::new(None));
+    let handler_caught = Arc::clone(&caught);
+    device.on_uncaptured_error(move |error| match error {
+        wgpu::Error::ValidationError { .. } => {
+            handler_caught.lock().unwrap().get_or_insert(error);
+        },
+        _ => fatal_error(error),
+    });
+
+    let value = build();
+    device.on_uncaptured_error(fatal_error);
+
+    let error = caught.lock().unwrap().take();
+    match error {
+        Some(error) => Err(error),
+        None => Ok(value),
+    }
+}
+
+fn fatal_error(error: wgpu::Error) {
+    log::error!("wgpu error: {}", error);
+    panic!("Handling wgpu errors as fatal by default");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::{GpuConfiguration, GpuContext};
+
+    #[test]
+    fn includes_read_before_a_preprocess_error_are_watched() {
+        let directory = std::env::temp_dir().join(format!("webgpu-shader-watcher-test-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let main = directory.join("main.wgsl");
+        let added = directory.join("added.wgsl");
+        fs::write(&main, "fn main() {}\n").unwrap();
+        fs::write(&added, "#ifdef BROKEN\n").unwrap();
+
+        let loader = ShaderLoader::new();
+        let mut watcher = ShaderWatcher::new(main.to_str().unwrap(), &loader.preprocess(main.to_str().unwrap()).unwrap());
+        fs::write(&main, "#include \"added.wgsl\"\nfn main() {}\n").unwrap();
+        let result = watcher.reload(&loader);
+        let files: Vec<PathBuf> = watcher.files().map(Path::to_path_buf).collect();
+        fs::remove_dir_all(&directory).unwrap();
+
+        assert!(result.is_err());
+        assert_eq!(files, vec![main, added]);
+    }
+
+    fn build_pipeline(device: &wgpu::Device, shader_module: &wgpu::ShaderModule) -> wgpu::RenderPipeline {
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: None,
+            layout: None,
+            vertex: wgpu::VertexState {
+                module: shader_module,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            fragment: None,
+        })
+    }
+
+    #[test]
+    fn failed_reload_keeps_the_previous_pipeline() {
+        let context = match async_std::task::block_on(GpuContext::new(&GpuConfiguration::default())) {
+            Ok(context) => context,
+            Err(error) => {
+                eprintln!("skipping, no device available: {}", error);
+                return;
+            },
+        };
+
+        let directory = std::env::temp_dir().join(format!("webgpu-hot-reload-test-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("shader.wgsl");
+        let shader = |entry_point: &str| format!(
+            "[[stage(vertex)]]\nfn {}() -> [[builtin(position)]] vec4<f32> {{\n    return vec4<f32>(0.0, 0.0, 0.0, 1.0);\n}}\n",
+            entry_point,
+        );
+        fs::write(&path, shader("vs_main")).unwrap();
+
+        let loader = ShaderLoader::new();
+        let name = path.to_str().unwrap();
+        let mut pipeline = HotReloadPipeline::new(&context.device, &loader, name, build_pipeline)
+            .unwrap()
+            .with_poll_interval(Duration::from_secs(0));
+
+        // Valid WGSL, but without the entry point the pipeline is built with.
+        std::thread::sleep(Duration::from_millis(20));
+        fs::write(&path, shader("renamed")).unwrap();
+        let broken = pipeline.update(&context.device, &loader);
+
+        std::thread::sleep(Duration::from_millis(20));
+        fs::write(&path, shader("vs_main")).unwrap();
+        let fixed = pipeline.update(&context.device, &loader);
+        fs::remove_dir_all(&directory).unwrap();
+
+        assert!(!broken);
+        assert!(fixed);
+    }
+}
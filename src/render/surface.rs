@@ -0,0 +1,199 @@
+use std::error::Error;
+use std::fmt;
+
+use winit::dpi::PhysicalSize;
+
+pub const DEFAULT_DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SurfaceError {
+    Incompatible,
+    OutOfMemory,
+}
+
+impl fmt::Display for SurfaceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SurfaceError::Incompatible => write!(f, "the adapter cannot present to this surface"),
+            SurfaceError::OutOfMemory => write!(f, "out of memory while acquiring the next frame"),
+        }
+    }
+}
+
+impl Error for SurfaceError { }
+
+struct DepthBuffer {
+    descriptor: wgpu::TextureDescriptor<'static>,
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+}
+
+impl DepthBuffer {
+    fn new(device: &wgpu::Device, format: wgpu::TextureFormat, size: PhysicalSize<u32>) -> Self {
+        let descriptor = wgpu::TextureDescriptor {
+            label: Some("depth texture"),
+            size: wgpu::Extent3d {
+                width: size.width,
+                height: size.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsage::RENDER_ATTACHMENT | wgpu::TextureUsage::SAMPLED,
+        };
+        let texture = device.create_texture(&descriptor);
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        Self {
+            descriptor,
+            texture,
+            view,
+        }
+    }
+
+    fn resize(&mut self, device: &wgpu::Device, size: PhysicalSize<u32>) {
+        *self = Self::new(device, self.descriptor.format, size);
+    }
+}
+
+// Owns the swap chain and an optional depth buffer of the same size. While
+// the window is minimized (zero sized) there is no swap chain and frames are
+// skipped.
+pub struct Surface {
+    surface: wgpu::Surface,
+    swap_chain_descriptor: wgpu::SwapChainDescriptor,
+    swap_chain: Option<wgpu::SwapChain>,
+    depth_buffer: Option<DepthBuffer>,
+}
+
+impl Surface {
+    pub fn new(adapter: &wgpu::Adapter, device: &wgpu::Device, surface: wgpu::Surface, size: PhysicalSize<u32>) -> Result<Self, SurfaceError> {
+        let format = adapter
+            .get_swap_chain_preferred_format(&surface)
+            .ok_or(SurfaceError::Incompatible)?;
+
+        let swap_chain_descriptor = wgpu::SwapChainDescriptor {
+            usage: wgpu::TextureUsage::RENDER_ATTACHMENT,
+            format,
+            width: size.width,
+            height: size.height,
+            present_mode: wgpu::PresentMode::Fifo,
+        };
+
+        let mut surface = Self {
+            surface,
+            swap_chain_descriptor,
+            swap_chain: None,
+            depth_buffer: None,
+        };
+        surface.recreate_swap_chain(device);
+
+        Ok(surface)
+    }
+
+    pub fn with_depth_buffer(mut self, device: &wgpu::Device, format: wgpu::TextureFormat) -> Self {
+        self.depth_buffer = Some(DepthBuffer::new(device, format, self.size_or_one()));
+        self
+    }
+
+    pub fn with_present_mode(mut self, device: &wgpu::Device, present_mode: wgpu::PresentMode) -> Self {
+        self.swap_chain_descriptor.present_mode = present_mode;
+        self.recreate_swap_chain(device);
+        self
+    }
+
+    pub fn format(&self) -> wgpu::TextureFormat {
+        self.swap_chain_descriptor.format
+    }
+
+    pub fn depth_format(&self) -> Option<wgpu::TextureFormat> {
+        self.depth_buffer.as_ref().map(|depth_buffer| depth_buffer.descriptor.format)
+    }
+
+    pub fn size(&self) -> PhysicalSize<u32> {
+        PhysicalSize::new(self.swap_chain_descriptor.width, self.swap_chain_descriptor.height)
+    }
+
+    pub fn aspect_ratio(&self) -> f32 {
+        let size = self.size_or_one();
+        size.width as f32 / size.height as f32
+    }
+
+    pub fn is_minimized(&self) -> bool {
+        self.swap_chain_descriptor.width == 0 || self.swap_chain_descriptor.height == 0
+    }
+
+    pub fn depth_view(&self) -> Option<&wgpu::TextureView> {
+        self.depth_buffer.as_ref().map(|depth_buffer| &depth_buffer.view)
+    }
+
+    pub fn depth_texture(&self) -> Option<&wgpu::Texture> {
+        self.depth_buffer.as_ref().map(|depth_buffer| &depth_buffer.texture)
+    }
+
+    pub fn resize(&mut self, device: &wgpu::Device, size: PhysicalSize<u32>) {
+        if size == self.size() && self.swap_chain.is_some() {
+            return;
+        }
+
+        self.swap_chain_descriptor.width = size.width;
+        self.swap_chain_descriptor.height = size.height;
+        self.recreate_swap_chain(device);
+
+        if self.is_minimized() {
+            return;
+        }
+
+        if let Some(depth_buffer) = self.depth_buffer.as_mut() {
+            depth_buffer.resize(device, size);
+        }
+    }
+
+    // Returns `Ok(None)` when there is nothing to render to this frame, either
+    // because the window is minimized or the swap chain timed out.
+    pub fn acquire_frame(&mut self, device: &wgpu::Device) -> Result<Option<wgpu::SwapChainFrame>, SurfaceError> {
+        let swap_chain = match self.swap_chain.as_ref() {
+            Some(swap_chain) => swap_chain,
+            None => return Ok(None),
+        };
+
+        match swap_chain.get_current_frame() {
+            Ok(frame) => Ok(Some(frame)),
+            Err(wgpu::SwapChainError::Lost) | Err(wgpu::SwapChainError::Outdated) => {
+                self.recreate_swap_chain(device);
+                self.retry_acquire_frame()
+            },
+            Err(wgpu::SwapChainError::Timeout) => Ok(None),
+            Err(wgpu::SwapChainError::OutOfMemory) => Err(SurfaceError::OutOfMemory),
+        }
+    }
+
+    fn retry_acquire_frame(&self) -> Result<Option<wgpu::SwapChainFrame>, SurfaceError> {
+        let swap_chain = match self.swap_chain.as_ref() {
+            Some(swap_chain) => swap_chain,
+            None => return Ok(None),
+        };
+
+        match swap_chain.get_current_frame() {
+            Ok(frame) => Ok(Some(frame)),
+            Err(wgpu::SwapChainError::OutOfMemory) => Err(SurfaceError::OutOfMemory),
+            Err(_) => Ok(None),
+        }
+    }
+
+    fn recreate_swap_chain(&mut self, device: &wgpu::Device) {
+        self.swap_chain = None;
+        if self.is_minimized() {
+            return;
+        }
+
+        self.swap_chain = Some(device.create_swap_chain(&self.surface, &self.swap_chain_descriptor));
+    }
+
+    fn size_or_one(&self) -> PhysicalSize<u32> {
+        let size = self.size();
+        PhysicalSize::new(size.width.max(1), size.height.max(1))
+    }
+}
@@ -0,0 +1,23 @@
+pub use webgpu_derive::VertexLayout;
+
+pub trait VertexLayout: Sized {
+    const STEP_MODE: wgpu::InputStepMode;
+    const ATTRIBUTES: &'static [wgpu::VertexAttribute];
+
+    fn buffer_layout<'a>() -> wgpu::VertexBufferLayout<'a> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: Self::STEP_MODE,
+            attributes: Self::ATTRIBUTES,
+        }
+    }
+
+    // First shader location after the attributes of this layout.
+    fn next_location() -> u32 {
+        Self::ATTRIBUTES
+            .iter()
+            .map(|attribute| attribute.shader_location + 1)
+            .max()
+            .unwrap_or(0)
+    }
+}
@@ -1,10 +0,0 @@
-use cgmath::Matrix4;
-
-pub fn opengl_to_wgpu_conversion_matrix() -> Matrix4<f32> {
-    cgmath::Matrix4::new(
-        1.0, 0.0, 0.0, 0.0,
-        0.0, 1.0, 0.0, 0.0,
-        0.0, 0.0, 0.5, 0.0,
-        0.0, 0.0, 0.5, 1.0,
-    )
-}
\ No newline at end of file
@@ -0,0 +1,275 @@
+use cgmath::{ElementWise, EuclideanSpace, InnerSpace, Matrix3, Matrix4, Point3, Quaternion, Rotation, Vector3};
+
+use super::Transform;
+
+fn abs(vector: Vector3<f32>) -> Vector3<f32> {
+    Vector3::new(vector.x.abs(), vector.y.abs(), vector.z.abs())
+}
+
+// Axis aligned bounding box.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: Point3<f32>,
+    pub max: Point3<f32>,
+}
+
+impl Aabb {
+    pub fn new(min: Point3<f32>, max: Point3<f32>) -> Self {
+        Self {
+            min,
+            max,
+        }
+    }
+
+    pub fn from_center_half_extents(center: Point3<f32>, half_extents: Vector3<f32>) -> Self {
+        Self::new(center - half_extents, center + half_extents)
+    }
+
+    // Returns `None` without any points.
+    pub fn from_points<I: IntoIterator<Item = Point3<f32>>>(points: I) -> Option<Self> {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+        Some(points.fold(Self::new(first, first), |aabb, point| aabb.expanded_to(point)))
+    }
+
+    pub fn center(&self) -> Point3<f32> {
+        self.min.midpoint(self.max)
+    }
+
+    pub fn size(&self) -> Vector3<f32> {
+        self.max - self.min
+    }
+
+    pub fn half_extents(&self) -> Vector3<f32> {
+        self.size() / 2.0
+    }
+
+    pub fn corners(&self) -> [Point3<f32>; 8] {
+        let (min, max) = (self.min, self.max);
+        [
+            Point3::new(min.x, min.y, min.z),
+            Point3::new(max.x, min.y, min.z),
+            Point3::new(min.x, max.y, min.z),
+            Point3::new(max.x, max.y, min.z),
+            Point3::new(min.x, min.y, max.z),
+            Point3::new(max.x, min.y, max.z),
+            Point3::new(min.x, max.y, max.z),
+            Point3::new(max.x, max.y, max.z),
+        ]
+    }
+
+    pub fn contains_point(&self, point: Point3<f32>) -> bool {
+        point.x >= self.min.x && point.x <= self.max.x
+            && point.y >= self.min.y && point.y <= self.max.y
+            && point.z >= self.min.z && point.z <= self.max.z
+    }
+
+    pub fn intersects(&self, other: &Aabb) -> bool {
+        self.min.x <= other.max.x && self.max.x >= other.min.x
+            && self.min.y <= other.max.y && self.max.y >= other.min.y
+            && self.min.z <= other.max.z && self.max.z >= other.min.z
+    }
+
+    pub fn closest_point(&self, point: Point3<f32>) -> Point3<f32> {
+        Point3::new(
+            point.x.clamp(self.min.x, self.max.x),
+            point.y.clamp(self.min.y, self.max.y),
+            point.z.clamp(self.min.z, self.max.z),
+        )
+    }
+
+    pub fn expanded_to(&self, point: Point3<f32>) -> Self {
+        Self::new(
+            Point3::new(self.min.x.min(point.x), self.min.y.min(point.y), self.min.z.min(point.z)),
+            Point3::new(self.max.x.max(point.x), self.max.y.max(point.y), self.max.z.max(point.z)),
+        )
+    }
+
+    pub fn union(&self, other: &Aabb) -> Self {
+        self.expanded_to(other.min).expanded_to(other.max)
+    }
+
+    // The box around this box after transforming it, which is larger than
+    // the box itself whenever the matrix rotates.
+    pub fn transformed(&self, matrix: &Matrix4<f32>) -> Self {
+        let center = Point3::from_homogeneous(matrix * self.center().to_homogeneous());
+        let half_extents = self.half_extents();
+        let linear = Matrix3::from_cols(matrix.x.truncate(), matrix.y.truncate(), matrix.z.truncate());
+        let extents = abs(linear.x) * half_extents.x + abs(linear.y) * half_extents.y + abs(linear.z) * half_extents.z;
+        Self::from_center_half_extents(center, extents)
+    }
+}
+
+// Oriented bounding box.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Obb {
+    pub center: Point3<f32>,
+    pub half_extents: Vector3<f32>,
+    pub rotation: Quaternion<f32>,
+}
+
+impl Obb {
+    pub fn new(center: Point3<f32>, half_extents: Vector3<f32>, rotation: Quaternion<f32>) -> Self {
+        Self {
+            center,
+            half_extents,
+            rotation,
+        }
+    }
+
+    pub fn from_aabb(aabb: &Aabb, transform: &Transform) -> Self {
+        Self::new(
+            transform.transform_point(aabb.center()),
+            aabb.half_extents().mul_element_wise(abs(transform.scale())),
+            transform.rotation(),
+        )
+    }
+
+    pub fn axes(&self) -> [Vector3<f32>; 3] {
+        [
+            self.rotation.rotate_vector(Vector3::unit_x()),
+            self.rotation.rotate_vector(Vector3::unit_y()),
+            self.rotation.rotate_vector(Vector3::unit_z()),
+        ]
+    }
+
+    pub fn corners(&self) -> [Point3<f32>; 8] {
+        let [x, y, z] = self.axes();
+        let (x, y, z) = (x * self.half_extents.x, y * self.half_extents.y, z * self.half_extents.z);
+        let center = self.center;
+        [
+            center - x - y - z,
+            center + x - y - z,
+            center - x + y - z,
+            center + x + y - z,
+            center - x - y + z,
+            center + x - y + z,
+            center - x + y + z,
+            center + x + y + z,
+        ]
+    }
+
+    pub fn contains_point(&self, point: Point3<f32>) -> bool {
+        let local = self.rotation.invert().rotate_vector(point - self.center);
+        local.x.abs() <= self.half_extents.x && local.y.abs() <= self.half_extents.y && local.z.abs() <= self.half_extents.z
+    }
+
+    pub fn to_aabb(&self) -> Aabb {
+        let [x, y, z] = self.axes();
+        let extents = abs(x) * self.half_extents.x + abs(y) * self.half_extents.y + abs(z) * self.half_extents.z;
+        Aabb::from_center_half_extents(self.center, extents)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sphere {
+    pub center: Point3<f32>,
+    pub radius: f32,
+}
+
+impl Sphere {
+    pub fn new(center: Point3<f32>, radius: f32) -> Self {
+        Self {
+            center,
+            radius,
+        }
+    }
+
+    // Centered on the bounding box of the points, so not the smallest
+    // sphere but close to it for most meshes.
+    pub fn from_points<I: IntoIterator<Item = Point3<f32>> + Clone>(points: I) -> Option<Self> {
+        let center = Aabb::from_points(points.clone())?.center();
+        let radius = points
+            .into_iter()
+            .map(|point| (point - center).magnitude2())
+            .fold(0.0, f32::max)
+            .sqrt();
+        Some(Self::new(center, radius))
+    }
+
+    pub fn from_aabb(aabb: &Aabb) -> Self {
+        Self::new(aabb.center(), aabb.half_extents().magnitude())
+    }
+
+    pub fn contains_point(&self, point: Point3<f32>) -> bool {
+        (point - self.center).magnitude2() <= self.radius * self.radius
+    }
+
+    pub fn intersects(&self, other: &Sphere) -> bool {
+        let radius = self.radius + other.radius;
+        (other.center - self.center).magnitude2() <= radius * radius
+    }
+
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        self.contains_point(aabb.closest_point(self.center))
+    }
+
+    // Scaled by the largest scale factor so the sphere still encloses
+    // everything after a non-uniform scale.
+    pub fn transformed(&self, transform: &Transform) -> Self {
+        let scale = abs(transform.scale());
+        Self::new(transform.transform_point(self.center), self.radius * scale.x.max(scale.y).max(scale.z))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::{Deg, Quaternion, Rotation3};
+
+    use super::*;
+
+    fn unit_cube() -> Aabb {
+        Aabb::new(Point3::new(-1.0, -1.0, -1.0), Point3::new(1.0, 1.0, 1.0))
+    }
+
+    #[test]
+    fn rotated_boxes_grow_to_enclose_their_corners() {
+        let matrix = Matrix4::from_translation(Vector3::new(5.0, 0.0, 0.0)) * Matrix4::from_angle_y(Deg(45.0));
+        let transformed = unit_cube().transformed(&matrix);
+
+        let sqrt_2 = 2.0f32.sqrt();
+        assert!((transformed.min - Point3::new(5.0 - sqrt_2, -1.0, -sqrt_2)).magnitude() < 1e-5, "{:?}", transformed);
+        assert!((transformed.max - Point3::new(5.0 + sqrt_2, 1.0, sqrt_2)).magnitude() < 1e-5, "{:?}", transformed);
+
+        // Rotating about a single axis keeps corners on the new box.
+        let corners = Aabb::from_points(unit_cube().corners().iter().map(|corner| Point3::from_homogeneous(matrix * corner.to_homogeneous()))).unwrap();
+        assert!((corners.min - transformed.min).magnitude() < 1e-5);
+        assert!((corners.max - transformed.max).magnitude() < 1e-5);
+    }
+
+    #[test]
+    fn oriented_boxes_match_the_transformed_box() {
+        let transform = Transform::identity()
+            .with_translation(Vector3::new(0.0, 2.0, 0.0))
+            .with_rotation(Quaternion::from_angle_y(Deg(45.0)));
+        let obb = Obb::from_aabb(&unit_cube(), &transform);
+
+        let aabb = obb.to_aabb();
+        let transformed = unit_cube().transformed(&transform.matrix());
+        assert!((aabb.min - transformed.min).magnitude() < 1e-5);
+        assert!((aabb.max - transformed.max).magnitude() < 1e-5);
+        assert!(obb.contains_point(Point3::new(1.3, 2.0, 0.0)));
+        assert!(!obb.contains_point(Point3::new(1.0, 2.0, 1.0)));
+    }
+
+    #[test]
+    fn bounds_from_points() {
+        assert_eq!(Aabb::from_points(vec![]), None);
+
+        let points = vec![Point3::new(1.0, 0.0, 0.0), Point3::new(-1.0, 2.0, 0.0), Point3::new(0.0, 0.0, -3.0)];
+        let aabb = Aabb::from_points(points.clone()).unwrap();
+        assert_eq!(aabb, Aabb::new(Point3::new(-1.0, 0.0, -3.0), Point3::new(1.0, 2.0, 0.0)));
+
+        let sphere = Sphere::from_points(points.clone()).unwrap();
+        assert_eq!(sphere.center, aabb.center());
+        assert!(points.into_iter().all(|point| sphere.contains_point(point)));
+    }
+
+    #[test]
+    fn spheres_intersect_boxes_through_the_closest_point() {
+        let sphere = Sphere::new(Point3::new(1.9, 1.9, 0.0), 1.2);
+        assert!(!sphere.intersects_aabb(&unit_cube()));
+        assert!(sphere.intersects(&Sphere::from_aabb(&unit_cube())));
+        assert!(Sphere::new(Point3::new(2.0, 0.0, 0.0), 1.0).intersects_aabb(&unit_cube()));
+    }
+}
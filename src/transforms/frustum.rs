@@ -0,0 +1,156 @@
+use cgmath::{Matrix, Matrix4, Point3};
+
+use super::{Aabb, Plane, Sphere};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Intersection {
+    Outside,
+    Intersecting,
+    Inside,
+}
+
+// The planes bounding everything a view-projection matrix can see, with
+// their normals pointing inwards.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frustum {
+    pub planes: Vec<Plane>,
+}
+
+impl Frustum {
+    // Expects wgpu clip space, with depth from 0 to 1. Planes that do not
+    // exist, like the far plane of an infinite projection, are left out.
+    pub fn from_view_projection(view_projection: &Matrix4<f32>) -> Self {
+        let x = view_projection.row(0);
+        let y = view_projection.row(1);
+        let z = view_projection.row(2);
+        let w = view_projection.row(3);
+
+        let planes = [w + x, w - x, w + y, w - y, z, w - z]
+            .iter()
+            .filter_map(|coefficients| Plane::from_coefficients(*coefficients))
+            .collect();
+
+        Self {
+            planes,
+        }
+    }
+
+    pub fn contains_point(&self, point: Point3<f32>) -> bool {
+        self.planes.iter().all(|plane| plane.signed_distance(point) >= 0.0)
+    }
+
+    pub fn classify_sphere(&self, sphere: &Sphere) -> Intersection {
+        let mut intersection = Intersection::Inside;
+        for plane in &self.planes {
+            let distance = plane.signed_distance(sphere.center);
+            if distance < -sphere.radius {
+                return Intersection::Outside;
+            }
+            if distance < sphere.radius {
+                intersection = Intersection::Intersecting;
+            }
+        }
+        intersection
+    }
+
+    // Tests the corner furthest along each plane normal, which may keep
+    // boxes near the edges of the frustum that are outside of it.
+    pub fn classify_aabb(&self, aabb: &Aabb) -> Intersection {
+        let mut intersection = Intersection::Inside;
+        for plane in &self.planes {
+            let select = |positive: bool, min: f32, max: f32| if positive { max } else { min };
+            let furthest = Point3::new(
+                select(plane.normal.x >= 0.0, aabb.min.x, aabb.max.x),
+                select(plane.normal.y >= 0.0, aabb.min.y, aabb.max.y),
+                select(plane.normal.z >= 0.0, aabb.min.z, aabb.max.z),
+            );
+            let nearest = Point3::new(
+                select(plane.normal.x >= 0.0, aabb.max.x, aabb.min.x),
+                select(plane.normal.y >= 0.0, aabb.max.y, aabb.min.y),
+                select(plane.normal.z >= 0.0, aabb.max.z, aabb.min.z),
+            );
+
+            if plane.signed_distance(furthest) < 0.0 {
+                return Intersection::Outside;
+            }
+            if plane.signed_distance(nearest) < 0.0 {
+                intersection = Intersection::Intersecting;
+            }
+        }
+        intersection
+    }
+
+    pub fn intersects_sphere(&self, sphere: &Sphere) -> bool {
+        self.classify_sphere(sphere) != Intersection::Outside
+    }
+
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        self.classify_aabb(aabb) != Intersection::Outside
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::{Deg, Vector3};
+
+    use super::*;
+    use crate::camera::Camera;
+
+    fn frustum(camera: Camera) -> Frustum {
+        Frustum::from_view_projection(&camera.view_projection_matrix())
+    }
+
+    fn box_at(z: f32) -> Aabb {
+        Aabb::from_center_half_extents(Point3::new(0.0, 0.0, z), Vector3::new(1.0, 1.0, 1.0))
+    }
+
+    // Cameras look down -z, so the far plane is the one facing +z.
+    fn has_far_plane(frustum: &Frustum) -> bool {
+        frustum.planes.iter().any(|plane| plane.normal.z > 0.99)
+    }
+
+    #[test]
+    fn infinite_projections_have_no_far_plane() {
+        for reverse_z in [false, true].iter() {
+            let frustum = frustum(Camera::perspective(Deg(60.0), 1.0, 0.1, None).with_reverse_z(*reverse_z));
+            assert_eq!(frustum.planes.len(), 5, "reverse_z: {}", reverse_z);
+            assert!(!has_far_plane(&frustum), "reverse_z: {}", reverse_z);
+            assert_eq!(frustum.classify_aabb(&box_at(-1.0e6)), Intersection::Inside);
+            assert_eq!(frustum.classify_aabb(&box_at(1.0)), Intersection::Outside);
+        }
+    }
+
+    #[test]
+    fn finite_projections_keep_the_far_plane() {
+        for reverse_z in [false, true].iter() {
+            let frustum = frustum(Camera::perspective(Deg(60.0), 1.0, 0.1, Some(100.0)).with_reverse_z(*reverse_z));
+            assert_eq!(frustum.planes.len(), 6, "reverse_z: {}", reverse_z);
+            assert!(has_far_plane(&frustum), "reverse_z: {}", reverse_z);
+            assert_eq!(frustum.classify_aabb(&box_at(-50.0)), Intersection::Inside);
+            assert_eq!(frustum.classify_aabb(&box_at(-100.0)), Intersection::Intersecting);
+            assert_eq!(frustum.classify_aabb(&box_at(-102.0)), Intersection::Outside);
+        }
+    }
+
+    #[test]
+    fn boxes_are_classified_against_every_plane() {
+        let frustum = frustum(Camera::orthographic(5.0, 5.0, 0.1, 100.0).with_position(Point3::new(0.0, 0.0, 10.0)));
+
+        assert_eq!(frustum.classify_aabb(&box_at(0.0)), Intersection::Inside);
+        assert_eq!(frustum.classify_aabb(&Aabb::new(Point3::new(-1.0, -1.0, 9.0), Point3::new(1.0, 1.0, 12.0))), Intersection::Intersecting);
+        assert_eq!(frustum.classify_aabb(&Aabb::new(Point3::new(4.0, -1.0, -1.0), Point3::new(6.0, 1.0, 1.0))), Intersection::Intersecting);
+        assert_eq!(frustum.classify_aabb(&Aabb::new(Point3::new(-1.0, -1.0, 11.0), Point3::new(1.0, 1.0, 12.0))), Intersection::Outside);
+        assert_eq!(frustum.classify_aabb(&Aabb::new(Point3::new(6.0, -1.0, -1.0), Point3::new(8.0, 1.0, 1.0))), Intersection::Outside);
+        assert!(frustum.contains_point(Point3::new(4.9, 0.0, 0.0)));
+        assert!(!frustum.contains_point(Point3::new(5.1, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn spheres_are_classified_against_every_plane() {
+        let frustum = frustum(Camera::orthographic(5.0, 5.0, 0.1, 100.0));
+
+        assert_eq!(frustum.classify_sphere(&Sphere::new(Point3::new(0.0, 0.0, -10.0), 1.0)), Intersection::Inside);
+        assert_eq!(frustum.classify_sphere(&Sphere::new(Point3::new(5.0, 0.0, -10.0), 1.0)), Intersection::Intersecting);
+        assert_eq!(frustum.classify_sphere(&Sphere::new(Point3::new(7.0, 0.0, -10.0), 1.0)), Intersection::Outside);
+    }
+}
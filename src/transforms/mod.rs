@@ -0,0 +1,13 @@
+mod bounds;
+mod frustum;
+mod plane;
+mod ray;
+mod transform;
+
+pub use {
+    bounds::*,
+    frustum::*,
+    plane::*,
+    ray::*,
+    transform::*,
+};
\ No newline at end of file
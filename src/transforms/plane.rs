@@ -0,0 +1,72 @@
+use cgmath::{EuclideanSpace, InnerSpace, Point3, Vector3, Vector4};
+
+// Points with `normal.dot(point) + distance == 0`, the normal points to the
+// positive side.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Plane {
+    pub normal: Vector3<f32>,
+    pub distance: f32,
+}
+
+impl Plane {
+    pub fn new(normal: Vector3<f32>, distance: f32) -> Self {
+        Self {
+            normal,
+            distance,
+        }
+    }
+
+    pub fn from_point_normal(point: Point3<f32>, normal: Vector3<f32>) -> Self {
+        let normal = normal.normalize();
+        Self::new(normal, -normal.dot(point.to_vec()))
+    }
+
+    // Counter-clockwise points, seen from the positive side.
+    pub fn from_points(a: Point3<f32>, b: Point3<f32>, c: Point3<f32>) -> Self {
+        Self::from_point_normal(a, (b - a).cross(c - a))
+    }
+
+    // Plane equation coefficients, as extracted from a projection matrix.
+    // Returns `None` when they do not describe a plane.
+    pub fn from_coefficients(coefficients: Vector4<f32>) -> Option<Self> {
+        let normal = coefficients.truncate();
+        let length = normal.magnitude();
+        if length <= f32::EPSILON {
+            return None;
+        }
+        Some(Self::new(normal / length, coefficients.w / length))
+    }
+
+    pub fn signed_distance(&self, point: Point3<f32>) -> f32 {
+        self.normal.dot(point.to_vec()) + self.distance
+    }
+
+    pub fn closest_point(&self, point: Point3<f32>) -> Point3<f32> {
+        point - self.normal * self.signed_distance(point)
+    }
+
+    pub fn flipped(&self) -> Self {
+        Self::new(-self.normal, -self.distance)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counter_clockwise_points_face_the_viewer() {
+        let plane = Plane::from_points(Point3::new(0.0, 1.0, 0.0), Point3::new(1.0, 1.0, 0.0), Point3::new(0.0, 1.0, -1.0));
+        assert_eq!(plane.normal, Vector3::unit_y());
+        assert_eq!(plane.signed_distance(Point3::new(3.0, 3.0, 3.0)), 2.0);
+        assert_eq!(plane.closest_point(Point3::new(3.0, 3.0, 3.0)), Point3::new(3.0, 1.0, 3.0));
+        assert_eq!(plane.flipped().signed_distance(Point3::new(3.0, 3.0, 3.0)), -2.0);
+    }
+
+    #[test]
+    fn coefficients_are_normalized() {
+        let plane = Plane::from_coefficients(Vector4::new(0.0, 0.0, 2.0, 4.0)).unwrap();
+        assert_eq!(plane, Plane::new(Vector3::unit_z(), 2.0));
+        assert_eq!(Plane::from_coefficients(Vector4::new(0.0, 0.0, 0.0, 1.0)), None);
+    }
+}
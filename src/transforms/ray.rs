@@ -0,0 +1,212 @@
+use cgmath::{InnerSpace, Matrix4, Point3, Vector2, Vector3, Vector4};
+
+use super::{Aabb, Plane, Sphere};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RayHit {
+    pub distance: f32,
+    // Barycentric coordinates of the hit for the second and third vertex of
+    // a triangle.
+    pub barycentric: Vector2<f32>,
+}
+
+// Distances along a ray are in multiples of its direction, which is
+// normalized when created through `new`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ray {
+    pub origin: Point3<f32>,
+    pub direction: Vector3<f32>,
+}
+
+impl Ray {
+    pub fn new(origin: Point3<f32>, direction: Vector3<f32>) -> Self {
+        Self {
+            origin,
+            direction: direction.normalize(),
+        }
+    }
+
+    // The ray through a point on screen in normalized device coordinates,
+    // from -1 to 1 with y up, for picking. Expects wgpu clip space, with
+    // depth from 0 to 1, or from 1 to 0 with `reverse_z`, like the
+    // projections of `Camera`. The ray starts on the near plane.
+    pub fn from_screen(ndc: Vector2<f32>, inverse_view_projection: &Matrix4<f32>, reverse_z: bool) -> Self {
+        let unproject = |depth: f32| Point3::from_homogeneous(inverse_view_projection * Vector4::new(ndc.x, ndc.y, depth, 1.0));
+        let near = match reverse_z {
+            true => unproject(1.0),
+            false => unproject(0.0),
+        };
+        // Halfway instead of at the far plane, which is infinitely far away
+        // for projections without one.
+        let far = unproject(0.5);
+        Self::new(near, far - near)
+    }
+
+    pub fn at(&self, distance: f32) -> Point3<f32> {
+        self.origin + self.direction * distance
+    }
+
+    // Distance to where the ray enters the box, 0 when it starts inside.
+    pub fn intersect_aabb(&self, aabb: &Aabb) -> Option<f32> {
+        let mut near = 0.0f32;
+        let mut far = f32::INFINITY;
+
+        for axis in 0..3 {
+            let origin = self.origin[axis];
+            let direction = self.direction[axis];
+            let (min, max) = (aabb.min[axis], aabb.max[axis]);
+
+            if direction.abs() <= f32::EPSILON {
+                if origin < min || origin > max {
+                    return None;
+                }
+                continue;
+            }
+
+            let t0 = (min - origin) / direction;
+            let t1 = (max - origin) / direction;
+            near = near.max(t0.min(t1));
+            far = far.min(t0.max(t1));
+            if near > far {
+                return None;
+            }
+        }
+
+        Some(near)
+    }
+
+    // Möller–Trumbore, both sides of the triangle count as a hit.
+    pub fn intersect_triangle(&self, a: Point3<f32>, b: Point3<f32>, c: Point3<f32>) -> Option<RayHit> {
+        let edge_ab = b - a;
+        let edge_ac = c - a;
+        let p = self.direction.cross(edge_ac);
+        let determinant = edge_ab.dot(p);
+        if determinant.abs() <= f32::EPSILON {
+            return None;
+        }
+
+        let inverse_determinant = 1.0 / determinant;
+        let to_origin = self.origin - a;
+        let u = to_origin.dot(p) * inverse_determinant;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+
+        let q = to_origin.cross(edge_ab);
+        let v = self.direction.dot(q) * inverse_determinant;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+
+        let distance = edge_ac.dot(q) * inverse_determinant;
+        if distance < 0.0 {
+            return None;
+        }
+
+        Some(RayHit {
+            distance,
+            barycentric: Vector2::new(u, v),
+        })
+    }
+
+    pub fn intersect_plane(&self, plane: &Plane) -> Option<f32> {
+        let denominator = plane.normal.dot(self.direction);
+        if denominator.abs() <= f32::EPSILON {
+            return None;
+        }
+
+        let distance = -plane.signed_distance(self.origin) / denominator;
+        match distance >= 0.0 {
+            true => Some(distance),
+            false => None,
+        }
+    }
+
+    pub fn intersect_sphere(&self, sphere: &Sphere) -> Option<f32> {
+        let projection = (sphere.center - self.origin).dot(self.direction) / self.direction.magnitude2();
+        let distance2 = (sphere.center - self.at(projection)).magnitude2();
+        let radius2 = sphere.radius * sphere.radius;
+        if distance2 > radius2 {
+            return None;
+        }
+
+        let half_chord = ((radius2 - distance2) / self.direction.magnitude2()).sqrt();
+        match (projection - half_chord, projection + half_chord) {
+            (enter, _) if enter >= 0.0 => Some(enter),
+            (_, exit) if exit >= 0.0 => Some(0.0),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::Deg;
+
+    use super::*;
+    use crate::camera::Camera;
+
+    #[test]
+    fn screen_rays_start_on_the_near_plane() {
+        for &(z_far, reverse_z) in [(Some(100.0), false), (Some(100.0), true), (None, false), (None, true)].iter() {
+            let camera = Camera::perspective(Deg(90.0), 1.0, 0.1, z_far).with_reverse_z(reverse_z);
+            let inverse_view_projection = camera.inverse_view_projection_matrix().unwrap();
+
+            let center = Ray::from_screen(Vector2::new(0.0, 0.0), &inverse_view_projection, reverse_z);
+            assert!((center.origin - Point3::new(0.0, 0.0, -0.1)).magnitude() < 1e-5, "{:?} {:?}", z_far, center);
+            assert!((center.direction + Vector3::unit_z()).magnitude() < 1e-5, "{:?} {:?}", z_far, center);
+
+            // A 90 degree field of view reaches the edge of the screen at 45 degrees.
+            let edge = Ray::from_screen(Vector2::new(1.0, 0.0), &inverse_view_projection, reverse_z);
+            assert!((edge.direction - Vector3::new(1.0, 0.0, -1.0).normalize()).magnitude() < 1e-4, "{:?} {:?}", z_far, edge);
+        }
+    }
+
+    #[test]
+    fn camera_screen_rays_follow_the_camera_depth_range() {
+        let camera = Camera::perspective(Deg(90.0), 1.0, 0.1, None)
+            .with_reverse_z(true)
+            .with_position(Point3::new(0.0, 0.0, 5.0));
+
+        // Depth 0 is at infinity with a reverse-Z infinite projection.
+        let ray = camera.screen_ray(Vector2::new(0.0, 0.0)).unwrap();
+        assert!((ray.origin - Point3::new(0.0, 0.0, 4.9)).magnitude() < 1e-5, "{:?}", ray);
+        assert!((ray.direction + Vector3::unit_z()).magnitude() < 1e-5, "{:?}", ray);
+    }
+
+    #[test]
+    fn triangle_hits_report_barycentric_coordinates() {
+        let (a, b, c) = (Point3::new(0.0, 0.0, 0.0), Point3::new(2.0, 0.0, 0.0), Point3::new(0.0, 2.0, 0.0));
+        let ray = Ray::new(Point3::new(0.5, 1.0, 3.0), -Vector3::unit_z());
+
+        let hit = ray.intersect_triangle(a, b, c).unwrap();
+        assert!((hit.distance - 3.0).abs() < 1e-6);
+        assert!((hit.barycentric - Vector2::new(0.25, 0.5)).magnitude() < 1e-6);
+        let point = a + (b - a) * hit.barycentric.x + (c - a) * hit.barycentric.y;
+        assert!((point - ray.at(hit.distance)).magnitude() < 1e-6);
+
+        // Both sides count, but not behind the origin or outside the edges.
+        assert!(ray.intersect_triangle(a, c, b).is_some());
+        assert_eq!(Ray::new(Point3::new(0.5, 1.0, 3.0), Vector3::unit_z()).intersect_triangle(a, b, c), None);
+        assert_eq!(Ray::new(Point3::new(1.5, 1.5, 3.0), -Vector3::unit_z()).intersect_triangle(a, b, c), None);
+    }
+
+    #[test]
+    fn rays_starting_inside_a_shape_hit_it_at_zero() {
+        let sphere = Sphere::new(Point3::new(0.0, 0.0, 0.0), 1.0);
+        let aabb = Aabb::new(Point3::new(-1.0, -1.0, -1.0), Point3::new(1.0, 1.0, 1.0));
+        let inside = Ray::new(Point3::new(0.0, 0.5, 0.0), Vector3::unit_x());
+
+        assert_eq!(inside.intersect_sphere(&sphere), Some(0.0));
+        assert_eq!(inside.intersect_aabb(&aabb), Some(0.0));
+
+        let outside = Ray::new(Point3::new(0.0, 0.0, 5.0), -Vector3::unit_z());
+        assert!((outside.intersect_sphere(&sphere).unwrap() - 4.0).abs() < 1e-6);
+        assert!((outside.intersect_aabb(&aabb).unwrap() - 4.0).abs() < 1e-6);
+        assert!((outside.intersect_plane(&Plane::from_point_normal(Point3::new(0.0, 0.0, 0.0), Vector3::unit_z())).unwrap() - 5.0).abs() < 1e-6);
+
+        let away = Ray::new(Point3::new(0.0, 0.0, 5.0), Vector3::unit_z());
+        assert_eq!(away.intersect_sphere(&sphere), None);
+        assert_eq!(away.intersect_aabb(&aabb), None);
+    }
+}
@@ -0,0 +1,362 @@
+use std::cell::Cell;
+use std::fmt;
+use std::ops::Mul;
+
+use cgmath::{Deg, ElementWise, EuclideanSpace, InnerSpace, Matrix3, Matrix4, Point3, Quaternion, Rotation, Rotation3, Vector3, VectorSpace, Zero};
+
+pub fn opengl_to_wgpu_conversion_matrix() -> Matrix4<f32> {
+    cgmath::Matrix4::new(
+        1.0, 0.0, 0.0, 0.0,
+        0.0, 1.0, 0.0, 0.0,
+        0.0, 0.0, 0.5, 0.0,
+        0.0, 0.0, 0.5, 1.0,
+    )
+}
+
+fn reciprocal_or_zero(value: f32) -> f32 {
+    if value == 0.0 {
+        return 0.0;
+    }
+    1.0 / value
+}
+
+// Translation, rotation and scale, applied to points in the reverse order:
+// scale first, then rotation, then translation. The model matrix is cached
+// in a `Cell` until one of the three changes, which makes `Transform`
+// `Send` but not `Sync`: share it between threads by cloning it or behind a
+// `Mutex`.
+#[derive(Clone)]
+pub struct Transform {
+    translation: Vector3<f32>,
+    rotation: Quaternion<f32>,
+    scale: Vector3<f32>,
+    matrix: Cell<Option<Matrix4<f32>>>,
+}
+
+impl Transform {
+    pub fn new(translation: Vector3<f32>, rotation: Quaternion<f32>, scale: Vector3<f32>) -> Self {
+        Self {
+            translation,
+            rotation: rotation.normalize(),
+            scale,
+            matrix: Cell::new(None),
+        }
+    }
+
+    pub fn identity() -> Self {
+        Self::new(Vector3::zero(), Quaternion::new(1.0, 0.0, 0.0, 0.0), Vector3::new(1.0, 1.0, 1.0))
+    }
+
+    pub fn from_translation(translation: Vector3<f32>) -> Self {
+        Self::identity().with_translation(translation)
+    }
+
+    // Euler angles in degrees, applied in the order Z, Y and then X.
+    pub fn from_euler_degrees(angles: Vector3<f32>) -> Self {
+        let rotation = Quaternion::from_angle_x(Deg(angles.x)) * Quaternion::from_angle_y(Deg(angles.y)) * Quaternion::from_angle_z(Deg(angles.z));
+        Self::identity().with_rotation(rotation)
+    }
+
+    // Placed at `eye` with its -Z axis pointing at `target`, the same way
+    // cameras and glTF nodes look.
+    pub fn look_at(eye: Point3<f32>, target: Point3<f32>, up: Vector3<f32>) -> Self {
+        let rotation = Quaternion::from(Matrix3::look_to_rh((target - eye).normalize(), up)).invert();
+        Self::new(eye.to_vec(), rotation, Vector3::new(1.0, 1.0, 1.0))
+    }
+
+    pub fn with_translation(mut self, translation: Vector3<f32>) -> Self {
+        self.set_translation(translation);
+        self
+    }
+
+    pub fn with_rotation(mut self, rotation: Quaternion<f32>) -> Self {
+        self.set_rotation(rotation);
+        self
+    }
+
+    pub fn with_scale(mut self, scale: Vector3<f32>) -> Self {
+        self.set_scale(scale);
+        self
+    }
+
+    pub fn with_uniform_scale(self, scale: f32) -> Self {
+        self.with_scale(Vector3::new(scale, scale, scale))
+    }
+
+    pub fn translation(&self) -> Vector3<f32> {
+        self.translation
+    }
+
+    pub fn rotation(&self) -> Quaternion<f32> {
+        self.rotation
+    }
+
+    pub fn scale(&self) -> Vector3<f32> {
+        self.scale
+    }
+
+    pub fn set_translation(&mut self, translation: Vector3<f32>) {
+        self.translation = translation;
+        self.matrix.set(None);
+    }
+
+    pub fn set_rotation(&mut self, rotation: Quaternion<f32>) {
+        self.rotation = rotation.normalize();
+        self.matrix.set(None);
+    }
+
+    pub fn set_scale(&mut self, scale: Vector3<f32>) {
+        self.scale = scale;
+        self.matrix.set(None);
+    }
+
+    pub fn translate(&mut self, offset: Vector3<f32>) {
+        self.set_translation(self.translation + offset);
+    }
+
+    // Rotates on top of the current rotation, in world space.
+    pub fn rotate(&mut self, rotation: Quaternion<f32>) {
+        self.set_rotation(rotation * self.rotation);
+    }
+
+    pub fn forward(&self) -> Vector3<f32> {
+        self.rotation.rotate_vector(-Vector3::unit_z())
+    }
+
+    pub fn right(&self) -> Vector3<f32> {
+        self.rotation.rotate_vector(Vector3::unit_x())
+    }
+
+    pub fn up(&self) -> Vector3<f32> {
+        self.rotation.rotate_vector(Vector3::unit_y())
+    }
+
+    pub fn matrix(&self) -> Matrix4<f32> {
+        if let Some(matrix) = self.matrix.get() {
+            return matrix;
+        }
+
+        let matrix = Matrix4::from_translation(self.translation)
+            * Matrix4::from(self.rotation)
+            * Matrix4::from_nonuniform_scale(self.scale.x, self.scale.y, self.scale.z);
+        self.matrix.set(Some(matrix));
+        matrix
+    }
+
+    // The inverse transpose of the upper 3x3 of the model matrix, which keeps
+    // normals perpendicular to their surface under non-uniform scale. For
+    // rotation times scale that is the rotation times the inverse scale.
+    pub fn normal_matrix(&self) -> Matrix3<f32> {
+        let inverse_scale = Matrix3::new(
+            reciprocal_or_zero(self.scale.x), 0.0, 0.0,
+            0.0, reciprocal_or_zero(self.scale.y), 0.0,
+            0.0, 0.0, reciprocal_or_zero(self.scale.z),
+        );
+        Matrix3::from(self.rotation) * inverse_scale
+    }
+
+    pub fn transform_point(&self, point: Point3<f32>) -> Point3<f32> {
+        Point3::from_vec(self.translation) + self.transform_vector(point.to_vec())
+    }
+
+    pub fn transform_vector(&self, vector: Vector3<f32>) -> Vector3<f32> {
+        self.rotation.rotate_vector(vector.mul_element_wise(self.scale))
+    }
+
+    // The transform of `child` placed inside this one. Exact as long as this
+    // transform has a uniform scale, otherwise the shear a rotated child
+    // would get cannot be represented and is dropped.
+    pub fn compose(&self, child: &Transform) -> Transform {
+        Transform::new(
+            self.transform_point(Point3::from_vec(child.translation)).to_vec(),
+            (self.rotation * child.rotation).normalize(),
+            self.scale.mul_element_wise(child.scale),
+        )
+    }
+
+    // Exact for uniform scales, for the same reason as `compose`.
+    pub fn inverse(&self) -> Transform {
+        let rotation = self.rotation.invert();
+        let scale = Vector3::new(reciprocal_or_zero(self.scale.x), reciprocal_or_zero(self.scale.y), reciprocal_or_zero(self.scale.z));
+        let translation = -rotation.rotate_vector(self.translation.mul_element_wise(scale));
+        Transform::new(translation, rotation, scale)
+    }
+
+    // Linear interpolation of translation and scale, rotations are
+    // normalized lerped along the shortest path.
+    pub fn lerp(&self, other: &Transform, amount: f32) -> Transform {
+        let other_rotation = shortest_path(self.rotation, other.rotation);
+        Transform::new(
+            self.translation.lerp(other.translation, amount),
+            self.rotation.nlerp(other_rotation, amount),
+            self.scale.lerp(other.scale, amount),
+        )
+    }
+
+    // Like `lerp` but with a constant angular speed for the rotation.
+    pub fn slerp(&self, other: &Transform, amount: f32) -> Transform {
+        let other_rotation = shortest_path(self.rotation, other.rotation);
+        Transform::new(
+            self.translation.lerp(other.translation, amount),
+            self.rotation.slerp(other_rotation, amount),
+            self.scale.lerp(other.scale, amount),
+        )
+    }
+}
+
+fn shortest_path(from: Quaternion<f32>, to: Quaternion<f32>) -> Quaternion<f32> {
+    match from.dot(to) < 0.0 {
+        true => -to,
+        false => to,
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl PartialEq for Transform {
+    fn eq(&self, other: &Self) -> bool {
+        self.translation == other.translation && self.rotation == other.rotation && self.scale == other.scale
+    }
+}
+
+impl fmt::Debug for Transform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Transform")
+            .field("translation", &self.translation)
+            .field("rotation", &self.rotation)
+            .field("scale", &self.scale)
+            .finish()
+    }
+}
+
+impl Mul for &Transform {
+    type Output = Transform;
+
+    fn mul(self, child: &Transform) -> Transform {
+        self.compose(child)
+    }
+}
+
+impl From<&Transform> for Matrix4<f32> {
+    fn from(transform: &Transform) -> Self {
+        transform.matrix()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::{Matrix, Rad, SquareMatrix};
+
+    use super::*;
+
+    fn assert_close(a: Vector3<f32>, b: Vector3<f32>) {
+        assert!((a - b).magnitude() < 1e-5, "{:?} != {:?}", a, b);
+    }
+
+    fn assert_same_transform(a: &Transform, b: &Transform) {
+        assert_close(a.translation(), b.translation());
+        assert!((a.rotation().dot(b.rotation()).abs() - 1.0).abs() < 1e-5, "{:?} != {:?}", a.rotation(), b.rotation());
+        assert_close(a.scale(), b.scale());
+    }
+
+    fn rotated_and_scaled() -> Transform {
+        Transform::new(
+            Vector3::new(1.0, -2.0, 3.0),
+            Quaternion::from_axis_angle(Vector3::new(1.0, 1.0, 0.0).normalize(), Deg(40.0)),
+            Vector3::new(2.0, 0.5, 3.0),
+        )
+    }
+
+    #[test]
+    fn normal_matrix_is_the_inverse_transpose_of_the_model_matrix() {
+        let transform = rotated_and_scaled();
+        let model = transform.matrix();
+        let upper = Matrix3::new(
+            model.x.x, model.x.y, model.x.z,
+            model.y.x, model.y.y, model.y.z,
+            model.z.x, model.z.y, model.z.z,
+        );
+        let expected = upper.invert().unwrap().transpose();
+        let normal = transform.normal_matrix();
+
+        assert_close(normal.x, expected.x);
+        assert_close(normal.y, expected.y);
+        assert_close(normal.z, expected.z);
+    }
+
+    #[test]
+    fn inverse_undoes_the_transform() {
+        let transform = rotated_and_scaled().with_uniform_scale(2.5);
+        let inverse = transform.inverse();
+        let point = Point3::new(0.3, -4.0, 7.0);
+
+        assert_close(inverse.transform_point(transform.transform_point(point)).to_vec(), point.to_vec());
+        assert_same_transform(&transform.compose(&inverse), &Transform::identity());
+        assert_same_transform(&inverse.compose(&transform), &Transform::identity());
+    }
+
+    #[test]
+    fn compose_matches_the_matrix_product() {
+        let parent = Transform::from_translation(Vector3::new(0.0, 1.0, 0.0))
+            .with_rotation(Quaternion::from_angle_y(Deg(90.0)))
+            .with_uniform_scale(2.0);
+        let child = rotated_and_scaled();
+        let composed = &parent * &child;
+        let point = Point3::new(1.0, 2.0, 3.0);
+
+        assert_close(composed.transform_point(point).to_vec(), parent.transform_point(child.transform_point(point)).to_vec());
+        let product = parent.matrix() * child.matrix();
+        let matrix = composed.matrix();
+        for column in 0..4 {
+            assert!((matrix[column] - product[column]).magnitude() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn look_at_faces_the_target() {
+        let eye = Point3::new(1.0, 2.0, 3.0);
+        let target = Point3::new(-4.0, 0.0, 1.0);
+        let transform = Transform::look_at(eye, target, Vector3::unit_y());
+
+        assert_close(transform.forward(), (target - eye).normalize());
+        assert!(transform.right().dot(Vector3::unit_y()).abs() < 1e-5);
+        assert!(transform.up().y > 0.0);
+        assert_close(transform.translation(), eye.to_vec());
+    }
+
+    #[test]
+    fn slerp_takes_the_shortest_path() {
+        let from = Transform::identity().with_rotation(Quaternion::from_angle_y(Deg(10.0)));
+        // The same rotation as -10 degrees, but on the other side of the
+        // quaternion sphere.
+        let to = Transform::identity().with_rotation(-Quaternion::from_angle_y(Deg(-10.0)));
+        assert!(from.rotation().dot(to.rotation()) < 0.0);
+
+        let halfway = from.slerp(&to, 0.5);
+        assert_close(halfway.forward(), -Vector3::unit_z());
+        let quarter = from.slerp(&to, 0.25);
+        let angle: Rad<f32> = Deg(5.0).into();
+        assert_close(quarter.forward(), Quaternion::from_angle_y(angle).rotate_vector(-Vector3::unit_z()));
+    }
+
+    #[test]
+    fn setters_invalidate_the_cached_matrix() {
+        let mut transform = Transform::identity();
+        assert_eq!(transform.matrix(), Matrix4::identity());
+        assert!(transform.matrix.get().is_some());
+
+        transform.set_translation(Vector3::new(1.0, 0.0, 0.0));
+        assert!(transform.matrix.get().is_none());
+        assert_eq!(transform.matrix(), Matrix4::from_translation(Vector3::new(1.0, 0.0, 0.0)));
+
+        transform.set_scale(Vector3::new(2.0, 2.0, 2.0));
+        assert_eq!(transform.matrix().x.x, 2.0);
+
+        transform.set_rotation(Quaternion::from_angle_z(Deg(90.0)));
+        assert_close(transform.matrix().x.truncate(), Vector3::new(0.0, 2.0, 0.0));
+    }
+}
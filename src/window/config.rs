@@ -1,8 +1,8 @@
 use std::path::PathBuf;
 use serde::Deserialize;
 
-use crate::config::load_configuration;
-use super::WindowModes;
+use crate::config::{is_not_found, load_configuration};
+use super::{TargetFrameTime, WindowModes};
 
 #[derive(Deserialize)]
 pub struct WindowConfiguration {
@@ -10,14 +10,19 @@ pub struct WindowConfiguration {
     pub width: u32,
     pub height: u32,
     pub window_mode: WindowModes,
-    pub target_frame_time: Option<u64>,
+    pub target_frame_time: Option<TargetFrameTime>,
 }
 
 impl WindowConfiguration {
     pub fn new() -> Self {
         match load_configuration(PathBuf::from("./window.config")) {
             Ok(config) => config,
-            Err(_) => default_window_configuration(),
+            Err(error) => {
+                if !is_not_found(error.as_ref()) {
+                    log::warn!("failed to load window.config, using the default configuration: {}", error);
+                }
+                default_window_configuration()
+            },
         }
     }
 }
@@ -0,0 +1,212 @@
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+
+use super::Clock;
+
+// Sleeping is only accurate to a few milliseconds on most platforms, so the
+// last part of the frame budget is spent spinning instead.
+pub const DEFAULT_SPIN_THRESHOLD: Duration = Duration::from_millis(2);
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(from = "TargetFrameTimeConfiguration")]
+pub enum TargetFrameTime {
+    Milliseconds(f64),
+    Fps(f64),
+}
+
+// Older configurations store the target as a bare number of milliseconds.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TargetFrameTimeConfiguration {
+    Milliseconds(f64),
+    Tagged(TaggedTargetFrameTime),
+}
+
+#[derive(Deserialize)]
+enum TaggedTargetFrameTime {
+    Milliseconds(f64),
+    Fps(f64),
+}
+
+impl From<TargetFrameTimeConfiguration> for TargetFrameTime {
+    fn from(configuration: TargetFrameTimeConfiguration) -> Self {
+        match configuration {
+            TargetFrameTimeConfiguration::Milliseconds(milliseconds) => TargetFrameTime::Milliseconds(milliseconds),
+            TargetFrameTimeConfiguration::Tagged(TaggedTargetFrameTime::Milliseconds(milliseconds)) => TargetFrameTime::Milliseconds(milliseconds),
+            TargetFrameTimeConfiguration::Tagged(TaggedTargetFrameTime::Fps(fps)) => TargetFrameTime::Fps(fps),
+        }
+    }
+}
+
+impl TargetFrameTime {
+    pub fn as_duration(&self) -> Duration {
+        match *self {
+            TargetFrameTime::Milliseconds(milliseconds) => Duration::from_secs_f64(milliseconds.max(0.0) / 1000.0),
+            TargetFrameTime::Fps(fps) if fps > 0.0 => Duration::from_secs_f64(1.0 / fps),
+            TargetFrameTime::Fps(_) => Duration::from_secs(0),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PacingReport {
+    pub paced_frames: u64,
+    pub missed_frames: u64,
+    pub last_error: Duration,
+    pub max_error: Duration,
+    pub average_error: Duration,
+}
+
+pub struct FramePacer {
+    target: Duration,
+    spin_threshold: Duration,
+    total_error: Duration,
+    report: PacingReport,
+}
+
+impl FramePacer {
+    pub fn new(target: TargetFrameTime) -> Self {
+        Self {
+            target: target.as_duration(),
+            spin_threshold: DEFAULT_SPIN_THRESHOLD,
+            total_error: Duration::from_secs(0),
+            report: PacingReport::default(),
+        }
+    }
+
+    pub fn with_spin_threshold(mut self, spin_threshold: Duration) -> Self {
+        self.spin_threshold = spin_threshold;
+        self
+    }
+
+    pub fn target(&self) -> Duration {
+        self.target
+    }
+
+    pub fn set_target(&mut self, target: TargetFrameTime) {
+        self.target = target.as_duration();
+    }
+
+    pub fn report(&self) -> &PacingReport {
+        &self.report
+    }
+
+    pub fn deadline(&self, frame_start: Instant) -> Instant {
+        frame_start + self.target
+    }
+
+    // Blocks until `target` has passed since `frame_start` and returns how far
+    // past the deadline the wait actually ended. Returns `None` when the frame
+    // already took longer than the target.
+    pub fn wait<C: Clock>(&mut self, clock: &C, frame_start: Instant) -> Option<Duration> {
+        let deadline = self.deadline(frame_start);
+
+        let now = clock.now();
+        if now >= deadline {
+            self.report.missed_frames += 1;
+            return None;
+        }
+
+        let remaining = deadline - now;
+        if remaining > self.spin_threshold {
+            clock.sleep(remaining - self.spin_threshold);
+        }
+
+        while clock.now() < deadline {
+            clock.spin();
+        }
+
+        let error = clock.now().saturating_duration_since(deadline);
+        self.record(error);
+
+        Some(error)
+    }
+
+    fn record(&mut self, error: Duration) {
+        self.total_error += error;
+
+        self.report.paced_frames += 1;
+        self.report.last_error = error;
+        self.report.max_error = self.report.max_error.max(error);
+        self.report.average_error = self.total_error / self.report.paced_frames as u32;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::window::ManualClock;
+
+    #[test]
+    fn target_frame_time_accepts_bare_milliseconds() {
+        assert_eq!(serde_json::from_str::<TargetFrameTime>("16").unwrap(), TargetFrameTime::Milliseconds(16.0));
+        assert_eq!(serde_json::from_str::<TargetFrameTime>(r#"{"Milliseconds": 8.5}"#).unwrap(), TargetFrameTime::Milliseconds(8.5));
+        assert_eq!(serde_json::from_str::<TargetFrameTime>(r#"{"Fps": 60}"#).unwrap(), TargetFrameTime::Fps(60.0));
+    }
+
+    // Sleeps longer than asked, like the system clock on most platforms.
+    #[derive(Clone)]
+    struct OversleepingClock {
+        clock: ManualClock,
+        oversleep: Duration,
+    }
+
+    impl Clock for OversleepingClock {
+        fn now(&self) -> Instant {
+            self.clock.now()
+        }
+
+        fn sleep(&self, duration: Duration) {
+            self.clock.advance(duration + self.oversleep);
+        }
+
+        fn spin(&self) {
+            self.clock.spin();
+        }
+    }
+
+    #[test]
+    fn wait_updates_the_report() {
+        let clock = ManualClock::new();
+        let mut pacer = FramePacer::new(TargetFrameTime::Milliseconds(10.0));
+
+        let frame_start = clock.now();
+        clock.advance(Duration::from_millis(3));
+        assert_eq!(pacer.wait(&clock, frame_start), Some(Duration::from_millis(0)));
+        assert_eq!(clock.now(), frame_start + Duration::from_millis(10));
+
+        let frame_start = clock.now();
+        clock.advance(Duration::from_millis(11));
+        assert_eq!(pacer.wait(&clock, frame_start), None);
+
+        let report = pacer.report();
+        assert_eq!(report.paced_frames, 1);
+        assert_eq!(report.missed_frames, 1);
+        assert_eq!(report.max_error, Duration::from_millis(0));
+    }
+
+    #[test]
+    fn spinning_absorbs_oversleeping_up_to_the_threshold() {
+        let clock = OversleepingClock {
+            clock: ManualClock::new(),
+            oversleep: Duration::from_millis(1),
+        };
+        let mut pacer = FramePacer::new(TargetFrameTime::Milliseconds(10.0));
+        let frame_start = clock.now();
+        assert_eq!(pacer.wait(&clock, frame_start), Some(Duration::from_millis(0)));
+
+        let clock = OversleepingClock {
+            oversleep: Duration::from_millis(3),
+            ..clock
+        };
+        let frame_start = clock.now();
+        assert_eq!(pacer.wait(&clock, frame_start), Some(Duration::from_millis(1)));
+
+        let report = pacer.report();
+        assert_eq!(report.paced_frames, 2);
+        assert_eq!(report.last_error, Duration::from_millis(1));
+        assert_eq!(report.max_error, Duration::from_millis(1));
+        assert_eq!(report.average_error, Duration::from_micros(500));
+    }
+}
@@ -0,0 +1,214 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use serde::Serialize;
+
+pub const DEFAULT_STATISTICS_WINDOW: usize = 120;
+
+pub struct FrameStatistics {
+    window_size: usize,
+    frame_times: VecDeque<Duration>,
+}
+
+#[derive(Serialize)]
+pub struct FrameTimeHistogram {
+    pub bucket_width_ms: f64,
+    pub buckets: Vec<HistogramBucket>,
+}
+
+#[derive(Serialize)]
+pub struct HistogramBucket {
+    pub start_ms: f64,
+    pub end_ms: f64,
+    pub count: usize,
+}
+
+impl FrameStatistics {
+    pub fn new(window_size: usize) -> Self {
+        let window_size = window_size.max(1);
+
+        Self {
+            window_size,
+            frame_times: VecDeque::with_capacity(window_size),
+        }
+    }
+
+    pub fn push(&mut self, frame_time: Duration) {
+        if self.frame_times.len() == self.window_size {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(frame_time);
+    }
+
+    pub fn clear(&mut self) {
+        self.frame_times.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.frame_times.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frame_times.is_empty()
+    }
+
+    pub fn window_size(&self) -> usize {
+        self.window_size
+    }
+
+    pub fn average(&self) -> Option<Duration> {
+        if self.frame_times.is_empty() {
+            return None;
+        }
+
+        let total: Duration = self.frame_times.iter().sum();
+        Some(total / self.frame_times.len() as u32)
+    }
+
+    pub fn min(&self) -> Option<Duration> {
+        self.frame_times.iter().min().copied()
+    }
+
+    pub fn max(&self) -> Option<Duration> {
+        self.frame_times.iter().max().copied()
+    }
+
+    pub fn fps(&self) -> Option<f64> {
+        let average = self.average()?;
+        if average.as_secs_f64() <= 0.0 {
+            return None;
+        }
+
+        Some(1.0 / average.as_secs_f64())
+    }
+
+    // Nearest-rank percentile, `percentile` is in the range 0..=100.
+    pub fn percentile(&self, percentile: f64) -> Option<Duration> {
+        if self.frame_times.is_empty() {
+            return None;
+        }
+
+        let mut sorted: Vec<Duration> = self.frame_times.iter().copied().collect();
+        sorted.sort();
+
+        let percentile = percentile.clamp(0.0, 100.0);
+        let rank = (percentile / 100.0 * sorted.len() as f64).ceil() as usize;
+        let index = rank.max(1) - 1;
+
+        Some(sorted[index])
+    }
+
+    pub fn p95(&self) -> Option<Duration> {
+        self.percentile(95.0)
+    }
+
+    pub fn p99(&self) -> Option<Duration> {
+        self.percentile(99.0)
+    }
+
+    pub fn histogram(&self, bucket_width: Duration) -> FrameTimeHistogram {
+        let bucket_width_ms = bucket_width.as_secs_f64() * 1000.0;
+        if bucket_width_ms <= 0.0 {
+            return FrameTimeHistogram {
+                bucket_width_ms,
+                buckets: vec![],
+            };
+        }
+
+        let mut counts: Vec<usize> = vec![];
+        for frame_time in self.frame_times.iter() {
+            let frame_time_ms = frame_time.as_secs_f64() * 1000.0;
+            let index = (frame_time_ms / bucket_width_ms).floor() as usize;
+            if index >= counts.len() {
+                counts.resize(index + 1, 0);
+            }
+            counts[index] += 1;
+        }
+
+        let buckets = counts
+            .into_iter()
+            .enumerate()
+            .map(|(index, count)| HistogramBucket {
+                start_ms: index as f64 * bucket_width_ms,
+                end_ms: (index + 1) as f64 * bucket_width_ms,
+                count,
+            })
+            .collect();
+
+        FrameTimeHistogram {
+            bucket_width_ms,
+            buckets,
+        }
+    }
+}
+
+impl Default for FrameStatistics {
+    fn default() -> Self {
+        Self::new(DEFAULT_STATISTICS_WINDOW)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn milliseconds(milliseconds: u64) -> Duration {
+        Duration::from_millis(milliseconds)
+    }
+
+    #[test]
+    fn empty_statistics_have_no_values() {
+        let statistics = FrameStatistics::new(4);
+        assert!(statistics.is_empty());
+        assert_eq!(statistics.average(), None);
+        assert_eq!(statistics.min(), None);
+        assert_eq!(statistics.max(), None);
+        assert_eq!(statistics.p95(), None);
+    }
+
+    #[test]
+    fn rolling_window_drops_the_oldest_frames() {
+        let mut statistics = FrameStatistics::new(3);
+        for frame_time in &[100, 10, 20, 30] {
+            statistics.push(milliseconds(*frame_time));
+        }
+
+        assert_eq!(statistics.len(), 3);
+        assert_eq!(statistics.average(), Some(milliseconds(20)));
+        assert_eq!(statistics.min(), Some(milliseconds(10)));
+        assert_eq!(statistics.max(), Some(milliseconds(30)));
+    }
+
+    #[test]
+    fn window_size_is_at_least_one() {
+        let mut statistics = FrameStatistics::new(0);
+        statistics.push(milliseconds(1));
+        statistics.push(milliseconds(2));
+        assert_eq!(statistics.window_size(), 1);
+        assert_eq!(statistics.max(), Some(milliseconds(2)));
+    }
+
+    #[test]
+    fn percentiles_use_the_nearest_rank() {
+        let mut statistics = FrameStatistics::new(100);
+        for frame_time in 1..=100 {
+            statistics.push(milliseconds(frame_time));
+        }
+
+        assert_eq!(statistics.percentile(0.0), Some(milliseconds(1)));
+        assert_eq!(statistics.percentile(50.0), Some(milliseconds(50)));
+        assert_eq!(statistics.p95(), Some(milliseconds(95)));
+        assert_eq!(statistics.p99(), Some(milliseconds(99)));
+    }
+
+    #[test]
+    fn histogram_buckets_frame_times() {
+        let mut statistics = FrameStatistics::new(10);
+        for frame_time in &[1, 4, 6, 12] {
+            statistics.push(milliseconds(*frame_time));
+        }
+
+        let counts: Vec<usize> = statistics.histogram(milliseconds(5)).buckets.iter().map(|bucket| bucket.count).collect();
+        assert_eq!(counts, vec![2, 1, 1]);
+    }
+}
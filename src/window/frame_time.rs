@@ -1,52 +1,137 @@
-use std::{
-    error::Error,
-    time::{Duration, SystemTime},
-};
+use std::time::{Duration, Instant};
 
-pub struct FrameTime {
-    frame_time: SystemTime,
+use super::{Clock, FramePacer, FrameStatistics, PacingReport, SystemClock, TargetFrameTime};
+
+pub struct FrameTime<C: Clock = SystemClock> {
+    clock: C,
+    frame_time: Instant,
     duration_since_last_frame: Duration,
-    target_frame_time: Option<u64>,
+    pacer: Option<FramePacer>,
+    statistics: FrameStatistics,
 }
 
 impl FrameTime {
-    pub fn new(target_frame_time: Option<u64>) -> Self {
+    pub fn new(target_frame_time: Option<TargetFrameTime>) -> Self {
+        Self::with_clock(SystemClock, target_frame_time)
+    }
+}
+
+impl<C: Clock> FrameTime<C> {
+    pub fn with_clock(clock: C, target_frame_time: Option<TargetFrameTime>) -> Self {
+        let frame_time = clock.now();
+
         Self {
-            frame_time: SystemTime::now(),
+            clock,
+            frame_time,
             duration_since_last_frame: Duration::from_millis(0),
-            target_frame_time,
+            pacer: target_frame_time.map(FramePacer::new),
+            statistics: FrameStatistics::default(),
         }
     }
 
-    pub fn update(&mut self) -> Result<u128, Box<dyn Error>> {
-        let now = SystemTime::now();
+    pub fn with_statistics_window(mut self, window_size: usize) -> Self {
+        self.statistics = FrameStatistics::new(window_size);
+        self
+    }
+
+    pub fn update(&mut self) -> Duration {
+        let now = self.clock.now();
 
-        self.duration_since_last_frame = now.duration_since(self.frame_time)?;
+        self.duration_since_last_frame = now.saturating_duration_since(self.frame_time);
 
         self.frame_time = now;
 
-        Ok(self.duration_since_last_frame.as_millis())
-    }
-
-    pub fn calc_sleep_duration(&self) -> Option<Duration> {
-        if let Some(target_frame_time) = self.target_frame_time {
-            let now = SystemTime::now();
-
-            let duration_since_last_frame = now
-                .duration_since(self.frame_time)
-                .expect("What is time?");
-    
-            let target_duration = Duration::from_millis(target_frame_time);
-            if duration_since_last_frame > target_duration {
-                return None;
-            }
-    
-            let duration_to_sleep = target_duration - duration_since_last_frame;
-            if duration_to_sleep.as_millis() > 0 {
-                return Some(duration_to_sleep);
-            }
+        self.statistics.push(self.duration_since_last_frame);
+
+        self.duration_since_last_frame
+    }
+
+    pub fn delta(&self) -> Duration {
+        self.duration_since_last_frame
+    }
+
+    pub fn statistics(&self) -> &FrameStatistics {
+        &self.statistics
+    }
+
+    pub fn pacer_mut(&mut self) -> Option<&mut FramePacer> {
+        self.pacer.as_mut()
+    }
+
+    pub fn pacing_report(&self) -> Option<&PacingReport> {
+        self.pacer.as_ref().map(|pacer| pacer.report())
+    }
+
+    // When the next frame is due, `None` without a target frame time or when
+    // it is due already. For event loops that wait instead of blocking.
+    pub fn next_frame_deadline(&self) -> Option<Instant> {
+        let deadline = self.pacer.as_ref()?.deadline(self.frame_time);
+        match self.clock.now() < deadline {
+            true => Some(deadline),
+            false => None,
         }
-        
-        None
+    }
+
+    pub fn wait_for_next_frame(&mut self) -> Option<Duration> {
+        let frame_time = self.frame_time;
+        let clock = &self.clock;
+
+        self.pacer.as_mut()?.wait(clock, frame_time)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::window::ManualClock;
+
+    #[test]
+    fn delta_is_the_time_between_updates() {
+        let clock = ManualClock::new();
+        let mut frame_time = FrameTime::with_clock(clock.clone(), None);
+
+        clock.advance(Duration::from_millis(16));
+        assert_eq!(frame_time.update(), Duration::from_millis(16));
+        assert_eq!(frame_time.delta(), Duration::from_millis(16));
+
+        clock.advance(Duration::from_millis(20));
+        assert_eq!(frame_time.update(), Duration::from_millis(20));
+        assert_eq!(frame_time.update(), Duration::from_millis(0));
+    }
+
+    #[test]
+    fn statistics_track_average_min_and_max() {
+        let clock = ManualClock::new();
+        let mut frame_time = FrameTime::with_clock(clock.clone(), None);
+
+        for milliseconds in &[10, 20, 30] {
+            clock.advance(Duration::from_millis(*milliseconds));
+            frame_time.update();
+        }
+
+        let statistics = frame_time.statistics();
+        assert_eq!(statistics.len(), 3);
+        assert_eq!(statistics.average(), Some(Duration::from_millis(20)));
+        assert_eq!(statistics.min(), Some(Duration::from_millis(10)));
+        assert_eq!(statistics.max(), Some(Duration::from_millis(30)));
+        assert_eq!(statistics.fps(), Some(50.0));
+    }
+
+    #[test]
+    fn pacing_waits_until_the_target_frame_time() {
+        let clock = ManualClock::new();
+        let mut frame_time = FrameTime::with_clock(clock.clone(), Some(TargetFrameTime::Milliseconds(10.0)));
+        let start = clock.now();
+
+        clock.advance(Duration::from_millis(4));
+        assert_eq!(frame_time.next_frame_deadline(), Some(start + Duration::from_millis(10)));
+        assert_eq!(frame_time.wait_for_next_frame(), Some(Duration::from_millis(0)));
+        assert_eq!(clock.now(), start + Duration::from_millis(10));
+        assert_eq!(frame_time.next_frame_deadline(), None);
+
+        frame_time.update();
+        clock.advance(Duration::from_millis(12));
+        assert_eq!(frame_time.wait_for_next_frame(), None);
+        assert_eq!(frame_time.pacing_report().unwrap().missed_frames, 1);
     }
 }
@@ -1,10 +1,17 @@
+mod clock;
 mod config;
 mod window;
 mod windowmodes;
+mod frame_pacer;
+mod frame_statistics;
 mod frame_time;
 
 pub use {
+    clock::*,
     config::*,
     window::*,
     windowmodes::*,
+    frame_pacer::*,
+    frame_statistics::*,
+    frame_time::*,
 };
\ No newline at end of file
@@ -9,11 +9,12 @@ use winit::monitor::MonitorHandle;
 use winit::platform::run_return::EventLoopExtRunReturn;
 use winit::window::{Fullscreen, WindowBuilder};
 
-use crate::window::{WindowConfiguration, WindowModes};
+use crate::window::{FrameTime, TargetFrameTime, WindowConfiguration, WindowModes};
 
 pub struct Window {
     handle: winit::window::Window,
     event_loop: EventLoop<()>,
+    target_frame_time: Option<TargetFrameTime>,
     pub ecs: EntityComponentSystem,
 }
 
@@ -87,6 +88,7 @@ impl Window {
         Ok(Self { 
             event_loop, 
             handle,
+            target_frame_time: config.target_frame_time,
             ecs,
         })
     }
@@ -102,8 +104,7 @@ impl Window {
     pub fn run(self) {
         let window = self.handle;
         let mut ecs = self.ecs;
-
-        // let mut frame_time = FrameTime::new(self.target_frame_time);
+        let mut frame_time = FrameTime::new(self.target_frame_time);
 
         self.event_loop.run(move |event, _, control_flow| {
             if let Event::WindowEvent { ref event, .. } = event {
@@ -139,18 +140,15 @@ impl Window {
             }
 
             if let Event::RedrawRequested(_) = event {
-                // let frame_ms = frame_time
-                //     .update()
-                //     .expect("Failed to update frame time");
-
-                // frame(frame_ms);
-
-                // if let Some(sleep_duration) = frame_time.calc_sleep_duration() {
-                //     sleep(sleep_duration);
-                // }
+                frame_time.update();
             }
 
+            // Events that arrived in the meantime are handled before the
+            // wait, the pacer then sleeps and spins until the target frame
+            // time so the frame starts on time.
             if let Event::MainEventsCleared = event {
+                frame_time.wait_for_next_frame();
+                *control_flow = ControlFlow::Poll;
                 window.request_redraw();
             }
         });
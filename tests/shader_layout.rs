@@ -0,0 +1,61 @@
+use webgpu::render::ShaderType;
+
+#[repr(C)]
+#[derive(ShaderType)]
+struct Light {
+    position: [f32; 3],
+    #[padding]
+    _padding: u32,
+    color: [f32; 3],
+    intensity: f32,
+}
+
+#[repr(C)]
+#[derive(ShaderType)]
+struct Material {
+    roughness: f32,
+    metallic: f32,
+    #[padding]
+    _padding: [u32; 2],
+    light: Light,
+    normal_matrix: [[f32; 4]; 3],
+}
+
+#[repr(C)]
+#[derive(ShaderType)]
+#[shader_layout(storage)]
+struct Particle {
+    position: [f32; 2],
+    velocity: [f32; 2],
+    age: f32,
+    #[padding]
+    _padding: u32,
+}
+
+#[repr(C, align(16))]
+#[derive(ShaderType)]
+struct Aligned {
+    value: [f32; 4],
+}
+
+#[test]
+fn alignment_is_the_largest_field_alignment() {
+    assert_eq!(Light::ALIGN, 16);
+    assert_eq!(Particle::ALIGN, 8);
+    assert_eq!(Aligned::ALIGN, 16);
+}
+
+#[test]
+fn size_is_the_struct_size() {
+    assert_eq!(Light::SIZE, 32);
+    assert_eq!(Material::SIZE, 96);
+    assert_eq!(Particle::SIZE, 24);
+    assert_eq!(Aligned::SIZE, 16);
+}
+
+#[test]
+fn uniform_alignment_is_rounded_to_16() {
+    assert_eq!(Light::UNIFORM_ALIGN, 16);
+    assert_eq!(Material::UNIFORM_ALIGN, 16);
+    assert_eq!(Particle::UNIFORM_ALIGN, 16);
+}
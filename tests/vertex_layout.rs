@@ -0,0 +1,39 @@
+use webgpu::render::VertexLayout;
+
+#[repr(C)]
+#[derive(VertexLayout)]
+#[step_mode(Instance)]
+struct InstanceRaw {
+    position: [f32; 3],
+    #[location(5)]
+    model_matrix: [[f32; 4]; 4],
+    #[format(Unorm8x4)]
+    color: [u8; 4],
+    layer: u32,
+}
+
+#[test]
+fn matrices_take_one_attribute_per_column() {
+    let attributes: Vec<(u64, u32, wgpu::VertexFormat)> = InstanceRaw::ATTRIBUTES
+        .iter()
+        .map(|attribute| (attribute.offset, attribute.shader_location, attribute.format))
+        .collect();
+
+    assert_eq!(attributes, vec![
+        (0, 0, wgpu::VertexFormat::Float32x3),
+        (12, 5, wgpu::VertexFormat::Float32x4),
+        (28, 6, wgpu::VertexFormat::Float32x4),
+        (44, 7, wgpu::VertexFormat::Float32x4),
+        (60, 8, wgpu::VertexFormat::Float32x4),
+        (76, 9, wgpu::VertexFormat::Unorm8x4),
+        (80, 10, wgpu::VertexFormat::Uint32),
+    ]);
+}
+
+#[test]
+fn buffer_layout_uses_the_struct_size() {
+    let layout = InstanceRaw::buffer_layout();
+    assert_eq!(layout.array_stride, 84);
+    assert_eq!(layout.step_mode, wgpu::InputStepMode::Instance);
+    assert_eq!(InstanceRaw::next_location(), 11);
+}
@@ -0,0 +1,377 @@
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{quote, quote_spanned};
+use syn::{
+    parse_macro_input, spanned::Spanned, Attribute, Data, DeriveInput, Error, Expr, Field, Fields, Ident, Lit,
+    LitInt, LitStr, Meta, NestedMeta, Type,
+};
+
+// Generates `webgpu::render::VertexLayout` for a `#[repr(C)]` struct. Every
+// field becomes one vertex attribute, except matrices (arrays of arrays)
+// which are split into one attribute per column, the inner arrays.
+//
+//     #[derive(VertexLayout)]
+//     #[step_mode(Instance)]
+//     struct InstanceRaw {
+//         #[location(3)]
+//         model_matrix: [[f32; 4]; 4],
+//         #[format(Unorm8x4)]
+//         color: [u8; 4],
+//     }
+#[proc_macro_derive(VertexLayout, attributes(location, step_mode, format))]
+pub fn derive_vertex_layout(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    match expand_vertex_layout(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+fn expand_vertex_layout(input: &DeriveInput) -> Result<TokenStream2, Error> {
+    let name = &input.ident;
+
+    if !has_repr_c(&input.attrs) {
+        return Err(Error::new(name.span(), "VertexLayout requires #[repr(C)] to have a predictable field layout"));
+    }
+
+    if !input.generics.params.is_empty() {
+        return Err(Error::new(input.generics.span(), "VertexLayout can not be derived for generic structs"));
+    }
+
+    let fields = struct_fields(input, "VertexLayout")?;
+
+    let step_mode = step_mode(&input.attrs)?;
+
+    let mut attributes = vec![];
+    let mut field_sizes = vec![];
+    let mut location = 0u32;
+
+    for field in fields {
+        if let Some(explicit_location) = location_attribute(&field.attrs)? {
+            location = explicit_location;
+        }
+
+        let field_type = &field.ty;
+        let offset = quote! { 0 #(+ ::std::mem::size_of::<#field_sizes>())* };
+
+        let (format, columns) = match format_attribute(&field.attrs)? {
+            Some(format) => (quote! { ::wgpu::VertexFormat::#format }, 1),
+            None => vertex_format(field_type)?,
+        };
+
+        for column in 0..columns {
+            let column_offset = match columns {
+                1 => quote! { 0 },
+                _ => {
+                    let column_type = column_type(field_type);
+                    quote! { #column as usize * ::std::mem::size_of::<#column_type>() }
+                },
+            };
+
+            attributes.push(quote_spanned! { field.span() =>
+                ::wgpu::VertexAttribute {
+                    offset: (#offset + #column_offset) as ::wgpu::BufferAddress,
+                    shader_location: #location,
+                    format: #format,
+                }
+            });
+            location += 1;
+        }
+
+        field_sizes.push(field_type.clone());
+    }
+
+    // The trailing constant fails to compile when the compiler inserted
+    // padding between the fields, in which case the offsets would be wrong.
+    let padding_message = LitStr::new(&format!("VertexLayout: `{}` has padding between fields", name), name.span());
+    Ok(quote! {
+        impl ::webgpu::render::VertexLayout for #name {
+            const STEP_MODE: ::wgpu::InputStepMode = ::wgpu::InputStepMode::#step_mode;
+            const ATTRIBUTES: &'static [::wgpu::VertexAttribute] = &[
+                #(#attributes,)*
+            ];
+        }
+
+        const _: () = assert!(::std::mem::size_of::<#name>() == 0 #(+ ::std::mem::size_of::<#field_sizes>())*, #padding_message);
+    })
+}
+
+// Implements `webgpu::render::ShaderType` for a `#[repr(C)]` struct and checks
+// at compile time that its fields are where WGSL expects them. Fields marked
+// `#[padding]` do not exist on the WGSL side. The checked address space is
+// picked with `#[shader_layout(uniform)]` (the default) or
+// `#[shader_layout(storage)]`.
+//
+//     #[derive(ShaderType)]
+//     struct Light {
+//         position: [f32; 3],
+//         #[padding]
+//         _padding: u32,
+//         color: [f32; 3],
+//         #[padding]
+//         _color_padding: u32,
+//     }
+#[proc_macro_derive(ShaderType, attributes(shader_layout, padding))]
+pub fn derive_shader_type(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    match expand_shader_type(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+fn expand_shader_type(input: &DeriveInput) -> Result<TokenStream2, Error> {
+    let name = &input.ident;
+
+    if !has_repr_c(&input.attrs) {
+        return Err(Error::new(name.span(), "ShaderType requires #[repr(C)] to have a predictable field layout"));
+    }
+
+    if !input.generics.params.is_empty() {
+        return Err(Error::new(input.generics.span(), "ShaderType can not be derived for generic structs"));
+    }
+
+    let fields = struct_fields(input, "ShaderType")?;
+    let (address_space, align_const) = shader_layout(&input.attrs)?;
+
+    let shader_fields = fields.iter().filter(|field| !is_padding(field)).map(|field| &field.ty);
+
+    let mut align = quote! { 1 };
+    let mut uniform_align = quote! { 1 };
+    for field_type in shader_fields {
+        align = quote! { ::webgpu::render::max_align(#align, <#field_type as ::webgpu::render::ShaderType>::ALIGN) };
+        uniform_align = quote! {
+            ::webgpu::render::max_align(#uniform_align, <#field_type as ::webgpu::render::ShaderType>::UNIFORM_ALIGN)
+        };
+    }
+
+    let mut checks = vec![];
+    for (index, field) in fields.iter().enumerate() {
+        let field_type = &field.ty;
+        let field_name = match &field.ident {
+            Some(ident) => ident.to_string(),
+            None => index.to_string(),
+        };
+
+        if is_padding(field) {
+            checks.push(quote! {
+                rust_end = align_to(rust_end, ::std::mem::align_of::<#field_type>() as u64)
+                    + ::std::mem::size_of::<#field_type>() as u64;
+            });
+            continue;
+        }
+
+        let missing_padding = LitStr::new(
+            &format!("field `{}` of `{}` is not aligned as WGSL requires in {} buffers, add padding before it", field_name, name, address_space),
+            field.span(),
+        );
+        let extra_padding = LitStr::new(
+            &format!("field `{}` of `{}` has more padding before it than WGSL expects in {} buffers", field_name, name, address_space),
+            field.span(),
+        );
+        let size_mismatch = LitStr::new(
+            &format!("field `{}` of `{}` does not have the same size as its WGSL type", field_name, name),
+            field.span(),
+        );
+
+        checks.push(quote_spanned! { field.span() =>
+            let rust_offset = align_to(rust_end, ::std::mem::align_of::<#field_type>() as u64);
+            let wgsl_offset = align_to(wgsl_end, <#field_type as ShaderType>::#align_const);
+            if rust_offset < wgsl_offset {
+                panic!(#missing_padding);
+            }
+            if rust_offset > wgsl_offset {
+                panic!(#extra_padding);
+            }
+            if ::std::mem::size_of::<#field_type>() as u64 != <#field_type as ShaderType>::SIZE {
+                panic!(#size_mismatch);
+            }
+            rust_end = rust_offset + ::std::mem::size_of::<#field_type>() as u64;
+            wgsl_end = wgsl_offset + <#field_type as ShaderType>::SIZE;
+        });
+    }
+
+    let missing_trailing_padding = LitStr::new(
+        &format!("`{}` is smaller than its WGSL layout in {} buffers, add padding at the end", name, address_space),
+        name.span(),
+    );
+    let extra_trailing_padding = LitStr::new(
+        &format!("`{}` is larger than its WGSL layout in {} buffers", name, address_space),
+        name.span(),
+    );
+
+    Ok(quote! {
+        impl ::webgpu::render::ShaderType for #name {
+            const ALIGN: u64 = #align;
+            const SIZE: u64 = ::std::mem::size_of::<#name>() as u64;
+            const UNIFORM_ALIGN: u64 = ::webgpu::render::buffer::align_to(#uniform_align, 16);
+        }
+
+        #[allow(unused_assignments)]
+        const _: () = {
+            use ::webgpu::render::{buffer::align_to, ShaderType};
+
+            let mut rust_end: u64 = 0;
+            let mut wgsl_end: u64 = 0;
+            #(#checks)*
+
+            let wgsl_size = align_to(wgsl_end, <#name as ShaderType>::#align_const);
+            if (::std::mem::size_of::<#name>() as u64) < wgsl_size {
+                panic!(#missing_trailing_padding);
+            }
+            if (::std::mem::size_of::<#name>() as u64) > wgsl_size {
+                panic!(#extra_trailing_padding);
+            }
+        };
+    })
+}
+
+fn struct_fields<'a>(input: &'a DeriveInput, derive: &str) -> Result<Vec<&'a Field>, Error> {
+    match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => Ok(fields.named.iter().collect()),
+            Fields::Unnamed(fields) => Ok(fields.unnamed.iter().collect()),
+            Fields::Unit => Ok(vec![]),
+        },
+        _ => Err(Error::new(input.ident.span(), format!("{} can only be derived for structs", derive))),
+    }
+}
+
+fn shader_layout(attrs: &[Attribute]) -> Result<(&'static str, Ident), Error> {
+    let layout = match attrs.iter().find(|attr| attr.path.is_ident("shader_layout")) {
+        Some(attr) => attr.parse_args::<Ident>()?,
+        None => return Ok(("uniform", Ident::new("UNIFORM_ALIGN", Span::call_site()))),
+    };
+
+    match layout.to_string().as_str() {
+        "uniform" => Ok(("uniform", Ident::new("UNIFORM_ALIGN", Span::call_site()))),
+        "storage" => Ok(("storage", Ident::new("ALIGN", Span::call_site()))),
+        _ => Err(Error::new(layout.span(), "expected #[shader_layout(uniform)] or #[shader_layout(storage)]")),
+    }
+}
+
+fn is_padding(field: &Field) -> bool {
+    field.attrs.iter().any(|attr| attr.path.is_ident("padding"))
+}
+
+// Looks for `C` among the representation hints, `#[repr(C, align(16))]`
+// counts but `#[repr(align(16))]` does not.
+fn has_repr_c(attrs: &[Attribute]) -> bool {
+    attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("repr"))
+        .filter_map(|attr| match attr.parse_meta() {
+            Ok(Meta::List(list)) => Some(list),
+            _ => None,
+        })
+        .any(|list| {
+            list.nested
+                .iter()
+                .any(|nested| matches!(nested, NestedMeta::Meta(Meta::Path(path)) if path.is_ident("C")))
+        })
+}
+
+fn step_mode(attrs: &[Attribute]) -> Result<Ident, Error> {
+    match attrs.iter().find(|attr| attr.path.is_ident("step_mode")) {
+        Some(attr) => {
+            let ident = attr.parse_args::<Ident>()?;
+            match ident.to_string().as_str() {
+                "Vertex" | "Instance" => Ok(ident),
+                _ => Err(Error::new(ident.span(), "expected #[step_mode(Vertex)] or #[step_mode(Instance)]")),
+            }
+        },
+        None => Ok(Ident::new("Vertex", Span::call_site())),
+    }
+}
+
+fn location_attribute(attrs: &[Attribute]) -> Result<Option<u32>, Error> {
+    match attrs.iter().find(|attr| attr.path.is_ident("location")) {
+        Some(attr) => Ok(Some(attr.parse_args::<LitInt>()?.base10_parse()?)),
+        None => Ok(None),
+    }
+}
+
+fn format_attribute(attrs: &[Attribute]) -> Result<Option<Ident>, Error> {
+    match attrs.iter().find(|attr| attr.path.is_ident("format")) {
+        Some(attr) => Ok(Some(attr.parse_args::<Ident>()?)),
+        None => Ok(None),
+    }
+}
+
+// The vertex format of a field and the number of attributes it takes up.
+fn vertex_format(field_type: &Type) -> Result<(TokenStream2, u32), Error> {
+    if let Type::Array(array) = field_type {
+        if let Type::Array(inner) = &*array.elem {
+            let columns = array_len(&array.len)?;
+            let format = vector_format(&inner.elem, array_len(&inner.len)?, field_type)?;
+            return Ok((format, columns));
+        }
+
+        let format = vector_format(&array.elem, array_len(&array.len)?, field_type)?;
+        return Ok((format, 1));
+    }
+
+    Ok((vector_format(field_type, 1, field_type)?, 1))
+}
+
+fn vector_format(scalar: &Type, components: u32, field_type: &Type) -> Result<TokenStream2, Error> {
+    let scalar_name = match scalar {
+        Type::Path(path) => path.path.get_ident().map(|ident| ident.to_string()),
+        _ => None,
+    };
+
+    let format = match (scalar_name.as_deref(), components) {
+        (Some("f32"), 1) => "Float32",
+        (Some("f32"), 2) => "Float32x2",
+        (Some("f32"), 3) => "Float32x3",
+        (Some("f32"), 4) => "Float32x4",
+        (Some("f64"), 1) => "Float64",
+        (Some("f64"), 2) => "Float64x2",
+        (Some("f64"), 3) => "Float64x3",
+        (Some("f64"), 4) => "Float64x4",
+        (Some("u32"), 1) => "Uint32",
+        (Some("u32"), 2) => "Uint32x2",
+        (Some("u32"), 3) => "Uint32x3",
+        (Some("u32"), 4) => "Uint32x4",
+        (Some("i32"), 1) => "Sint32",
+        (Some("i32"), 2) => "Sint32x2",
+        (Some("i32"), 3) => "Sint32x3",
+        (Some("i32"), 4) => "Sint32x4",
+        (Some("u16"), 2) => "Uint16x2",
+        (Some("u16"), 4) => "Uint16x4",
+        (Some("i16"), 2) => "Sint16x2",
+        (Some("i16"), 4) => "Sint16x4",
+        (Some("u8"), 2) => "Uint8x2",
+        (Some("u8"), 4) => "Uint8x4",
+        (Some("i8"), 2) => "Sint8x2",
+        (Some("i8"), 4) => "Sint8x4",
+        _ => {
+            return Err(Error::new(
+                field_type.span(),
+                "unsupported vertex attribute type, use #[format(...)] to specify the vertex format",
+            ))
+        },
+    };
+
+    let format = Ident::new(format, Span::call_site());
+    Ok(quote! { ::wgpu::VertexFormat::#format })
+}
+
+fn column_type(field_type: &Type) -> Type {
+    match field_type {
+        Type::Array(array) => (*array.elem).clone(),
+        _ => field_type.clone(),
+    }
+}
+
+fn array_len(len: &Expr) -> Result<u32, Error> {
+    match len {
+        Expr::Lit(expr) => match &expr.lit {
+            Lit::Int(int) => int.base10_parse(),
+            _ => Err(Error::new(len.span(), "expected an integer array length")),
+        },
+        _ => Err(Error::new(len.span(), "array lengths of vertex attributes have to be integer literals")),
+    }
+}